@@ -0,0 +1,47 @@
+import Fusion from "fusion-wasm";
+
+const adjectives = ["pretty", "large", "big", "small", "tall", "short", "long", "handsome", "plain", "quaint", "clean", "elegant", "easy", "angry", "crazy", "helpful", "mushy", "odd", "unsightly", "adorable", "important", "inexpensive", "cheap", "expensive", "fancy"];
+const colours = ["red", "yellow", "blue", "green", "pink", "brown", "purple", "brown", "white", "black", "orange"];
+const nouns = ["table", "chair", "house", "bbq", "desk", "car", "pony", "cookie", "sandwich", "burger", "pizza", "mouse", "keyboard"];
+
+const pick = (words) => words[Math.floor(Math.random() * words.length)];
+
+let nextId = 1;
+
+export const buildRows = (count) => {
+  const rows = new Array(count);
+
+  for (let i = 0; i < count; i++) {
+    rows[i] = { id: nextId++, label: `${pick(adjectives)} ${pick(colours)} ${pick(nouns)}` };
+  }
+
+  return rows;
+};
+
+// Set by the table on every render, so scenarios can drive it
+export const table = { setRows: null, rows: [] };
+
+function Row({ row }) {
+  return (
+    <tr>
+      <td className="id">{String(row.id)}</td>
+      <td className="label"><a>{row.label}</a></td>
+      <td className="remove"><a>x</a></td>
+    </tr>
+  );
+}
+
+export default function Table() {
+  const [rows, setRows] = Fusion.useState([]);
+
+  table.rows = rows;
+  table.setRows = setRows;
+
+  return (
+    <table>
+      <tbody>
+        {rows.map(row => <Row row={row} />)}
+      </tbody>
+    </table>
+  );
+}
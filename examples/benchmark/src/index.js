@@ -0,0 +1,106 @@
+import Fusion from "fusion-wasm";
+import Table, { buildRows, table } from "./Table.js";
+
+const ITERATIONS = 5;
+
+const setRows = (rows) => Fusion.flushSync(() => table.setRows(rows));
+
+// js-framework-benchmark style scenarios: `setup` brings the table to its
+// starting point, `run` is the measured update
+const scenarios = [
+  {
+    name: "create 1,000 rows",
+    setup: () => setRows([]),
+    run: () => setRows(buildRows(1000)),
+  },
+  {
+    name: "create 10,000 rows",
+    setup: () => setRows([]),
+    run: () => setRows(buildRows(10000)),
+  },
+  {
+    name: "replace 1,000 rows",
+    setup: () => setRows(buildRows(1000)),
+    run: () => setRows(buildRows(1000)),
+  },
+  {
+    name: "append 1,000 rows to 1,000",
+    setup: () => setRows(buildRows(1000)),
+    run: () => setRows(table.rows.concat(buildRows(1000))),
+  },
+  {
+    name: "update every 10th row of 1,000",
+    setup: () => setRows(buildRows(1000)),
+    run: () => setRows(table.rows.map((row, i) => (i % 10 === 0 ? { ...row, label: row.label + " !!!" } : row))),
+  },
+  {
+    name: "swap rows 2 and 999",
+    setup: () => setRows(buildRows(1000)),
+    run: () => {
+      const rows = table.rows.slice();
+      [rows[1], rows[998]] = [rows[998], rows[1]];
+      setRows(rows);
+    },
+  },
+  {
+    name: "remove row 500 of 1,000",
+    setup: () => setRows(buildRows(1000)),
+    run: () => setRows(table.rows.filter((_, i) => i !== 499)),
+  },
+  {
+    name: "clear 1,000 rows",
+    setup: () => setRows(buildRows(1000)),
+    run: () => setRows([]),
+  },
+];
+
+const median = (values) => {
+  const sorted = values.slice().sort((a, b) => a - b);
+  return sorted[Math.floor(sorted.length / 2)];
+};
+
+// Each scenario runs ITERATIONS times; the render and commit of every run
+// is recorded as a `fusion:<scenario>` performance measure
+const runScenarios = () => {
+  const results = [];
+
+  for (const scenario of scenarios) {
+    const durations = [];
+
+    for (let i = 0; i < ITERATIONS; i++) {
+      scenario.setup();
+
+      const start = performance.now();
+      scenario.run();
+      const end = performance.now();
+
+      performance.measure(`fusion:${scenario.name}`, { start, end });
+      durations.push(end - start);
+    }
+
+    results.push({
+      scenario: scenario.name,
+      median: Number(median(durations).toFixed(2)),
+      min: Number(Math.min(...durations).toFixed(2)),
+      max: Number(Math.max(...durations).toFixed(2)),
+    });
+  }
+
+  setRows([]);
+  return results;
+};
+
+Fusion.load().then(() => {
+  Fusion.render(<Table />, document.getElementById("root"));
+  Fusion.flushSync();
+
+  document.getElementById("run").addEventListener("click", () => {
+    const results = runScenarios();
+
+    console.table(results);
+    window.benchmarkResults = results;
+    document.getElementById("results").textContent = results
+      .map(({ scenario, median, min, max }) => `${scenario}: ${median} ms (${min}-${max})`)
+      .join("\n");
+  });
+});
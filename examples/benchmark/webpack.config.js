@@ -0,0 +1,32 @@
+const path = require("path");
+const CopyPlugin = require("copy-webpack-plugin");
+
+const dist = path.resolve(__dirname, "dist");
+
+module.exports = {
+  mode: "production",
+  entry: {
+    index: "./src/index.js"
+  },
+  module: {
+    rules: [
+      {
+        test: /\.js$/,
+        use: 'babel-loader',
+        exclude: '/node_modules',
+      },
+    ],
+  },
+  output: {
+    path: dist,
+    filename: "[name].js"
+  },
+  devServer: {
+    contentBase: dist,
+  },
+  plugins: [
+    new CopyPlugin([
+      path.resolve(__dirname, "static")
+    ]),
+  ]
+};
@@ -19,6 +19,10 @@ export default function TodoTextInput({ value, onSave, newTodo, editing, placeho
     }
   };
 
+  let handleInput = (e) => {
+    setText(e.target.value);
+  };
+
   let handleBlur = (e) => {
     let newValue = e.target.value.trim();
     onSave(newValue);
@@ -37,6 +41,7 @@ export default function TodoTextInput({ value, onSave, newTodo, editing, placeho
       placeholder={placeholder}
       autoFocus={true}
       value={text}
+      onInput={handleInput}
       onBlur={handleBlur}
       onKeyDown={handleSubmit}
     />
@@ -1,31 +1,362 @@
 let context;
-let workLoop;
 
 export default {
+  // Element type of a canvas handing its rendering context to a `draw` prop
+  Surface: "__SURFACE",
+
   render() {
     throw new Error("Reactron: 'render' used before loading wasm module");
   },
 
+  renderMany() {
+    throw new Error("Reactron: 'renderMany' used before loading wasm module");
+  },
+
   useState() {
     throw new Error("Reactron: 'useState' used before loading wasm module");
   },
 
+  useEffect() {
+    throw new Error("Reactron: 'useEffect' used before loading wasm module");
+  },
+
+  useEventCallback() {
+    throw new Error("Reactron: 'useEventCallback' used before loading wasm module");
+  },
+
+  useAnnouncer() {
+    throw new Error("Reactron: 'useAnnouncer' used before loading wasm module");
+  },
+
+  useFullscreen() {
+    throw new Error("Reactron: 'useFullscreen' used before loading wasm module");
+  },
+
+  usePictureInPicture() {
+    throw new Error("Reactron: 'usePictureInPicture' used before loading wasm module");
+  },
+
+  useWakeLock() {
+    throw new Error("Reactron: 'useWakeLock' used before loading wasm module");
+  },
+
+  useIdle() {
+    throw new Error("Reactron: 'useIdle' used before loading wasm module");
+  },
+
+  useInstance() {
+    throw new Error("Reactron: 'useInstance' used before loading wasm module");
+  },
+
+  useImperativeHandle() {
+    throw new Error("Reactron: 'useImperativeHandle' used before loading wasm module");
+  },
+
+  sendCommand() {
+    throw new Error("Reactron: 'sendCommand' used before loading wasm module");
+  },
+
+  useBroadcastChannel() {
+    throw new Error("Reactron: 'useBroadcastChannel' used before loading wasm module");
+  },
+
+  useUndoableState() {
+    throw new Error("Reactron: 'useUndoableState' used before loading wasm module");
+  },
+
+  useContainerQuery() {
+    throw new Error("Reactron: 'useContainerQuery' used before loading wasm module");
+  },
+
+  useHotkey() {
+    throw new Error("Reactron: 'useHotkey' used before loading wasm module");
+  },
+
+  usePrefetch() {
+    throw new Error("Reactron: 'usePrefetch' used before loading wasm module");
+  },
+
+  useWorker() {
+    throw new Error("Reactron: 'useWorker' used before loading wasm module");
+  },
+
+  useDirection() {
+    throw new Error("Reactron: 'useDirection' used before loading wasm module");
+  },
+
+  useBlocker() {
+    throw new Error("Reactron: 'useBlocker' used before loading wasm module");
+  },
+
+  confirmNavigation() {
+    throw new Error("Reactron: 'confirmNavigation' used before loading wasm module");
+  },
+
+  focusAfterNavigation() {
+    throw new Error("Reactron: 'focusAfterNavigation' used before loading wasm module");
+  },
+
+  registerIsland() {
+    throw new Error("Reactron: 'registerIsland' used before loading wasm module");
+  },
+
+  prerender() {
+    throw new Error("Reactron: 'prerender' used before loading wasm module");
+  },
+
+  defineCustomElement() {
+    throw new Error("Reactron: 'defineCustomElement' used before loading wasm module");
+  },
+
+  css() {
+    throw new Error("Reactron: 'css' used before loading wasm module");
+  },
+
+  Direction() {
+    throw new Error("Reactron: 'Direction' used before loading wasm module");
+  },
+
+  LazyMount() {
+    throw new Error("Reactron: 'LazyMount' used before loading wasm module");
+  },
+
+  createPortal() {
+    throw new Error("Reactron: 'createPortal' used before loading wasm module");
+  },
+
   createElement() {
     throw new Error("Reactron: 'createElement' used before loading wasm module");
   },
 
-  load() {
+  setErrorHandler() {
+    throw new Error("Reactron: 'setErrorHandler' used before loading wasm module");
+  },
+
+  debugTree() {
+    throw new Error("Reactron: 'debugTree' used before loading wasm module");
+  },
+
+  fiberIdOfNode() {
+    throw new Error("Reactron: 'fiberIdOfNode' used before loading wasm module");
+  },
+
+  describeFiber() {
+    throw new Error("Reactron: 'describeFiber' used before loading wasm module");
+  },
+
+  isFiberWithin() {
+    throw new Error("Reactron: 'isFiberWithin' used before loading wasm module");
+  },
+
+  validateElement() {
+    throw new Error("Reactron: 'validateElement' used before loading wasm module");
+  },
+
+  invalidateSubtree() {
+    throw new Error("Reactron: 'invalidateSubtree' used before loading wasm module");
+  },
+
+  getDomNode() {
+    throw new Error("Reactron: 'getDomNode' used before loading wasm module");
+  },
+
+  inspectHooks() {
+    throw new Error("Reactron: 'inspectHooks' used before loading wasm module");
+  },
+
+  setUrlPolicy() {
+    throw new Error("Reactron: 'setUrlPolicy' used before loading wasm module");
+  },
+
+  setDevMode() {
+    throw new Error("Reactron: 'setDevMode' used before loading wasm module");
+  },
+
+  batchedUpdates() {
+    throw new Error("Reactron: 'batchedUpdates' used before loading wasm module");
+  },
+
+  startTransition() {
+    throw new Error("Reactron: 'startTransition' used before loading wasm module");
+  },
+
+  runWithPriority() {
+    throw new Error("Reactron: 'runWithPriority' used before loading wasm module");
+  },
+
+  flushSync() {
+    throw new Error("Reactron: 'flushSync' used before loading wasm module");
+  },
+
+  registerSchemaComponent() {
+    throw new Error("Reactron: 'registerSchemaComponent' used before loading wasm module");
+  },
+
+  renderSchema() {
+    throw new Error("Reactron: 'renderSchema' used before loading wasm module");
+  },
+
+  setCommitOnFrame() {
+    throw new Error("Reactron: 'setCommitOnFrame' used before loading wasm module");
+  },
+
+  setGuardrails() {
+    throw new Error("Reactron: 'setGuardrails' used before loading wasm module");
+  },
+
+  setComponentOwner() {
+    throw new Error("Reactron: 'setComponentOwner' used before loading wasm module");
+  },
+
+  replaceComponent() {
+    throw new Error("Reactron: 'replaceComponent' used before loading wasm module");
+  },
+
+  fusionVersion() {
+    throw new Error("Reactron: 'fusionVersion' used before loading wasm module");
+  },
+
+  capabilities() {
+    throw new Error("Reactron: 'capabilities' used before loading wasm module");
+  },
+
+  setNodeRecycling() {
+    throw new Error("Reactron: 'setNodeRecycling' used before loading wasm module");
+  },
+
+  setFrameBudget() {
+    throw new Error("Reactron: 'setFrameBudget' used before loading wasm module");
+  },
+
+  frameStats() {
+    throw new Error("Reactron: 'frameStats' used before loading wasm module");
+  },
+
+  // `scheduler` picks how the work loop gets its passes: `{ backend,
+  // frameBudget }`, `backend` being "idle" (the default), "message-channel",
+  // "timeout" or a function called with a continuation to run the next pass
+  load({ scheduler } = {}) {
     return import("../pkg/reactron_bg.js").then((glue) => {
-      context = glue.get_context();
+      context = glue.get_context(scheduler);
+
+      // The module runs its own work loop from here on
+      glue.start(context);
+
+      this.render = (element, parentDom, { shadow } = {}) => {
+        context = glue.render(context, element, parentDom, shadow);
+      };
+
+      this.setFrameBudget = (milliseconds) => {
+        glue.start(context, milliseconds);
+      };
+
+      // `{ unitEstimate, utilization, passes, overruns }`: the rolling cost
+      // of a unit of work and share of its budget a pass uses
+      this.frameStats = () => {
+        return glue.frame_stats(context);
+      };
+
+      this.renderMany = (pairs) => {
+        const elements = pairs.map(([element]) => element);
+        const containers = pairs.map(([, container]) => container);
+
+        context = glue.render_many(context, elements, containers);
+      };
+
+      this.setErrorHandler = (handler) => {
+        context = glue.set_error_handler(context, handler);
+      };
+
+      this.debugTree = () => {
+        return glue.debug_tree(context);
+      };
+
+      this.fiberIdOfNode = (node) => {
+        return glue.fiber_id_of_node(context, node);
+      };
+
+      this.describeFiber = (fiberId) => {
+        return glue.describe_fiber(context, fiberId);
+      };
+
+      this.isFiberWithin = (fiberId, ancestorId) => {
+        return glue.is_fiber_within(context, fiberId, ancestorId);
+      };
+
+      this.validateElement = (element, options = {}) => {
+        return glue.validate_element(element, options);
+      };
+
+      this.invalidateSubtree = (fiberId) => {
+        return glue.invalidate_subtree(context, fiberId);
+      };
+
+      this.getDomNode = (fiberId) => {
+        return glue.get_dom_node(context, fiberId);
+      };
+
+      this.inspectHooks = (fiberId) => {
+        return glue.inspect_hooks(context, fiberId);
+      };
 
-      workLoop = (deadline) => {
-        context = glue.work_loop(context, deadline.didTimeout);
-        window.requestIdleCallback(workLoop);
+      this.setUrlPolicy = (policy) => {
+        context = glue.set_url_policy(context, policy);
       };
 
-      this.render = (element, parentDom) => {
-        context = glue.render(context, element, parentDom);
-        window.requestIdleCallback(workLoop);
+      this.setDevMode = (options = true) => {
+        context = glue.set_dev_mode(context, options);
+      };
+
+      this.batchedUpdates = (callback) => {
+        return glue.batched_updates(context, callback);
+      };
+
+      this.startTransition = (callback) => {
+        glue.start_transition(context, callback);
+      };
+
+      this.runWithPriority = (lane, callback) => {
+        return glue.run_with_lane(context, lane, callback);
+      };
+
+      this.flushSync = (callback) => {
+        return glue.flush_sync(context, callback);
+      };
+
+      this.registerSchemaComponent = (name, component) => {
+        context = glue.register_schema_component(context, name, component);
+      };
+
+      this.renderSchema = (schema, data = {}) => {
+        return glue.render_schema(context, schema, data);
+      };
+
+      this.setCommitOnFrame = (enabled) => {
+        context = glue.set_commit_on_frame(context, enabled);
+      };
+
+      this.setGuardrails = (options) => {
+        glue.set_guardrails(context, options);
+      };
+
+      this.setComponentOwner = (component, owner) => {
+        context = glue.set_component_owner(context, component, owner);
+      };
+
+      this.replaceComponent = (oldComponent, newComponent) => {
+        context = glue.replace_component(context, oldComponent, newComponent);
+      };
+
+      this.fusionVersion = () => {
+        return glue.fusion_version();
+      };
+
+      this.capabilities = () => {
+        return glue.capabilities(context);
+      };
+
+      this.setNodeRecycling = (enabled) => {
+        context = glue.set_node_recycling(context, enabled);
       };
 
       this.useState = (initialValue) => {
@@ -33,6 +364,302 @@ export default {
         return result;
       };
 
+      this.useEffect = (callback, deps) => {
+        glue.use_effect(context, callback, deps);
+      };
+
+      this.useEventCallback = (callback) => {
+        return glue.use_event_callback(context, callback);
+      };
+
+      this.useAnnouncer = () => {
+        return glue.use_announcer(context);
+      };
+
+      this.useFullscreen = (element) => {
+        return glue.use_fullscreen(context, element);
+      };
+
+      this.usePictureInPicture = (video) => {
+        return glue.use_picture_in_picture(context, video);
+      };
+
+      this.useWakeLock = () => {
+        glue.use_wake_lock(context);
+      };
+
+      this.useIdle = (timeout) => {
+        return glue.use_idle(context, timeout);
+      };
+
+      this.useInstance = (create, dispose) => {
+        return glue.use_instance(context, create, dispose);
+      };
+
+      this.useImperativeHandle = (create, deps) => {
+        glue.use_imperative_handle(context, create, deps);
+      };
+
+      this.sendCommand = (target, name, payload) => {
+        return glue.send_command(context, target, name, payload);
+      };
+
+      this.useBroadcastChannel = (name) => {
+        return glue.use_broadcast_channel(context, name);
+      };
+
+      this.useUndoableState = (initialValue, options = {}) => {
+        return glue.use_undoable_state(context, initialValue, options);
+      };
+
+      this.useContainerQuery = (element, breakpoints) => {
+        return glue.use_container_query(context, element, breakpoints);
+      };
+
+      this.useHotkey = (combo, handler) => {
+        glue.use_hotkey(context, combo, handler);
+      };
+
+      this.usePrefetch = (url, as) => {
+        glue.use_prefetch(context, url, as);
+      };
+
+      this.useWorker = (url, input, deps) => {
+        return glue.use_worker(context, url, input, deps);
+      };
+
+      this.useDirection = () => {
+        return glue.use_direction(context);
+      };
+
+      this.useBlocker = (when, message) => {
+        glue.use_blocker(context, when, message);
+      };
+
+      this.confirmNavigation = () => {
+        return glue.confirm_navigation(context);
+      };
+
+      this.focusAfterNavigation = (target, { scrollY, announcement } = {}) => {
+        context = glue.focus_after_navigation(context, target, scrollY, announcement);
+      };
+
+      // Runs `work` with hooks pointed at another root's Context
+      const withContext = (ownContext, work) => {
+        const outerContext = context;
+        context = ownContext;
+
+        try {
+          return work();
+        } finally {
+          context = outerContext;
+        }
+      };
+
+      // Creates a Context for another root, started on a scheduler backend
+      // that runs each pass with hooks pointed at that Context. Like the main
+      // Context's, it only asks for passes while the root has work.
+      const createRootContext = () => {
+        let ownContext = null;
+
+        const backend = (continuation, urgent) => {
+          const pass = (deadline) => withContext(ownContext, () => continuation(deadline ? deadline.timeRemaining() : undefined));
+
+          if (urgent || !window.requestIdleCallback) {
+            setTimeout(pass, 0);
+          } else {
+            window.requestIdleCallback(pass);
+          }
+        };
+
+        ownContext = glue.get_context({ backend });
+        glue.start(ownContext);
+
+        return ownContext;
+      };
+
+      // Each instance renders the component into its own shadow root with a
+      // Context of its own
+      this.defineCustomElement = (tagName, component, observedAttributes = []) => {
+        const reactron = this;
+
+        const propName = (attribute) => attribute.replace(/-([a-z])/g, (_, letter) => letter.toUpperCase());
+
+        class FusionElement extends HTMLElement {
+          static get observedAttributes() {
+            return observedAttributes;
+          }
+
+          connectedCallback() {
+            this.fusionContext = this.fusionContext || createRootContext();
+            this.fusionRender();
+          }
+
+          disconnectedCallback() {
+            this.fusionContext = glue.unmount(this.fusionContext);
+          }
+
+          attributeChangedCallback() {
+            if (this.isConnected && this.fusionContext) {
+              this.fusionRender();
+            }
+          }
+
+          fusionRender() {
+            const props = {};
+
+            for (const attribute of observedAttributes) {
+              if (this.hasAttribute(attribute)) {
+                props[propName(attribute)] = this.getAttribute(attribute);
+              }
+            }
+
+            const element = reactron.createElement(component, props);
+            this.fusionContext = glue.render(this.fusionContext, element, this, "open");
+          }
+        }
+
+        window.customElements.define(tagName, FusionElement);
+        return FusionElement;
+      };
+
+      // Islands: registered components mounted into `[data-island="Name"]`
+      // containers, with props from their `data-props` JSON. Containers
+      // inserted later, by turbo frames or other HTML-over-the-wire swaps,
+      // are mounted as they arrive and unmounted when they leave. Each
+      // island is a root with a Context of its own, like custom elements.
+      // There's no hydration: the server markup in a container is replaced.
+      const islands = new Map();
+      const mountedIslands = new Map();
+      let islandObserver = null;
+
+      const islandsIn = (node) => {
+        if (node.nodeType !== Node.ELEMENT_NODE) {
+          return [];
+        }
+
+        const containers = [...node.querySelectorAll("[data-island]")];
+        return node.matches("[data-island]") ? [node, ...containers] : containers;
+      };
+
+      const mountIsland = (container) => {
+        const component = islands.get(container.dataset.island);
+
+        if (!component || !container.isConnected || mountedIslands.has(container)) {
+          return;
+        }
+
+        let props;
+        try {
+          props = JSON.parse(container.dataset.props || "{}");
+        } catch (error) {
+          console.error("Reactron: invalid data-props on island", container, error);
+          return;
+        }
+
+        const island = { context: createRootContext() };
+        mountedIslands.set(container, island);
+
+        container.replaceChildren();
+        island.context = glue.render(island.context, this.createElement(component, props), container);
+      };
+
+      const unmountIsland = (container) => {
+        const island = mountedIslands.get(container);
+
+        if (!island || container.isConnected) {
+          return;
+        }
+
+        mountedIslands.delete(container);
+        island.context = glue.unmount(island.context);
+      };
+
+      this.registerIsland = (name, component) => {
+        islands.set(name, component);
+        islandsIn(document.body).forEach(mountIsland);
+
+        if (!islandObserver) {
+          islandObserver = new MutationObserver((records) => {
+            for (const record of records) {
+              record.removedNodes.forEach((node) => islandsIn(node).forEach(unmountIsland));
+              record.addedNodes.forEach((node) => islandsIn(node).forEach(mountIsland));
+            }
+          });
+
+          islandObserver.observe(document.body, { childList: true, subtree: true });
+        }
+      };
+
+      // Builds `element` for `container` in the idle lane, on a Context of
+      // its own like islands, without touching the container. `activate()`
+      // finishes the render if needed and swaps the tree in; `discard()`
+      // drops a prerender that won't be shown.
+      this.prerender = (element, container) => {
+        let prerenderContext = glue.prerender(createRootContext(), element, container);
+
+        return {
+          activate: () => {
+            withContext(prerenderContext, () => glue.activate(prerenderContext));
+          },
+          discard: () => {
+            prerenderContext = glue.unmount(prerenderContext);
+          },
+        };
+      };
+
+      this.css = (component, source) => {
+        let name = typeof component === "function" ? component.name : component;
+        return glue.scoped_css(context, name || "", source);
+      };
+
+      // Sets the text direction of its children, "ltr" or "rtl", on a
+      // wrapper that takes no part in layout
+      this.Direction = ({ dir, children }) => {
+        return this.createElement("div", { dir, style: { display: "contents" } }, children);
+      };
+
+      // Renders `placeholder` inside a sentinel element until the sentinel
+      // scrolls into view, or the browser goes idle with the `idle` prop,
+      // then mounts the children in its place for good.
+      this.LazyMount = ({ placeholder, idle, children }) => {
+        let [visible, setVisible] = this.useState(false);
+
+        this.useEffect(() => {
+          if (!idle || visible) {
+            return;
+          }
+
+          let handle = window.requestIdleCallback(() => setVisible(true));
+          return () => window.cancelIdleCallback(handle);
+        }, [idle, visible]);
+
+        if (visible) {
+          return this.createElement("div", null, children);
+        }
+
+        let onIntersect = (entry) => {
+          if (entry.isIntersecting) {
+            setVisible(true);
+          }
+        };
+
+        return this.createElement("div", { onIntersect }, placeholder);
+      };
+
+      this.createPortal = (children, target) => {
+        let portalChildren = [children]
+          .flat()
+          .filter((x) => x)
+          .map((x) => {
+            return typeof x === "string"
+              ? glue.create_text_element(x)
+              : x;
+          });
+
+        return glue.create_portal(portalChildren, target);
+      };
+
       this.createElement = (type, props, ...rawChildren) => {
         props = props || {};
         let children = rawChildren
@@ -50,18 +677,7 @@ export default {
           props.children = children;
           return glue.create_functional_component(type, props);
         } else {
-          let elementProps = glue.create_props(
-            props ? props.className : null,
-            props ? props.nodeValue: null,
-            props ? props.onClick : null,
-            props ? props.onChange : null,
-            props ? props.onBlur : null,
-            props ? props.onKeyDown : null,
-            props ? props.type : null,
-            props ? props.value : null,
-            props ? props.checked : null,
-            props ? props.placeholder : null,
-          );
+          let elementProps = glue.create_props_from_object(props, type);
           return glue.create_element(type, elementProps, children);
         }
       };
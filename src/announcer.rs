@@ -0,0 +1,186 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use web_sys::{Element as HTMLElement, Document};
+use super::timers::set_timeout;
+use super::{ANNOUNCE_THROTTLE_MS, ANNOUNCE_DEDUPE_MS, ANNOUNCE_CLEAR_MS};
+
+const VISUALLY_HIDDEN: &str = "position:absolute;width:1px;height:1px;margin:-1px;padding:0;\
+    overflow:hidden;clip:rect(0,0,0,0);white-space:nowrap;border:0";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Politeness {
+    Polite,
+    Assertive,
+}
+
+impl Politeness {
+    pub fn from_value(value: &JsValue) -> Self {
+        match value.as_string().as_deref() {
+            Some("assertive") => Politeness::Assertive,
+            _ => Politeness::Polite,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Politeness::Polite => "polite",
+            Politeness::Assertive => "assertive",
+        }
+    }
+}
+
+/// Visually hidden `aria-live` regions shared by every `use_announcer` of a
+/// Context. They are appended to the document body on the first announcement
+/// and removed once no mounted component uses the announcer anymore.
+pub struct Announcer {
+    document: Document,
+    regions: Option<(HTMLElement, HTMLElement)>,
+    pending: Vec<(String, Politeness)>,
+    last: Option<(String, f64)>,
+    flush_scheduled: bool,
+    clear_timeout: Option<i32>,
+    users: usize,
+}
+
+impl Announcer {
+    pub fn new(document: Document) -> Self {
+        Announcer {
+            document,
+            regions: None,
+            pending: Vec::new(),
+            last: None,
+            flush_scheduled: false,
+            clear_timeout: None,
+            users: 0,
+        }
+    }
+
+    pub fn retain(&mut self) {
+        self.users += 1;
+    }
+
+    pub fn release(&mut self) {
+        self.users = self.users.saturating_sub(1);
+
+        if self.users == 0 {
+            self.pending.clear();
+            self.last = None;
+
+            if let Some(timeout) = self.clear_timeout.take() {
+                web_sys::window().unwrap().clear_timeout_with_handle(timeout);
+            }
+
+            if let Some((polite, assertive)) = self.regions.take() {
+                polite.remove();
+                assertive.remove();
+            }
+        }
+    }
+
+    /// Queues a message for the live region. Repeats of a message that is
+    /// pending or was just announced are dropped.
+    pub fn announce(announcer: &Rc<RefCell<Announcer>>, message: String, politeness: Politeness) {
+        let mut this = announcer.borrow_mut();
+        let now = js_sys::Date::now();
+
+        if message.is_empty() || this.users == 0 {
+            return;
+        }
+
+        let is_pending = this.pending.iter().any(|(pending, _)| *pending == message);
+        let was_just_announced = this.last.as_ref()
+            .is_some_and(|(last, at)| *last == message && now - at < ANNOUNCE_DEDUPE_MS);
+
+        if is_pending || was_just_announced {
+            return;
+        }
+
+        this.pending.push((message, politeness));
+
+        if !this.flush_scheduled {
+            this.flush_scheduled = true;
+
+            let announcer = Rc::clone(announcer);
+            set_timeout(ANNOUNCE_THROTTLE_MS, move || Announcer::flush(&announcer));
+        }
+    }
+
+    /// Announces for a caller that doesn't hold the announcer, such as a
+    /// navigation. It's held until the message has been cleared from the
+    /// live region, so the regions still go away once no component uses them.
+    pub fn announce_once(announcer: &Rc<RefCell<Announcer>>, message: String, politeness: Politeness) {
+        announcer.borrow_mut().retain();
+        Announcer::announce(announcer, message, politeness);
+
+        let announcer = Rc::clone(announcer);
+        set_timeout(ANNOUNCE_THROTTLE_MS + ANNOUNCE_CLEAR_MS, move || announcer.borrow_mut().release());
+    }
+
+    fn flush(announcer: &Rc<RefCell<Announcer>>) {
+        let mut this = announcer.borrow_mut();
+        this.flush_scheduled = false;
+
+        if this.pending.is_empty() {
+            return;
+        }
+
+        let pending = std::mem::take(&mut this.pending);
+        let (polite, assertive) = this.ensure_regions();
+
+        for (region, politeness) in [(polite, Politeness::Polite), (assertive, Politeness::Assertive)] {
+            let messages: Vec<&str> = pending.iter()
+                .filter(|(_, p)| *p == politeness)
+                .map(|(message, _)| message.as_str())
+                .collect();
+
+            if !messages.is_empty() {
+                region.set_text_content(Some(&messages.join(" ")));
+            }
+        }
+
+        if let Some((message, _)) = pending.last() {
+            this.last = Some((message.clone(), js_sys::Date::now()));
+        }
+
+        if let Some(timeout) = this.clear_timeout.take() {
+            web_sys::window().unwrap().clear_timeout_with_handle(timeout);
+        }
+
+        let weak = Rc::downgrade(announcer);
+        this.clear_timeout = Some(set_timeout(ANNOUNCE_CLEAR_MS, move || {
+            if let Some(announcer) = weak.upgrade() {
+                let mut this = announcer.borrow_mut();
+                this.clear_timeout = None;
+
+                if let Some((polite, assertive)) = this.regions.as_ref() {
+                    polite.set_text_content(None);
+                    assertive.set_text_content(None);
+                }
+            }
+        }));
+    }
+
+    fn ensure_regions(&mut self) -> (HTMLElement, HTMLElement) {
+        if self.regions.is_none() {
+            let polite = self.create_region(Politeness::Polite);
+            let assertive = self.create_region(Politeness::Assertive);
+            self.regions = Some((polite, assertive));
+        }
+
+        self.regions.clone().unwrap()
+    }
+
+    fn create_region(&self, politeness: Politeness) -> HTMLElement {
+        let region = self.document.create_element("div").unwrap();
+        region.set_attribute("aria-live", politeness.as_str()).unwrap();
+        region.set_attribute("aria-atomic", "true").unwrap();
+        region.set_attribute("style", VISUALLY_HIDDEN).unwrap();
+
+        if let Some(body) = self.document.body() {
+            body.append_child(&region).unwrap();
+        }
+
+        region
+    }
+}
@@ -0,0 +1,23 @@
+use super::{ARIA_ATTRIBUTES, ARIA_HIDDEN_ELEMENTS};
+
+/// Development check of an `aria-*` attribute written to an element of the
+/// given tag name. Returns the warning to print, if any.
+pub fn check_attribute(tag_name: &str, name: &str) -> Option<String> {
+    let aria_name = name.strip_prefix("aria-")?;
+
+    if !ARIA_ATTRIBUTES.contains(&aria_name) {
+        let hint = ARIA_ATTRIBUTES.iter()
+            .find(|known| known.eq_ignore_ascii_case(aria_name) || known.replace('-', "") == aria_name.replace('-', "").to_ascii_lowercase());
+
+        return Some(match hint {
+            Some(known) => format!("Reactron: invalid ARIA attribute '{}', did you mean 'aria-{}'?", name, known),
+            None => format!("Reactron: invalid ARIA attribute '{}'", name),
+        });
+    }
+
+    if ARIA_HIDDEN_ELEMENTS.contains(&tag_name.to_ascii_lowercase().as_str()) {
+        return Some(format!("Reactron: '{}' has no effect on <{}>, which is never exposed to assistive technologies", name, tag_name));
+    }
+
+    None
+}
@@ -0,0 +1,162 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use super::Element;
+
+pub type ElementChildren = Rc<RefCell<Vec<Box<Element>>>>;
+
+/// Outcome of a component function that returned a Promise or a generator.
+/// Carried over from the alternate on every render, instead of calling the
+/// component again, until the component receives different props.
+#[derive(Clone)]
+pub struct AsyncResult {
+    props: Rc<JsValue>,
+    resolved: Rc<RefCell<Option<ElementChildren>>>,
+}
+
+impl AsyncResult {
+    pub fn new(props: Rc<JsValue>) -> Self {
+        AsyncResult {
+            props,
+            resolved: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    pub fn is(&self, other: &AsyncResult) -> bool {
+        Rc::ptr_eq(&self.resolved, &other.resolved)
+    }
+
+    /// The element the promise resolved to or the generator yielded last,
+    /// nothing until then. Every render gets a copy, as reconciling takes the
+    /// props and children out of the elements it's given.
+    pub fn resolved(&self) -> Option<ElementChildren> {
+        self.resolved.borrow().as_ref().map(|children| Rc::new(RefCell::new(children.borrow().clone())))
+    }
+
+    pub fn resolve(&self, element: Option<Box<Element>>) {
+        self.resolved.replace(Some(Rc::new(RefCell::new(element.into_iter().collect()))));
+    }
+
+    // Props are compared one level deep with `Object.is`. `children` is left
+    // out since the parent recreates its elements on every render.
+    pub fn props_match(&self, props: &JsValue) -> bool {
+        let (prev, next) = match (self.props.dyn_ref::<js_sys::Object>(), props.dyn_ref::<js_sys::Object>()) {
+            (Some(prev), Some(next)) => (prev, next),
+            (_, _) => return js_sys::Object::is(&self.props, props),
+        };
+
+        let keys = |props: &js_sys::Object| -> Vec<String> {
+            js_sys::Object::keys(props).iter()
+                .filter_map(|key| key.as_string())
+                .filter(|key| key != "children")
+                .collect()
+        };
+
+        let prev_keys = keys(prev);
+
+        prev_keys == keys(next) && prev_keys.iter().all(|key| {
+            let key = JsValue::from_str(key);
+            js_sys::Object::is(
+                &js_sys::Reflect::get(prev, &key).unwrap_or(JsValue::UNDEFINED),
+                &js_sys::Reflect::get(next, &key).unwrap_or(JsValue::UNDEFINED),
+            )
+        })
+    }
+}
+
+/// Generator objects and other iterators, told apart from elements (numbers)
+/// and promises by their `next` method
+pub fn as_iterator(output: &JsValue) -> Option<js_sys::Iterator> {
+    if !output.is_object() || output.is_instance_of::<js_sys::Promise>() {
+        return None;
+    }
+
+    js_sys::Reflect::get(output, &"next".into()).ok()
+        .filter(|next| next.is_function())
+        .map(|_| output.clone().unchecked_into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::element::create_text_element;
+
+    // What reconciling a render does to the elements it's given
+    fn reconcile(children: &ElementChildren) {
+        for child in children.borrow_mut().iter_mut() {
+            assert!(child.props_mut().take().is_some(), "element without props");
+            child.children_mut().take();
+        }
+    }
+
+    #[test]
+    fn resolved_element_survives_re_renders() {
+        let async_result = AsyncResult::new(Rc::new(JsValue::UNDEFINED));
+        async_result.resolve(Some(Element::from_ptr(create_text_element(String::from("loaded")))));
+
+        for _ in 0..3 {
+            reconcile(&async_result.resolved().unwrap());
+        }
+    }
+
+    // A generator yields a new stage between renders; each one is rendered
+    // more than once while the component's props stay the same
+    #[test]
+    fn every_generator_stage_survives_re_renders() {
+        let async_result = AsyncResult::new(Rc::new(JsValue::UNDEFINED));
+
+        for stage in ["loading", "partial", "done"] {
+            async_result.resolve(Some(Element::from_ptr(create_text_element(String::from(stage)))));
+
+            for _ in 0..2 {
+                let children = async_result.resolved().unwrap();
+                assert_eq!(children.borrow()[0].props().as_ref().and_then(|props| props.node_value()).map(String::as_str), Some(stage));
+                reconcile(&children);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod js_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn object(entries: &[(&str, JsValue)]) -> JsValue {
+        let object = js_sys::Object::new();
+        for (name, value) in entries {
+            js_sys::Reflect::set(&object, &JsValue::from_str(name), value).unwrap();
+        }
+
+        object.into()
+    }
+
+    #[wasm_bindgen_test]
+    fn props_match_ignores_children() {
+        let async_result = AsyncResult::new(Rc::new(object(&[("id", 1.into()), ("children", 10.into())])));
+
+        assert!(async_result.props_match(&object(&[("id", 1.into()), ("children", 20.into())])));
+        assert!(!async_result.props_match(&object(&[("id", 2.into()), ("children", 10.into())])));
+        assert!(!async_result.props_match(&object(&[("id", 1.into()), ("extra", true.into())])));
+    }
+
+    #[wasm_bindgen_test]
+    fn props_match_compares_objects_by_identity() {
+        let shared = object(&[]);
+        let async_result = AsyncResult::new(Rc::new(object(&[("user", shared.clone())])));
+
+        assert!(async_result.props_match(&object(&[("user", shared)])));
+        assert!(!async_result.props_match(&object(&[("user", object(&[]))])));
+    }
+
+    #[wasm_bindgen_test]
+    fn only_objects_with_next_are_iterators() {
+        let next = js_sys::Function::new_no_args("return { done: true }");
+
+        assert!(as_iterator(&object(&[("next", next.into())])).is_some());
+        assert!(as_iterator(&js_sys::Promise::resolve(&JsValue::NULL).into()).is_none());
+        assert!(as_iterator(&42.into()).is_none());
+        assert!(as_iterator(&object(&[("next", 1.into())])).is_none());
+    }
+}
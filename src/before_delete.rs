@@ -0,0 +1,65 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Element as HTMLElement;
+use super::{FiberCell, FiberSubtreeIterator, Node, promise, timers, BEFORE_DELETE_GRACE_MS};
+
+/// Calls the `onBeforeDelete` props of a deleted subtree with their
+/// elements, while those are still attached. Returns the promises of the
+/// callbacks that want to finish asynchronously. A throwing callback is
+/// reported and doesn't stop the commit.
+pub fn notify(fiber: &FiberCell) -> Vec<js_sys::Promise> {
+    let mut promises = Vec::new();
+
+    for fiber in fiber.subtree() {
+        let fiber = fiber.borrow();
+
+        let (callback, dom_node) = match (fiber.props().and_then(|props| props.on_before_delete()), fiber.dom_node()) {
+            (Some(callback), Some(dom_node)) => (callback, dom_node),
+            _ => continue,
+        };
+
+        if let Node::Element(node) = &*dom_node.borrow() {
+            match callback.call1(&JsValue::NULL, node) {
+                Ok(output) => promises.extend(output.dyn_into::<js_sys::Promise>().ok()),
+                Err(error) => web_sys::console::error_2(&"Reactron: onBeforeDelete threw".into(), &error),
+            }
+        };
+    }
+
+    promises
+}
+
+/// Removes `node` once every promise settled, or when the grace period is
+/// over, whichever comes first
+pub fn remove_after(node: HTMLElement, promises: Vec<js_sys::Promise>) {
+    let pending = Rc::new(Cell::new(promises.len()));
+    let removed = Rc::new(Cell::new(false));
+
+    let remove = {
+        let removed = Rc::clone(&removed);
+        move || {
+            if !removed.replace(true) {
+                node.remove();
+            }
+        }
+    };
+
+    for promise in promises {
+        let pending = Rc::clone(&pending);
+        let remove = remove.clone();
+
+        let on_settle = Closure::once_into_js(move |_: JsValue| {
+            pending.set(pending.get() - 1);
+
+            if pending.get() == 0 {
+                remove();
+            }
+        });
+
+        let _ = promise::then(&promise, &on_settle, &on_settle);
+    }
+
+    timers::set_timeout(BEFORE_DELETE_GRACE_MS, remove);
+}
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Window;
+use super::BLOCKER_MESSAGE;
+
+/// Every `use_blocker` of a Context, by id in mount order. While one of them
+/// is active, a `beforeunload` listener asks the browser to confirm reloads
+/// and hard navigations; routers ask for soft ones through
+/// `confirm_navigation`.
+pub struct BlockerRegistry {
+    window: Window,
+    blockers: BTreeMap<u32, (bool, Option<String>)>,
+    next_id: u32,
+    on_before_unload: Option<js_sys::Function>,
+}
+
+impl BlockerRegistry {
+    pub fn new(window: Window) -> Self {
+        BlockerRegistry {
+            window,
+            blockers: BTreeMap::new(),
+            next_id: 0,
+            on_before_unload: None,
+        }
+    }
+
+    pub fn register(&mut self, when: bool, message: Option<String>) -> u32 {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        self.blockers.insert(id, (when, message));
+        self.sync_listener();
+
+        id
+    }
+
+    pub fn update(&mut self, id: u32, when: bool, message: Option<String>) {
+        if let Some(blocker) = self.blockers.get_mut(&id) {
+            *blocker = (when, message);
+        }
+
+        self.sync_listener();
+    }
+
+    pub fn unregister(&mut self, id: u32) {
+        self.blockers.remove(&id);
+        self.sync_listener();
+    }
+
+    /// Message of the innermost active blocker, the one mounted last
+    pub fn active_message(&self) -> Option<&str> {
+        self.blockers.values()
+            .rev()
+            .find(|(when, _)| *when)
+            .map(|(_, message)| message.as_deref().unwrap_or(BLOCKER_MESSAGE))
+    }
+
+    fn sync_listener(&mut self) {
+        let is_blocking = self.active_message().is_some();
+
+        match (is_blocking, self.on_before_unload.as_ref()) {
+            (true, None) => {
+                // Browsers show their own wording, the message can't be customized
+                let on_before_unload: js_sys::Function = Closure::wrap(Box::new(|event: web_sys::Event| {
+                    event.prevent_default();
+                    let _ = js_sys::Reflect::set(&event, &"returnValue".into(), &"".into());
+                }) as Box<dyn FnMut(web_sys::Event)>).into_js_value().unchecked_into();
+
+                self.window.add_event_listener_with_callback("beforeunload", &on_before_unload).unwrap();
+                self.on_before_unload = Some(on_before_unload);
+            },
+            (false, Some(on_before_unload)) => {
+                let _ = self.window.remove_event_listener_with_callback("beforeunload", on_before_unload);
+                self.on_before_unload = None;
+            },
+            (_, _) => {}
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{BroadcastChannel, MessageEvent};
+use super::timers::set_timeout;
+
+struct ChannelState {
+    latest: JsValue,
+    update_scheduled: bool,
+}
+
+/// State of a `use_broadcast_channel` hook: the open channel, the latest
+/// message received from another tab and the stable `post` function handed
+/// to JS. Messages arriving within the same task cause a single update.
+#[derive(Clone)]
+pub struct ChannelBinding {
+    name: String,
+    channel: BroadcastChannel,
+    state: Rc<RefCell<ChannelState>>,
+    post: js_sys::Function,
+}
+
+impl ChannelBinding {
+    pub fn open(name: String, on_update: impl Fn() + 'static) -> Result<Self, JsValue> {
+        let channel = BroadcastChannel::new(&name)?;
+        let state = Rc::new(RefCell::new(ChannelState { latest: JsValue::UNDEFINED, update_scheduled: false }));
+        let on_update = Rc::new(on_update);
+
+        let on_message: js_sys::Function = {
+            let state = Rc::clone(&state);
+
+            Closure::wrap(Box::new(move |event: MessageEvent| {
+                let mut channel_state = state.borrow_mut();
+                channel_state.latest = event.data();
+
+                if channel_state.update_scheduled {
+                    return;
+                }
+                channel_state.update_scheduled = true;
+
+                let state = Rc::clone(&state);
+                let on_update = Rc::clone(&on_update);
+
+                set_timeout(0, move || {
+                    state.borrow_mut().update_scheduled = false;
+                    on_update();
+                });
+            }) as Box<dyn FnMut(MessageEvent)>).into_js_value().unchecked_into()
+        };
+        channel.set_onmessage(Some(&on_message));
+
+        let post: js_sys::Function = {
+            let channel = channel.clone();
+
+            // Posting to a closed channel throws, which is left to the caller
+            Closure::wrap(Box::new(move |message: JsValue| -> Result<(), JsValue> {
+                channel.post_message(&message)
+            }) as Box<dyn FnMut(JsValue) -> Result<(), JsValue>>).into_js_value().unchecked_into()
+        };
+
+        Ok(ChannelBinding { name, channel, state, post })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn latest(&self) -> JsValue {
+        self.state.borrow().latest.clone()
+    }
+
+    pub fn post(&self) -> &js_sys::Function {
+        &self.post
+    }
+
+    pub fn close(&self) {
+        self.channel.set_onmessage(None);
+        self.channel.close();
+    }
+}
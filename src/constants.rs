@@ -1,3 +1,190 @@
 pub static TEXT_ELEMENT: &str = "__TEXT";
 pub static FIBER_ROOT: &str = "_R_";
 pub static FIBER_FUNCTIONAL: &str = "_F_";
+pub static SURFACE_ELEMENT: &str = "__SURFACE";
+pub static PORTAL_ELEMENT: &str = "__PORTAL";
+
+// Props handled by the reconciler itself rather than written to the DOM
+pub static RESERVED_PROPS: &[&str] = &["children", "key", "ref"];
+
+// Attributes whose presence alone means true, lowercase
+pub static BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen", "async", "autofocus", "autoplay", "controls", "default", "defer",
+    "disabled", "formnovalidate", "hidden", "inert", "ismap", "itemscope", "loop", "multiple",
+    "nomodule", "novalidate", "open", "playsinline", "readonly", "required", "reversed",
+];
+
+// Props written as DOM properties rather than attributes, by element type
+// ("*" for any element). `value`, `checked`, `srcObject` and the boolean
+// properties below have dedicated handling in the reconciler and are listed
+// for completeness.
+pub static DOM_PROPERTIES: &[(&str, &str)] = &[
+    ("input", "value"), ("textarea", "value"), ("select", "value"),
+    ("input", "checked"), ("input", "indeterminate"), ("option", "selected"),
+    ("input", "defaultValue"), ("textarea", "defaultValue"), ("input", "defaultChecked"),
+    ("audio", "muted"), ("video", "muted"), ("audio", "volume"), ("video", "volume"),
+    ("audio", "playbackRate"), ("video", "playbackRate"), ("audio", "currentTime"), ("video", "currentTime"),
+    ("audio", "srcObject"), ("video", "srcObject"),
+];
+
+// Attributes taking "true" and "false", lowercase
+pub static ENUMERATED_ATTRIBUTES: &[&str] = &["contenteditable", "draggable", "spellcheck"];
+
+// Boolean props written as IDL properties: their attribute only sets the
+// initial state, which the user or the element itself changes afterwards
+pub static BOOLEAN_PROPERTIES: &[&str] = &["selected", "muted", "indeterminate"];
+
+// States and properties defined by WAI-ARIA 1.2, without the `aria-` prefix
+pub static ARIA_ATTRIBUTES: &[&str] = &[
+    "activedescendant", "atomic", "autocomplete", "busy", "checked", "colcount", "colindex",
+    "colspan", "controls", "current", "describedby", "description", "details", "disabled",
+    "dropeffect", "errormessage", "expanded", "flowto", "grabbed", "haspopup", "hidden", "invalid",
+    "keyshortcuts", "label", "labelledby", "level", "live", "modal", "multiline",
+    "multiselectable", "orientation", "owns", "placeholder", "posinset", "pressed", "readonly",
+    "relevant", "required", "roledescription", "rowcount", "rowindex", "rowspan", "selected",
+    "setsize", "sort", "valuemax", "valuemin", "valuenow", "valuetext",
+];
+
+// Elements that take no ARIA attributes since they're never rendered to the
+// accessibility tree
+pub static ARIA_HIDDEN_ELEMENTS: &[&str] = &[
+    "base", "head", "html", "link", "meta", "noscript", "script", "slot", "style", "template", "title",
+];
+
+// CSS properties taking plain numbers in a style object, without `px`
+pub static UNITLESS_STYLE_PROPERTIES: &[&str] = &[
+    "animation-iteration-count", "aspect-ratio", "column-count", "columns", "fill-opacity", "flex",
+    "flex-grow", "flex-shrink", "flood-opacity", "font-weight", "grid-area", "grid-column",
+    "grid-column-end", "grid-column-start", "grid-row", "grid-row-end", "grid-row-start",
+    "line-clamp", "line-height", "opacity", "order", "orphans", "scale", "stop-opacity",
+    "stroke-dashoffset", "stroke-miterlimit", "stroke-opacity", "stroke-width", "tab-size",
+    "widows", "z-index", "zoom", "-webkit-line-clamp",
+];
+
+pub static SVG_NAMESPACE: &str = "http://www.w3.org/2000/svg";
+pub static XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+pub static XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+// Longest stretch of passive effects run in one go after a commit; the rest
+// waits for the next idle callback
+pub static PASSIVE_EFFECT_BUDGET_MS: f64 = 5.0;
+
+// Limits `validate_element` checks a tree against unless given others
+pub static VALIDATION_MAX_DEPTH: usize = 64;
+pub static VALIDATION_MAX_NODES: usize = 10_000;
+
+// Elements and props a server-driven UI schema may not use, since they could
+// run script or replace markup wholesale
+pub static SCHEMA_BLOCKED_ELEMENTS: [&str; 9] = ["script", "iframe", "object", "embed", "base", "link", "meta", "frame", "frameset"];
+pub static SCHEMA_BLOCKED_PROPS: [&str; 5] = ["innerHTML", "outerHTML", "srcdoc", "dangerouslySetInnerHTML", "ref"];
+
+// Longest an `onBeforeDelete` Promise keeps its element in the document
+pub static BEFORE_DELETE_GRACE_MS: i32 = 5000;
+
+// How long dev mode outlines the nodes a commit wrote to
+pub static HIGHLIGHT_UPDATES_MS: i32 = 300;
+pub static HIGHLIGHT_OUTLINE: &str = "2px solid rgba(255, 64, 129, 0.8)";
+// Descriptions of the per-Context symbols a flashing node keeps its timer
+// and own outline under
+pub static HIGHLIGHT_TIMER_KEY: &str = "__fusionHighlight";
+pub static HIGHLIGHT_OUTLINE_KEY: &str = "__fusionOutline";
+
+// How long updates in each lane may wait for a commit before their render
+// is made synchronous
+pub static USER_INPUT_LANE_TIMEOUT_MS: f64 = 250.0;
+pub static DEFAULT_LANE_TIMEOUT_MS: f64 = 5000.0;
+pub static TRANSITION_LANE_TIMEOUT_MS: f64 = 5000.0;
+
+// Passes `flush_sync` runs at most, in case every commit schedules another
+// update
+pub static SYNC_FLUSH_LIMIT: usize = 50;
+
+// Time a pass of the built-in scheduler works for before giving the main
+// thread back, unless `start` is given another budget
+pub static FRAME_BUDGET_MS: f64 = 5.0;
+
+// Weight of the latest measurement in the work loop's rolling estimates of
+// unit of work cost and budget utilization
+pub static FRAME_STATS_SMOOTHING: f64 = 0.2;
+
+// Element standing in for the children a guardrail truncated
+pub static GUARDRAIL_PLACEHOLDER: &str = "fusion-truncated";
+
+// Logical style properties written as the physical ones they stand for in
+// left to right and in right to left text, for engines without them
+pub static LOGICAL_STYLE_PROPERTIES: [(&str, &str, &str); 18] = [
+    ("margin-inline-start", "margin-left", "margin-right"),
+    ("margin-inline-end", "margin-right", "margin-left"),
+    ("padding-inline-start", "padding-left", "padding-right"),
+    ("padding-inline-end", "padding-right", "padding-left"),
+    ("inset-inline-start", "left", "right"),
+    ("inset-inline-end", "right", "left"),
+    ("border-inline-start", "border-left", "border-right"),
+    ("border-inline-end", "border-right", "border-left"),
+    ("border-inline-start-width", "border-left-width", "border-right-width"),
+    ("border-inline-end-width", "border-right-width", "border-left-width"),
+    ("border-inline-start-style", "border-left-style", "border-right-style"),
+    ("border-inline-end-style", "border-right-style", "border-left-style"),
+    ("border-inline-start-color", "border-left-color", "border-right-color"),
+    ("border-inline-end-color", "border-right-color", "border-left-color"),
+    ("border-start-start-radius", "border-top-left-radius", "border-top-right-radius"),
+    ("border-start-end-radius", "border-top-right-radius", "border-top-left-radius"),
+    ("border-end-start-radius", "border-bottom-left-radius", "border-bottom-right-radius"),
+    ("border-end-end-radius", "border-bottom-right-radius", "border-bottom-left-radius"),
+];
+
+// Maximum number of detached nodes kept per element type when recycling
+pub static RECYCLE_POOL_LIMIT: usize = 256;
+
+// Elements never recycled: form controls keep dirty value, checkedness and
+// selectedness flags no script can clear, and frames keep their document
+pub static UNRECYCLABLE_ELEMENTS: [&str; 5] = ["input", "textarea", "select", "option", "iframe"];
+
+// Description of the per-Context symbol linking a rendered DOM node to its
+// fiber id
+pub static FIBER_ID_KEY: &str = "__fusionFiberId";
+
+// Events that don't bubble: they are delegated in the capture phase and only
+// reach the handler of the target itself
+pub static NON_BUBBLING_EVENTS: &[&str] = &[
+    "blur", "focus", "mouseenter", "mouseleave", "pointerenter", "pointerleave",
+    "load", "error", "scroll", "play", "pause", "ended", "invalid", "toggle",
+    // Media events
+    "abort", "canplay", "canplaythrough", "durationchange", "emptied", "loadeddata",
+    "loadedmetadata", "loadstart", "playing", "progress", "ratechange", "seeked", "seeking",
+    "stalled", "suspend", "timeupdate", "volumechange", "waiting",
+];
+
+// Confirmation shown when a router navigation hits a `use_blocker` without
+// a message of its own
+pub static BLOCKER_MESSAGE: &str = "Changes you made may not be saved. Leave this page?";
+
+// Prefetches running at the same time
+pub static PREFETCH_CONCURRENCY: usize = 2;
+
+// Values kept by `use_undoable_state` when no `limit` option is given
+pub static UNDO_HISTORY_LIMIT: usize = 100;
+
+// Key combinations bound by `use_undoable_state` with the `hotkeys` option
+pub static UNDO_HOTKEYS: &[&str] = &["ctrl+z", "meta+z"];
+pub static REDO_HOTKEYS: &[&str] = &["ctrl+shift+z", "meta+shift+z", "ctrl+y"];
+
+// Events whose handlers are registered as passive unless they ask otherwise,
+// so the browser can scroll without waiting on them
+pub static PASSIVE_EVENTS: &[&str] = &["scroll", "wheel", "touchstart", "touchmove"];
+
+// Document events counting as user activity for `use_idle`
+pub static IDLE_ACTIVITY_EVENTS: &[&str] = &[
+    "mousemove", "mousedown", "keydown", "touchstart", "wheel", "scroll", "visibilitychange",
+];
+
+// Announcements posted within this window are coalesced into one update of
+// the live region
+pub static ANNOUNCE_THROTTLE_MS: i32 = 150;
+// The same message posted again within this window is dropped
+pub static ANNOUNCE_DEDUPE_MS: f64 = 1000.0;
+// Live regions are emptied this long after the last announcement
+pub static ANNOUNCE_CLEAR_MS: i32 = 5000;
+
+// Idle workers kept per script URL for `use_worker` jobs
+pub static WORKER_POOL_LIMIT: usize = 4;
@@ -0,0 +1,113 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as HTMLElement, ResizeObserver, ResizeObserverEntry};
+
+struct QueryState {
+    target: Option<HTMLElement>,
+    breakpoints: Vec<(String, f64)>,
+    width: Option<f64>,
+    active: Option<String>,
+}
+
+impl QueryState {
+    // The breakpoint with the largest minimum width the element reaches
+    fn resolve(&self) -> Option<String> {
+        let width = self.width?;
+
+        self.breakpoints.iter()
+            .rev()
+            .find(|(_, min_width)| *min_width <= width)
+            .map(|(name, _)| name.clone())
+    }
+}
+
+/// State of a `use_container_query` hook: a `ResizeObserver` on the target
+/// element and the breakpoint its width falls into. Components only
+/// re-render when the breakpoint changes, not on every resize.
+#[derive(Clone)]
+pub struct ContainerQuery {
+    observer: ResizeObserver,
+    state: Rc<RefCell<QueryState>>,
+}
+
+impl ContainerQuery {
+    pub fn mount(on_change: impl Fn() + 'static) -> Result<Self, JsValue> {
+        let state = Rc::new(RefCell::new(QueryState {
+            target: None,
+            breakpoints: Vec::new(),
+            width: None,
+            active: None,
+        }));
+
+        let on_resize: js_sys::Function = {
+            let state = Rc::clone(&state);
+
+            Closure::wrap(Box::new(move |entries: js_sys::Array| {
+                let entry = entries.get(entries.length().saturating_sub(1)).unchecked_into::<ResizeObserverEntry>();
+                let mut query_state = state.borrow_mut();
+
+                query_state.width = Some(entry.content_rect().width());
+                let active = query_state.resolve();
+
+                if active != query_state.active {
+                    query_state.active = active;
+                    drop(query_state);
+                    on_change();
+                }
+            }) as Box<dyn FnMut(js_sys::Array)>).into_js_value().unchecked_into()
+        };
+
+        let observer = ResizeObserver::new(&on_resize)?;
+
+        Ok(ContainerQuery { observer, state })
+    }
+
+    /// Observes `target` instead of the previous element, if it changed
+    pub fn set_target(&self, target: Option<HTMLElement>) {
+        let mut state = self.state.borrow_mut();
+
+        if state.target == target {
+            return;
+        }
+
+        if let Some(previous) = state.target.take() {
+            self.observer.unobserve(&previous);
+        }
+
+        if let Some(target) = target.as_ref() {
+            self.observer.observe(target);
+        } else {
+            state.width = None;
+            state.active = None;
+        }
+
+        state.target = target;
+    }
+
+    /// Takes `{ name: minWidth }` breakpoints. The active one is recomputed
+    /// from the last measured width right away.
+    pub fn set_breakpoints(&self, breakpoints: &JsValue) {
+        let mut breakpoints: Vec<(String, f64)> = js_sys::Object::entries(breakpoints.unchecked_ref())
+            .iter()
+            .filter_map(|entry| {
+                let entry: js_sys::Array = entry.unchecked_into();
+                Some((entry.get(0).as_string()?, entry.get(1).as_f64()?))
+            })
+            .collect();
+        breakpoints.sort_by(|(_, a), (_, b)| a.total_cmp(b));
+
+        let mut state = self.state.borrow_mut();
+        state.breakpoints = breakpoints;
+        state.active = state.resolve();
+    }
+
+    pub fn active(&self) -> Option<String> {
+        self.state.borrow().active.clone()
+    }
+
+    pub fn disconnect(&self) {
+        self.observer.disconnect();
+    }
+}
@@ -0,0 +1,105 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Element as HTMLElement;
+use super::{timers, HIGHLIGHT_UPDATES_MS, HIGHLIGHT_OUTLINE, HIGHLIGHT_TIMER_KEY, HIGHLIGHT_OUTLINE_KEY};
+
+/// Diagnostics switched at runtime rather than at build time, so the same
+/// module serves staging with them and production without.
+#[derive(Clone, Copy)]
+pub struct DevMode {
+    // Console warnings about likely mistakes (ARIA, contentEditable, hotkeys)
+    pub warnings: bool,
+    // Components render twice on updates, the first output thrown away
+    pub strict_render: bool,
+    // Nodes written by a commit flash an outline
+    pub highlight_updates: bool,
+    // Reconciler invariants are checked before every commit
+    pub assertions: bool,
+}
+
+impl DevMode {
+    /// Warnings in debug builds, nothing in release builds
+    pub fn for_build() -> Self {
+        DevMode {
+            warnings: cfg!(debug_assertions),
+            strict_render: false,
+            highlight_updates: false,
+            assertions: false,
+        }
+    }
+
+    /// `true` or `false` switches everything. An object such as
+    /// `{ highlightUpdates: false }` picks, the flags it leaves out being on.
+    pub fn from_options(options: &JsValue) -> Self {
+        let flag = |name: &str| match options.as_bool() {
+            Some(enabled) => enabled,
+            None => js_sys::Reflect::get(options, &name.into()).ok()
+                .and_then(|value| value.as_bool())
+                .unwrap_or(true),
+        };
+
+        DevMode {
+            warnings: flag("warnings"),
+            strict_render: flag("strictRender"),
+            highlight_updates: flag("highlightUpdates"),
+            assertions: flag("assertions"),
+        }
+    }
+}
+
+/// Per-Context symbols a flashing node keeps its timer and its own outline
+/// under, so Contexts sharing a page don't cancel each other's flashes
+pub struct HighlightKeys {
+    timer: JsValue,
+    outline: JsValue,
+}
+
+impl HighlightKeys {
+    pub fn new() -> Self {
+        HighlightKeys {
+            timer: JsValue::symbol(Some(HIGHLIGHT_TIMER_KEY)),
+            outline: JsValue::symbol(Some(HIGHLIGHT_OUTLINE_KEY)),
+        }
+    }
+}
+
+/// Outlines `node` for HIGHLIGHT_UPDATES_MS, then puts its own outline back.
+/// A node written again while flashing starts over.
+pub fn highlight(node: &HTMLElement, keys: &HighlightKeys) {
+    let style = match node.dyn_ref::<web_sys::HtmlElement>() {
+        Some(element) => element.style(),
+        None => match node.dyn_ref::<web_sys::SvgElement>() {
+            Some(element) => element.style(),
+            None => return,
+        },
+    };
+
+    let timer_key = keys.timer.clone();
+    let outline_key = keys.outline.clone();
+
+    match js_sys::Reflect::get(node, &timer_key).ok().and_then(|timer| timer.as_f64()) {
+        Some(timer) => web_sys::window().unwrap().clear_timeout_with_handle(timer as i32),
+        None => {
+            let outline = style.get_property_value("outline").unwrap_or_default();
+            let _ = js_sys::Reflect::set(node, &outline_key, &outline.into());
+            let _ = style.set_property("outline", HIGHLIGHT_OUTLINE);
+        },
+    }
+
+    let target = node.clone();
+    let timer = timers::set_timeout(HIGHLIGHT_UPDATES_MS, {
+        let timer_key = timer_key.clone();
+
+        move || {
+            let outline = js_sys::Reflect::get(&target, &outline_key).ok()
+                .and_then(|outline| outline.as_string())
+                .unwrap_or_default();
+
+            let _ = style.set_property("outline", &outline);
+            let _ = js_sys::Reflect::delete_property(target.unchecked_ref::<js_sys::Object>(), &outline_key);
+            let _ = js_sys::Reflect::delete_property(target.unchecked_ref::<js_sys::Object>(), &timer_key);
+        }
+    });
+
+    let _ = js_sys::Reflect::set(node, &timer_key, &timer.into());
+}
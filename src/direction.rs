@@ -0,0 +1,66 @@
+use std::rc::Rc;
+use web_sys::Document;
+use super::{Fiber, FiberParentIterator, LOGICAL_STYLE_PROPERTIES};
+
+/// Text direction of a subtree
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// `auto` leaves the direction to the content, so it doesn't count
+    pub fn from_attribute(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "ltr" => Some(Direction::Ltr),
+            "rtl" => Some(Direction::Rtl),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+
+    /// Direction `fiber` renders in: the closest `dir` attribute from the
+    /// fiber outwards, then the document's, left to right without any.
+    pub fn of(fiber: &Fiber, document: &Document) -> Self {
+        let own = dir_of(fiber);
+
+        let inherited = || fiber.parent().as_ref().and_then(|parent| {
+            std::iter::once(Rc::clone(parent))
+                .chain(parent.parents())
+                .find_map(|ancestor| dir_of(&ancestor.borrow()))
+        });
+
+        let document_dir = || document.document_element()
+            .and_then(|root| root.get_attribute("dir"))
+            .and_then(|dir| Direction::from_attribute(&dir));
+
+        own.or_else(inherited).or_else(document_dir).unwrap_or(Direction::Ltr)
+    }
+}
+
+fn dir_of(fiber: &Fiber) -> Option<Direction> {
+    fiber.props()
+        .and_then(|props| props.attributes().get("dir"))
+        .and_then(|dir| Direction::from_attribute(dir))
+}
+
+/// Whether the `dir` attribute of a host fiber changed since its last commit
+pub fn has_changed(fiber: &Fiber, alternate: &Fiber) -> bool {
+    dir_of(fiber) != dir_of(alternate)
+}
+
+/// Physical property a logical style property maps to in `direction`, such
+/// as `margin-inline-start` to `margin-right` in right to left text. Other
+/// properties are kept as they are.
+pub fn physical_property(name: &str, direction: Direction) -> &str {
+    LOGICAL_STYLE_PROPERTIES.iter()
+        .find(|(logical, _, _)| *logical == name)
+        .map_or(name, |(_, ltr, rtl)| if direction == Direction::Rtl { rtl } else { ltr })
+}
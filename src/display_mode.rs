@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+
+/// Browser modes taking a single element out of the page layout
+#[derive(Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    Fullscreen,
+    PictureInPicture,
+}
+
+impl DisplayMode {
+    fn change_events(self) -> &'static [&'static str] {
+        match self {
+            DisplayMode::Fullscreen => &["fullscreenchange"],
+            DisplayMode::PictureInPicture => &["enterpictureinpicture", "leavepictureinpicture"],
+        }
+    }
+
+    fn active_element_key(self) -> &'static str {
+        match self {
+            DisplayMode::Fullscreen => "fullscreenElement",
+            DisplayMode::PictureInPicture => "pictureInPictureElement",
+        }
+    }
+
+    fn request_key(self) -> &'static str {
+        match self {
+            DisplayMode::Fullscreen => "requestFullscreen",
+            DisplayMode::PictureInPicture => "requestPictureInPicture",
+        }
+    }
+
+    fn exit_key(self) -> &'static str {
+        match self {
+            DisplayMode::Fullscreen => "exitFullscreen",
+            DisplayMode::PictureInPicture => "exitPictureInPicture",
+        }
+    }
+
+    // Read through Reflect: Picture-in-Picture isn't available everywhere
+    fn active_element(self, document: &Document) -> JsValue {
+        js_sys::Reflect::get(document, &self.active_element_key().into()).unwrap_or(JsValue::NULL)
+    }
+
+    // The returned promise rejects when the browser refuses, e.g. outside of
+    // a user gesture
+    fn request(self, target: &JsValue) -> JsValue {
+        match js_sys::Reflect::get(target, &self.request_key().into()).ok().and_then(|f| f.dyn_into::<js_sys::Function>().ok()) {
+            Some(request) => request.call0(target).unwrap_or_else(|error| js_sys::Promise::reject(&error).into()),
+            None => js_sys::Promise::reject(&JsValue::from_str("Reactron: display mode not supported")).into(),
+        }
+    }
+
+    fn exit(self, document: &Document) -> JsValue {
+        match js_sys::Reflect::get(document, &self.exit_key().into()).ok().and_then(|f| f.dyn_into::<js_sys::Function>().ok()) {
+            Some(exit) => exit.call0(document).unwrap_or_else(|error| js_sys::Promise::reject(&error).into()),
+            None => js_sys::Promise::resolve(&JsValue::UNDEFINED).into(),
+        }
+    }
+}
+
+/// State of a `use_fullscreen` or `use_picture_in_picture` hook: the element
+/// of the latest render, the stable `enter`/`exit` functions handed to JS and
+/// the document listeners re-rendering the tree when the mode changes.
+#[derive(Clone)]
+pub struct DisplayModeBinding {
+    mode: DisplayMode,
+    document: Document,
+    target: Rc<RefCell<JsValue>>,
+    enter: js_sys::Function,
+    exit: js_sys::Function,
+    on_change: js_sys::Function,
+}
+
+impl DisplayModeBinding {
+    pub fn mount(mode: DisplayMode, document: Document, on_change: impl FnMut() + 'static) -> Self {
+        let target = Rc::new(RefCell::new(JsValue::NULL));
+
+        let enter: js_sys::Function = {
+            let target = Rc::clone(&target);
+
+            Closure::wrap(Box::new(move || -> JsValue {
+                let target = target.borrow();
+
+                if target.is_null() {
+                    return js_sys::Promise::reject(&JsValue::from_str("Reactron: no element to display")).into();
+                }
+
+                mode.request(&target)
+            }) as Box<dyn FnMut() -> JsValue>).into_js_value().unchecked_into()
+        };
+
+        let exit: js_sys::Function = {
+            let target = Rc::clone(&target);
+            let document = document.clone();
+
+            // Only leaves the mode when it's this hook's element that holds it
+            Closure::wrap(Box::new(move || -> JsValue {
+                if mode.active_element(&document) == *target.borrow() {
+                    mode.exit(&document)
+                } else {
+                    js_sys::Promise::resolve(&JsValue::UNDEFINED).into()
+                }
+            }) as Box<dyn FnMut() -> JsValue>).into_js_value().unchecked_into()
+        };
+
+        let on_change: js_sys::Function = Closure::wrap(Box::new(on_change) as Box<dyn FnMut()>)
+            .into_js_value()
+            .unchecked_into();
+
+        for event_type in mode.change_events() {
+            document.add_event_listener_with_callback_and_bool(event_type, &on_change, true).unwrap();
+        }
+
+        DisplayModeBinding { mode, document, target, enter, exit, on_change }
+    }
+
+    pub fn mode(&self) -> DisplayMode {
+        self.mode
+    }
+
+    pub fn set_target(&self, target: JsValue) {
+        self.target.replace(target);
+    }
+
+    pub fn is_active(&self) -> bool {
+        let target = self.target.borrow();
+        !target.is_null() && self.mode.active_element(&self.document) == *target
+    }
+
+    pub fn enter(&self) -> &js_sys::Function {
+        &self.enter
+    }
+
+    pub fn exit(&self) -> &js_sys::Function {
+        &self.exit
+    }
+
+    /// Removes the listeners and leaves the mode if this hook's element
+    /// still holds it.
+    pub fn unmount(&self) {
+        for event_type in self.mode.change_events() {
+            let _ = self.document.remove_event_listener_with_callback_and_bool(event_type, &self.on_change, true);
+        }
+
+        let _ = self.exit.call0(&JsValue::null());
+    }
+}
@@ -0,0 +1,47 @@
+use wasm_bindgen::prelude::*;
+use web_sys::Element as HTMLElement;
+use super::DOM_PROPERTIES;
+
+/// Where a plain prop is written on its element
+#[derive(Clone, Copy, PartialEq)]
+pub enum PropTarget {
+    Attribute,
+    Property,
+}
+
+/// Looks the prop up in the DOM_PROPERTIES table first. Props of custom
+/// elements that the element defines as a property (`name in element`) are
+/// written as properties too, so its setters see them; everything else is an
+/// attribute. A `style` string is always the attribute, diffed as a whole.
+pub fn resolve(node: &HTMLElement, name: &str) -> PropTarget {
+    let tag_name = node.local_name();
+
+    let in_table = DOM_PROPERTIES.iter()
+        .any(|(element_type, prop_name)| *prop_name == name && (*element_type == "*" || *element_type == tag_name));
+
+    if in_table || (is_custom_element(&tag_name) && is_defined_property(node, name)) {
+        PropTarget::Property
+    } else {
+        PropTarget::Attribute
+    }
+}
+
+/// The value a built-in property has on a freshly created element of the same
+/// tag, such as "" for an input's `value` or 1 for a media element's `volume`
+pub fn default_value(node: &HTMLElement, name: &str) -> JsValue {
+    node.owner_document()
+        .and_then(|document| document.create_element(&node.local_name()).ok())
+        .and_then(|pristine| js_sys::Reflect::get(&pristine, &JsValue::from_str(name)).ok())
+        .unwrap_or(JsValue::UNDEFINED)
+}
+
+pub fn is_custom_element(tag_name: &str) -> bool {
+    tag_name.contains('-')
+}
+
+// `style` is defined on every element, but clearing it as a property would
+// write "undefined" into its `cssText`
+fn is_defined_property(node: &HTMLElement, name: &str) -> bool {
+    name != "style" && !name.starts_with("aria-") && !name.starts_with("data-") &&
+        js_sys::Reflect::has(node, &JsValue::from_str(name)).unwrap_or(false)
+}
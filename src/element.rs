@@ -1,13 +1,29 @@
+use std::collections::{BTreeMap, HashSet};
 use std::rc::Rc;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
 use web_sys::{Element as HTMLElement, Text as HTMLText};
-use super::{TEXT_ELEMENT, FIBER_FUNCTIONAL};
+use super::media::MediaSource;
+use super::{TEXT_ELEMENT, FIBER_FUNCTIONAL, PORTAL_ELEMENT, RESERVED_PROPS, BOOLEAN_ATTRIBUTES, BOOLEAN_PROPERTIES, ENUMERATED_ATTRIBUTES, PASSIVE_EVENTS, style};
 
 pub enum Node {
     Text(HTMLText),
     Element(HTMLElement),
+    // Only ever the root container
+    Shadow(web_sys::ShadowRoot),
 }
 
+impl Node {
+    pub fn as_js_value(&self) -> &JsValue {
+        match self {
+            Node::Text(text) => text.as_ref(),
+            Node::Element(element) => element.as_ref(),
+            Node::Shadow(shadow_root) => shadow_root.as_ref(),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Element {
     element_type: String,
     component_function: Option<Rc<js_sys::Function>>,
@@ -68,23 +84,95 @@ impl Element {
     pub fn from_ptr(ptr: *mut Element) -> Box<Element> {
         unsafe { Box::from_raw(ptr) }
     }
+
+    /// Frees an element tree that is thrown away, except for the elements in
+    /// `borrowed` and their subtrees: a component that forwards the elements
+    /// of its props places them in its output, while its parent still owns
+    /// them.
+    pub fn free_except(self: Box<Self>, borrowed: &HashSet<*const Element>) {
+        if borrowed.contains(&(&*self as *const Element)) {
+            let _ = Box::into_raw(self);
+            return;
+        }
+
+        let mut element = self;
+
+        for child in element.children.take().into_iter().flatten() {
+            child.free_except(borrowed);
+        }
+    }
 }
 
-#[derive(Eq)]
+#[derive(Clone, Eq)]
 pub struct ElementProps {
     class_name: Option<String>,
     node_value: Option<String>,
-    on_click: Option<js_sys::Function>,
-    on_change: Option<js_sys::Function>,
-    on_blur: Option<js_sys::Function>,
-    on_keydown: Option<js_sys::Function>,
+    listeners: BTreeMap<String, Listener>,
+    capture_listeners: BTreeMap<String, Listener>,
 
     input_type: Option<String>,
     input_value: Option<String>,
     input_placeholder: Option<String>,
     input_checked: Option<bool>,
+    select_value: Option<Vec<String>>,
+
+    // Surface
+    draw: Option<js_sys::Function>,
+    context_type: Option<String>,
+
+    // Media
+    src_object: Option<MediaSource>,
+    playing: Option<bool>,
+
+    // Observers
+    on_resize: Option<js_sys::Function>,
+    on_intersect: Option<js_sys::Function>,
+
+    // Scroll position, written once the node is laid out
+    scroll_top: Option<i32>,
+    scroll_left: Option<i32>,
+
+    // Every other prop, written with `setAttribute`
+    attributes: BTreeMap<String, String>,
+
+    // Declarations of a `style` object, by CSS property name
+    style: Option<BTreeMap<String, String>>,
+
+    // Boolean IDL properties whose attribute only holds the default
+    properties: BTreeMap<String, bool>,
+
+    // Object values given to a custom element
+    rich_props: BTreeMap<String, PropValue>,
+
+    // Container the children of a portal are rendered into
+    portal_target: Option<HTMLElement>,
+
+    // Rich text
+    uncontrolled_children: bool,
+    suppress_content_editable_warning: bool,
+
+    // Deletion
+    on_before_delete: Option<js_sys::Function>,
+}
+
+/// A JS value compared with `Object.is`
+#[derive(Clone)]
+pub struct PropValue(JsValue);
+
+impl PropValue {
+    pub fn value(&self) -> &JsValue {
+        &self.0
+    }
 }
 
+impl PartialEq for PropValue {
+    fn eq(&self, other: &Self) -> bool {
+        js_sys::Object::is(&self.0, &other.0)
+    }
+}
+
+impl Eq for PropValue {}
+
 impl ElementProps {
     pub fn class_name(&self) -> Option<&String> {
         self.class_name.as_ref()
@@ -94,20 +182,22 @@ impl ElementProps {
         self.node_value.as_ref()
     }
 
-    pub fn on_click(&self) -> Option<&js_sys::Function> {
-        self.on_click.as_ref()
+    /// Event handler props, keyed by DOM event type
+    pub fn listeners(&self) -> &BTreeMap<String, Listener> {
+        &self.listeners
     }
 
-    pub fn on_change(&self) -> Option<&js_sys::Function> {
-        self.on_change.as_ref()
+    pub fn listener(&self, event_type: &str) -> Option<&Listener> {
+        self.listeners.get(event_type)
     }
 
-    pub fn on_blur(&self) -> Option<&js_sys::Function> {
-        self.on_blur.as_ref()
+    /// `on*Capture` handler props, keyed by DOM event type
+    pub fn capture_listeners(&self) -> &BTreeMap<String, Listener> {
+        &self.capture_listeners
     }
 
-    pub fn on_keydown(&self) -> Option<&js_sys::Function> {
-        self.on_keydown.as_ref()
+    pub fn capture_listener(&self, event_type: &str) -> Option<&Listener> {
+        self.capture_listeners.get(event_type)
     }
 
     pub fn input_type(&self) -> Option<&String> {
@@ -126,6 +216,92 @@ impl ElementProps {
         self.input_checked
     }
 
+    /// Values of the options a `<select>` has selected, several of them for
+    /// a multiple select given an array
+    pub fn select_value(&self) -> Option<&Vec<String>> {
+        self.select_value.as_ref()
+    }
+
+    pub fn draw(&self) -> Option<&js_sys::Function> {
+        self.draw.as_ref()
+    }
+
+    pub fn context_type(&self) -> Option<&String> {
+        self.context_type.as_ref()
+    }
+
+    /// Stream or Blob for the `srcObject` of an audio or video element
+    pub fn src_object(&self) -> Option<&MediaSource> {
+        self.src_object.as_ref()
+    }
+
+    pub fn playing(&self) -> Option<bool> {
+        self.playing
+    }
+
+    /// Called with the element's ResizeObserverEntry, not a DOM event
+    pub fn on_resize(&self) -> Option<&js_sys::Function> {
+        self.on_resize.as_ref()
+    }
+
+    /// Called with the element's IntersectionObserverEntry, not a DOM event
+    pub fn on_intersect(&self) -> Option<&js_sys::Function> {
+        self.on_intersect.as_ref()
+    }
+
+    pub fn scroll_top(&self) -> Option<i32> {
+        self.scroll_top
+    }
+
+    pub fn scroll_left(&self) -> Option<i32> {
+        self.scroll_left
+    }
+
+    /// Plain attributes (`id`, `href`, `data-*`, `aria-*`...), keyed by
+    /// attribute name
+    pub fn attributes(&self) -> &BTreeMap<String, String> {
+        &self.attributes
+    }
+
+    pub fn style(&self) -> Option<&BTreeMap<String, String>> {
+        self.style.as_ref()
+    }
+
+    /// `selected`, `muted`... written to the node as properties
+    pub fn properties(&self) -> &BTreeMap<String, bool> {
+        &self.properties
+    }
+
+    /// Objects and arrays handed to a custom element, which only make sense
+    /// as JS properties
+    pub fn rich_props(&self) -> &BTreeMap<String, PropValue> {
+        &self.rich_props
+    }
+
+    pub fn portal_target(&self) -> Option<&HTMLElement> {
+        self.portal_target.as_ref()
+    }
+
+    pub fn is_content_editable(&self) -> bool {
+        self.attributes.get("contenteditable").is_some_and(|value| value != "false")
+    }
+
+    /// Whether the children are only rendered on mount, leaving the content
+    /// to the user afterwards
+    pub fn uncontrolled_children(&self) -> bool {
+        self.uncontrolled_children
+    }
+
+    pub fn suppress_content_editable_warning(&self) -> bool {
+        self.suppress_content_editable_warning
+    }
+
+    /// Called with the element before its removal; a returned Promise delays
+    /// the removal until it settles
+    pub fn on_before_delete(&self) -> Option<&js_sys::Function> {
+        self.on_before_delete.as_ref()
+    }
+
     pub fn from_ptr(ptr: *mut ElementProps) -> Box<ElementProps> {
         unsafe { Box::from_raw(ptr) }
     }
@@ -135,14 +311,29 @@ impl PartialEq for ElementProps {
     fn eq(&self, other: &Self) -> bool {
         self.class_name == other.class_name &&
         self.node_value == other.node_value &&
-        self.on_click == other.on_click &&
-        self.on_change == other.on_change &&
-        self.on_blur == other.on_blur &&
-        self.on_keydown == other.on_keydown &&
+        self.listeners == other.listeners &&
+        self.capture_listeners == other.capture_listeners &&
         self.input_type == other.input_type &&
         self.input_value == other.input_value &&
         self.input_placeholder == other.input_placeholder &&
-        self.input_checked == self.input_checked
+        self.input_checked == other.input_checked &&
+        self.select_value == other.select_value &&
+        self.draw == other.draw &&
+        self.context_type == other.context_type &&
+        self.src_object == other.src_object &&
+        self.playing == other.playing &&
+        self.on_resize == other.on_resize &&
+        self.on_intersect == other.on_intersect &&
+        self.scroll_top == other.scroll_top &&
+        self.scroll_left == other.scroll_left &&
+        self.attributes == other.attributes &&
+        self.style == other.style &&
+        self.properties == other.properties &&
+        self.rich_props == other.rich_props &&
+        self.portal_target == other.portal_target &&
+        self.uncontrolled_children == other.uncontrolled_children &&
+        self.suppress_content_editable_warning == other.suppress_content_editable_warning &&
+        self.on_before_delete == other.on_before_delete
     }
 }
 
@@ -155,7 +346,7 @@ pub fn create_element(
     let props = ElementProps::from_ptr(props_ptr);
 
     let children = children_ptr.iter()
-        .map(|ptr| Element::from_ptr(ptr.clone() as *mut Element))
+        .map(|ptr| Element::from_ptr(*ptr as *mut Element))
         .collect::<Vec<Box<Element>>>();
 
     let element = Element::new(
@@ -174,14 +365,29 @@ pub fn create_text_element(value: String) -> *mut Element {
     let props = ElementProps {
         class_name: None,
         node_value: Some(value),
-        on_click: None,
-        on_change: None,
-        on_blur: None,
-        on_keydown: None,
+        listeners: BTreeMap::new(),
+        capture_listeners: BTreeMap::new(),
         input_type: None,
         input_checked: None,
+        select_value: None,
         input_placeholder: None,
         input_value: None,
+        draw: None,
+        context_type: None,
+        src_object: None,
+        playing: None,
+        on_resize: None,
+        on_intersect: None,
+        scroll_top: None,
+        scroll_left: None,
+        attributes: BTreeMap::new(),
+        style: None,
+        properties: BTreeMap::new(),
+        rich_props: BTreeMap::new(),
+        portal_target: None,
+        uncontrolled_children: false,
+        suppress_content_editable_warning: false,
+        on_before_delete: None,
     };
 
     let element = Element::new(
@@ -195,6 +401,26 @@ pub fn create_text_element(value: String) -> *mut Element {
     Box::into_raw(Box::new(element))
 }
 
+/// Element rendering `children` into `target` instead of its parent's DOM
+/// node. The children stay part of the fiber tree, so events bubble to the
+/// portal's ancestors.
+#[wasm_bindgen]
+pub fn create_portal(children_ptr: &[u32], target: HTMLElement) -> *mut Element {
+    let children = children_ptr.iter()
+        .map(|ptr| Element::from_ptr(*ptr as *mut Element))
+        .collect::<Vec<Box<Element>>>();
+
+    Box::into_raw(portal(children, target))
+}
+
+/// A portal rendering `children` into `target`
+pub fn portal(children: Vec<Box<Element>>, target: HTMLElement) -> Box<Element> {
+    let mut props = ElementProps::from_ptr(create_props_from_object(&js_sys::Object::new().into(), None));
+    props.portal_target = Some(target);
+
+    Box::new(Element::new(String::from(PORTAL_ELEMENT), None, None, Some(props), Some(children)))
+}
+
 #[wasm_bindgen]
 pub fn create_functional_component(func: js_sys::Function, props: JsValue) -> *mut Element {
     let element = Element::new(
@@ -209,6 +435,7 @@ pub fn create_functional_component(func: js_sys::Function, props: JsValue) -> *m
 }
 
 #[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
 pub fn create_props(
     class_name: Option<String>,
     node_value: Option<String>,
@@ -221,17 +448,571 @@ pub fn create_props(
     input_checked: Option<bool>,
     input_placeholder: Option<String>,
 ) -> *mut ElementProps {
+    let listeners = vec![
+        ("click", on_click),
+        ("change", on_change),
+        ("blur", on_blur),
+        ("keydown", on_keydown),
+    ];
+
     let props = ElementProps {
         class_name,
         node_value,
-        on_click,
-        on_change,
-        on_blur,
-        on_keydown,
+        listeners: listeners.into_iter()
+            .filter_map(|(event_type, listener)| listener.map(|listener| (String::from(event_type), Listener::new(listener))))
+            .collect(),
+        capture_listeners: BTreeMap::new(),
         input_type,
         input_value,
         input_placeholder,
         input_checked,
+        select_value: None,
+        draw: None,
+        context_type: None,
+        src_object: None,
+        playing: None,
+        on_resize: None,
+        on_intersect: None,
+        scroll_top: None,
+        scroll_left: None,
+        attributes: BTreeMap::new(),
+        style: None,
+        properties: BTreeMap::new(),
+        rich_props: BTreeMap::new(),
+        portal_target: None,
+        uncontrolled_children: false,
+        suppress_content_editable_warning: false,
+        on_before_delete: None,
     };
     Box::into_raw(Box::new(props))
 }
+
+/// A handler prop along with the options its listener is registered with
+#[derive(Clone, PartialEq, Eq)]
+pub struct Listener {
+    handler: js_sys::Function,
+    passive: bool,
+    once: bool,
+    prevent_default: bool,
+    custom: bool,
+}
+
+impl Listener {
+    pub fn new(handler: js_sys::Function) -> Self {
+        Listener {
+            handler,
+            passive: false,
+            once: false,
+            prevent_default: false,
+            custom: false,
+        }
+    }
+
+    pub fn handler(&self) -> &js_sys::Function {
+        &self.handler
+    }
+
+    pub fn passive(&self) -> bool {
+        self.passive
+    }
+
+    pub fn once(&self) -> bool {
+        self.once
+    }
+
+    pub fn prevent_default(&self) -> bool {
+        self.prevent_default
+    }
+
+    // Listens to an event named by the app rather than by the DOM, which may
+    // or may not bubble
+    pub fn custom(&self) -> bool {
+        self.custom
+    }
+}
+
+/// Parses an `on*` prop into its DOM event type, whether it is a capture
+/// handler and its listener. Options are read from `Capture`, `Passive`,
+/// `Once` and `PreventDefault` suffixes (`onTouchMovePassive`) or from an
+/// options object (`onScroll={{ handler, passive: true }}`). `on:name` props
+/// listen to the event named exactly `name`, such as a `CustomEvent` from a
+/// web component. Scroll, wheel and touch handlers are passive unless given
+/// `passive: false` or `PreventDefault`. On custom elements, `onValueChanged` does the same for
+/// `valueChanged` unless it names a native event. Returns None for other
+/// props.
+fn parse_listener_prop(prop_name: &str, value: &JsValue, custom_element: bool) -> Option<(String, bool, Listener)> {
+    let custom_event_name = prop_name.strip_prefix("on:").filter(|name| !name.is_empty());
+    let mut event_name = prop_name.strip_prefix("on")?;
+
+    if custom_event_name.is_none() && !event_name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+
+    let read_flag = |name: &str| {
+        js_sys::Reflect::get(value, &name.into()).ok().and_then(|flag| flag.as_bool()).unwrap_or(false)
+    };
+
+    let mut listener = match value.dyn_ref::<js_sys::Function>() {
+        Some(handler) => Listener::new(handler.clone()),
+        None => {
+            let handler = js_sys::Reflect::get(value, &"handler".into()).ok()?.dyn_into().ok()?;
+
+            Listener {
+                handler,
+                passive: read_flag("passive"),
+                once: read_flag("once"),
+                prevent_default: read_flag("preventDefault"),
+                custom: false,
+            }
+        }
+    };
+    let mut capture = !value.is_function() && read_flag("capture");
+    let passive_option = js_sys::Reflect::get(value, &"passive".into()).ok().and_then(|flag| flag.as_bool())
+        .filter(|_| !value.is_function());
+
+    if let Some(event_name) = custom_event_name {
+        listener.custom = true;
+        return Some((String::from(event_name), capture, listener));
+    }
+
+    loop {
+        if let Some(name) = event_name.strip_suffix("Capture").filter(|name| !name.is_empty()) {
+            capture = true;
+            event_name = name;
+        } else if let Some(name) = event_name.strip_suffix("Passive").filter(|name| !name.is_empty()) {
+            listener.passive = true;
+            event_name = name;
+        } else if let Some(name) = event_name.strip_suffix("Once").filter(|name| !name.is_empty()) {
+            listener.once = true;
+            event_name = name;
+        } else if let Some(name) = event_name.strip_suffix("PreventDefault").filter(|name| !name.is_empty()) {
+            listener.prevent_default = true;
+            event_name = name;
+        } else {
+            break;
+        }
+    }
+
+    let event_type = match event_name.to_ascii_lowercase().as_str() {
+        "doubleclick" => String::from("dblclick"),
+        event_type => String::from(event_type),
+    };
+
+    if custom_element && !is_native_event(&event_type) {
+        let mut chars = event_name.chars();
+        let event_name: String = chars.next().map(|first| first.to_ascii_lowercase()).into_iter().chain(chars).collect();
+
+        listener.custom = true;
+        return Some((event_name, capture, listener));
+    }
+
+    if passive_option.is_none() && !listener.prevent_default && PASSIVE_EVENTS.contains(&event_type.as_str()) {
+        listener.passive = true;
+    }
+
+    Some((event_type, capture, listener))
+}
+
+// Native events have an `on*` handler property on every HTML element
+fn is_native_event(event_type: &str) -> bool {
+    let prototype = js_sys::Reflect::get(&js_sys::global(), &"HTMLElement".into())
+        .and_then(|constructor| js_sys::Reflect::get(&constructor, &"prototype".into()));
+
+    match prototype {
+        Ok(prototype) if prototype.is_object() => {
+            js_sys::Reflect::has(&prototype, &format!("on{}", event_type).into()).unwrap_or(false)
+        },
+        _ => true,
+    }
+}
+
+// What a prop value says as attribute text: a boolean, number or string,
+// anything else only being truthy or not
+#[derive(Clone)]
+enum PropScalar {
+    Bool(bool),
+    Number(f64),
+    Text(String),
+    Other(bool),
+}
+
+impl PropScalar {
+    fn of(value: &JsValue) -> Self {
+        match (value.as_bool(), value.as_f64(), value.as_string()) {
+            (Some(flag), _, _) => PropScalar::Bool(flag),
+            (_, Some(number), _) => PropScalar::Number(number),
+            (_, _, Some(text)) => PropScalar::Text(text),
+            _ => PropScalar::Other(value.is_truthy()),
+        }
+    }
+
+    fn is_truthy(&self) -> bool {
+        match self {
+            PropScalar::Bool(flag) | PropScalar::Other(flag) => *flag,
+            PropScalar::Number(number) => *number != 0.0 && !number.is_nan(),
+            PropScalar::Text(text) => !text.is_empty(),
+        }
+    }
+}
+
+fn attribute_value(prop_name: &str, value: &JsValue) -> Option<String> {
+    attribute_text(prop_name, PropScalar::of(value))
+}
+
+// Props that never reach the DOM as attributes: reconciler props and event
+// handlers that didn't parse as listeners. Boolean attributes are present
+// with an empty value or absent, while `aria-*`, `data-*` and enumerated
+// attributes such as `contenteditable` spell booleans out since
+// `aria-hidden=""` doesn't mean true and a missing `contenteditable` is
+// inherited rather than false.
+fn attribute_text(prop_name: &str, value: PropScalar) -> Option<String> {
+    if RESERVED_PROPS.contains(&prop_name) || prop_name.starts_with("on") {
+        return None;
+    }
+
+    if BOOLEAN_ATTRIBUTES.contains(&prop_name.to_ascii_lowercase().as_str()) {
+        return value.is_truthy().then(String::new);
+    }
+
+    let is_enumerated = prop_name.starts_with("aria-") || prop_name.starts_with("data-") ||
+        ENUMERATED_ATTRIBUTES.contains(&prop_name.to_ascii_lowercase().as_str());
+
+    match value {
+        PropScalar::Bool(flag) if is_enumerated => Some(flag.to_string()),
+        PropScalar::Bool(flag) => flag.then(String::new),
+        PropScalar::Number(number) => Some(number.to_string()),
+        PropScalar::Text(text) => Some(text),
+        PropScalar::Other(_) => None,
+    }
+}
+
+/// Attributes to write and to remove to get a node from `prev` to `next`
+pub struct AttributeChanges<'a> {
+    pub set: Vec<(&'a str, &'a str)>,
+    pub removed: Vec<&'a str>,
+}
+
+pub fn attribute_changes<'a>(prev: Option<&'a BTreeMap<String, String>>, next: &'a BTreeMap<String, String>) -> AttributeChanges<'a> {
+    let set = next.iter()
+        .filter(|(name, value)| prev.and_then(|prev| prev.get(*name)) != Some(*value))
+        .map(|(name, value)| (name.as_str(), value.as_str()))
+        .collect();
+
+    let removed = prev.into_iter()
+        .flat_map(|prev| prev.keys())
+        .filter(|name| !next.contains_key(*name))
+        .map(String::as_str)
+        .collect();
+
+    AttributeChanges { set, removed }
+}
+
+// A string, or an array of strings for a multiple select. Null and
+// undefined leave the select uncontrolled.
+fn select_values(value: &JsValue) -> Option<Vec<String>> {
+    match value.dyn_ref::<js_sys::Array>() {
+        Some(values) => Some(values.iter().filter_map(|value| text_value(&value)).collect()),
+        None => text_value(value).map(|value| vec![value]),
+    }
+}
+
+// A string, or a number written out the way attributes write it, so
+// `value={3}` shows "3"
+fn text_value(value: &JsValue) -> Option<String> {
+    value.as_string().or_else(|| value.as_f64().map(|number| number.to_string()))
+}
+
+// Flattens `className` values into a space separated string: arrays keep
+// their truthy items, objects the keys with truthy values, so
+// `["btn", active && "btn-active", { hidden: !show }]` works.
+fn class_list(value: &JsValue) -> Option<String> {
+    fn collect(value: &JsValue, classes: &mut Vec<String>) {
+        if !value.is_truthy() {
+            return;
+        }
+
+        if let Some(class_name) = value.as_string() {
+            classes.extend(class_name.split_whitespace().map(String::from));
+        } else if let Some(number) = value.as_f64() {
+            classes.push(number.to_string());
+        } else if let Some(items) = value.dyn_ref::<js_sys::Array>() {
+            items.iter().for_each(|item| collect(&item, classes));
+        } else if let Some(object) = value.dyn_ref::<js_sys::Object>() {
+            for entry in js_sys::Object::entries(object).iter() {
+                let entry: js_sys::Array = entry.unchecked_into();
+
+                if let (Some(class_name), true) = (entry.get(0).as_string(), entry.get(1).is_truthy()) {
+                    classes.push(class_name);
+                }
+            }
+        }
+    }
+
+    let mut classes = Vec::new();
+    collect(value, &mut classes);
+
+    if classes.is_empty() { None } else { Some(classes.join(" ")) }
+}
+
+// `xlinkHref` and `xmlLang` are accepted for `xlink:href` and `xml:lang`,
+// `contentEditable` for `contenteditable`
+fn attribute_name(prop_name: &str) -> String {
+    let namespaced = ["xlink", "xml"].iter().find_map(|prefix| {
+        let local_name = prop_name.strip_prefix(prefix)?;
+
+        match local_name.chars().next() {
+            Some(first) if first.is_ascii_uppercase() => {
+                Some(format!("{}:{}{}", prefix, first.to_ascii_lowercase(), &local_name[1..]))
+            },
+            _ => None,
+        }
+    });
+
+    let lowercase = prop_name.to_ascii_lowercase();
+
+    match prop_name {
+        "htmlFor" => String::from("for"),
+        _ if ENUMERATED_ATTRIBUTES.contains(&lowercase.as_str()) => lowercase,
+        _ => namespaced.unwrap_or_else(|| String::from(prop_name)),
+    }
+}
+
+/// Builds element props straight from a JSX props object. Functions under
+/// `on*` keys become event listeners. For custom elements (a `-` in
+/// `element_type`), object values are kept to be set as properties.
+#[wasm_bindgen]
+pub fn create_props_from_object(props: &JsValue, element_type: Option<String>) -> *mut ElementProps {
+    let custom_element = element_type.as_ref().is_some_and(|element_type| element_type.contains('-'));
+    let is_select = element_type.as_deref() == Some("select");
+
+    let mut element_props = ElementProps {
+        class_name: None,
+        node_value: None,
+        listeners: BTreeMap::new(),
+        capture_listeners: BTreeMap::new(),
+        input_type: None,
+        input_value: None,
+        input_placeholder: None,
+        input_checked: None,
+        select_value: None,
+        draw: None,
+        context_type: None,
+        src_object: None,
+        playing: None,
+        on_resize: None,
+        on_intersect: None,
+        scroll_top: None,
+        scroll_left: None,
+        attributes: BTreeMap::new(),
+        style: None,
+        properties: BTreeMap::new(),
+        rich_props: BTreeMap::new(),
+        portal_target: None,
+        uncontrolled_children: false,
+        suppress_content_editable_warning: false,
+        on_before_delete: None,
+    };
+
+    if let Some(props) = props.dyn_ref::<js_sys::Object>() {
+        for entry in js_sys::Object::entries(props).iter() {
+            let entry: js_sys::Array = entry.unchecked_into();
+            let name = entry.get(0).as_string().unwrap_or_default();
+            let value = entry.get(1);
+
+            if custom_element && value.is_object() && !name.starts_with("on") && !RESERVED_PROPS.contains(&name.as_str()) && name != "style" {
+                element_props.rich_props.insert(name, PropValue(value));
+                continue;
+            }
+
+            match name.as_str() {
+                "className" | "class" => element_props.class_name = class_list(&value),
+                "nodeValue" => element_props.node_value = value.as_string(),
+                "type" => element_props.input_type = value.as_string(),
+                "value" if is_select => element_props.select_value = select_values(&value),
+                "value" => element_props.input_value = text_value(&value),
+                "placeholder" => element_props.input_placeholder = text_value(&value),
+                "checked" => element_props.input_checked = value.as_bool(),
+                "draw" => element_props.draw = value.dyn_into().ok(),
+                "contextType" => element_props.context_type = value.as_string(),
+                "srcObject" => element_props.src_object = MediaSource::from_value(value),
+                "playing" => element_props.playing = value.as_bool(),
+                "onResize" => element_props.on_resize = value.dyn_into().ok(),
+                "onIntersect" => element_props.on_intersect = value.dyn_into().ok(),
+                // Saturating, so `Infinity` pins the end
+                "scrollTop" => element_props.scroll_top = value.as_f64().map(|offset| offset as i32),
+                "scrollLeft" => element_props.scroll_left = value.as_f64().map(|offset| offset as i32),
+                "onBeforeDelete" => element_props.on_before_delete = value.dyn_into().ok(),
+                "uncontrolledChildren" => element_props.uncontrolled_children = value.is_truthy(),
+                "suppressContentEditableWarning" => element_props.suppress_content_editable_warning = value.is_truthy(),
+                name if BOOLEAN_PROPERTIES.contains(&name) => {
+                    element_props.properties.insert(String::from(name), value.is_truthy());
+                },
+                // A style string is written as the plain attribute
+                "style" if value.is_object() => element_props.style = Some(style::parse_style(value.unchecked_ref())),
+                _ => {
+                    if let Some((event_type, capture, listener)) = parse_listener_prop(&name, &value, custom_element) {
+                        if capture {
+                            element_props.capture_listeners.insert(event_type, listener);
+                        } else {
+                            element_props.listeners.insert(event_type, listener);
+                        }
+                    } else if let Some(attribute_value) = attribute_value(&name, &value) {
+                        element_props.attributes.insert(attribute_name(&name), attribute_value);
+                    }
+                }
+            }
+        }
+    }
+
+    Box::into_raw(Box::new(element_props))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn div(children: Vec<Box<Element>>) -> Box<Element> {
+        Box::new(Element::new(String::from("div"), None, None, None, Some(children)))
+    }
+
+    fn attributes(pairs: &[(&str, PropScalar)]) -> BTreeMap<String, String> {
+        pairs.iter()
+            .filter_map(|(name, value)| attribute_text(name, value.clone()).map(|text| (String::from(*name), text)))
+            .collect()
+    }
+
+    #[test]
+    fn boolean_attributes_are_present_or_absent() {
+        assert_eq!(attribute_text("disabled", PropScalar::Bool(true)), Some(String::new()));
+        assert_eq!(attribute_text("disabled", PropScalar::Bool(false)), None);
+        assert_eq!(attribute_text("hidden", PropScalar::Text(String::from("hidden"))), Some(String::new()));
+        assert_eq!(attribute_text("hidden", PropScalar::Text(String::new())), None);
+        assert_eq!(attribute_text("autofocus", PropScalar::Number(0.0)), None);
+        assert_eq!(attribute_text("readOnly", PropScalar::Other(true)), Some(String::new()));
+    }
+
+    #[test]
+    fn enumerated_attributes_spell_booleans_out() {
+        assert_eq!(attribute_text("aria-hidden", PropScalar::Bool(true)), Some(String::from("true")));
+        assert_eq!(attribute_text("aria-hidden", PropScalar::Bool(false)), Some(String::from("false")));
+        assert_eq!(attribute_text("data-open", PropScalar::Bool(false)), Some(String::from("false")));
+        assert_eq!(attribute_text("contenteditable", PropScalar::Bool(false)), Some(String::from("false")));
+    }
+
+    #[test]
+    fn other_props_keep_their_text() {
+        assert_eq!(attribute_text("tabindex", PropScalar::Number(3.0)), Some(String::from("3")));
+        assert_eq!(attribute_text("title", PropScalar::Text(String::from("Save"))), Some(String::from("Save")));
+        assert_eq!(attribute_text("title", PropScalar::Other(true)), None);
+        assert_eq!(attribute_text("key", PropScalar::Text(String::from("a"))), None);
+        assert_eq!(attribute_text("onclick", PropScalar::Text(String::from("alert(1)"))), None);
+    }
+
+    #[test]
+    fn toggling_a_boolean_across_renders() {
+        let enabled = attributes(&[("disabled", PropScalar::Bool(false)), ("title", PropScalar::Text(String::from("Save")))]);
+        let disabled = attributes(&[("disabled", PropScalar::Bool(true)), ("title", PropScalar::Text(String::from("Save")))]);
+
+        let mount = attribute_changes(None, &enabled);
+        assert_eq!(mount.set, vec![("title", "Save")]);
+        assert!(mount.removed.is_empty());
+
+        let disable = attribute_changes(Some(&enabled), &disabled);
+        assert_eq!(disable.set, vec![("disabled", "")]);
+        assert!(disable.removed.is_empty());
+
+        let enable = attribute_changes(Some(&disabled), &enabled);
+        assert!(enable.set.is_empty());
+        assert_eq!(enable.removed, vec!["disabled"]);
+
+        let unchanged = attribute_changes(Some(&enabled), &enabled);
+        assert!(unchanged.set.is_empty() && unchanged.removed.is_empty());
+    }
+
+    #[test]
+    fn reflected_booleans_are_properties() {
+        for name in ["selected", "muted"] {
+            assert!(BOOLEAN_PROPERTIES.contains(&name));
+            assert!(!BOOLEAN_ATTRIBUTES.contains(&name));
+        }
+    }
+
+    // A wrapper component under strict rendering is called twice with the
+    // same props, and both outputs take its children
+    #[test]
+    fn discarded_output_leaves_forwarded_children_alone() {
+        let child_ptr = create_text_element(String::from("forwarded"));
+        let borrowed = HashSet::from([child_ptr as *const Element]);
+
+        div(vec![Element::from_ptr(child_ptr), Element::from_ptr(create_text_element(String::from("own")))])
+            .free_except(&borrowed);
+
+        let output = div(vec![Element::from_ptr(child_ptr)]);
+        let child = &output.children().as_ref().unwrap()[0];
+        assert_eq!(child.element_type(), TEXT_ELEMENT);
+        assert_eq!(child.props().as_ref().and_then(|props| props.node_value()).map(String::as_str), Some("forwarded"));
+    }
+
+    fn props_with_checked(checked: Option<bool>) -> Box<ElementProps> {
+        let mut props = Element::from_ptr(create_text_element(String::new())).props_mut().take().unwrap();
+        props.input_checked = checked;
+        props
+    }
+
+    #[test]
+    fn checked_changes_make_props_unequal() {
+        assert!(props_with_checked(Some(true)) == props_with_checked(Some(true)));
+        assert!(props_with_checked(Some(true)) != props_with_checked(Some(false)));
+        assert!(props_with_checked(None) != props_with_checked(Some(false)));
+    }
+}
+
+// Props parsing goes through JS values, so these run under
+// `wasm-pack test --node`
+#[cfg(all(test, target_arch = "wasm32"))]
+mod js_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    fn props_of(element_type: &str, entries: &[(&str, JsValue)]) -> Box<ElementProps> {
+        let props = js_sys::Object::new();
+        for (name, value) in entries {
+            js_sys::Reflect::set(&props, &JsValue::from_str(name), value).unwrap();
+        }
+
+        unsafe { Box::from_raw(create_props_from_object(&props.into(), Some(String::from(element_type)))) }
+    }
+
+    #[wasm_bindgen_test]
+    fn numeric_value_and_placeholder_are_written_out() {
+        let props = props_of("input", &[("value", 3.into()), ("placeholder", 2.5.into())]);
+
+        assert_eq!(props.input_value().map(String::as_str), Some("3"));
+        assert_eq!(props.input_placeholder().map(String::as_str), Some("2.5"));
+    }
+
+    #[wasm_bindgen_test]
+    fn text_value_and_placeholder_are_kept() {
+        let props = props_of("input", &[("value", "three".into()), ("placeholder", "".into())]);
+
+        assert_eq!(props.input_value().map(String::as_str), Some("three"));
+        assert_eq!(props.input_placeholder().map(String::as_str), Some(""));
+    }
+
+    #[wasm_bindgen_test]
+    fn other_values_are_left_unset() {
+        let props = props_of("input", &[("value", JsValue::NULL), ("placeholder", true.into())]);
+
+        assert_eq!(props.input_value(), None);
+        assert_eq!(props.input_placeholder(), None);
+    }
+
+    #[wasm_bindgen_test]
+    fn select_values_accept_numbers() {
+        let values = js_sys::Array::of2(&1.into(), &"two".into());
+        let props = props_of("select", &[("value", values.into())]);
+
+        assert_eq!(props.input_value(), None);
+        assert_eq!(props.select_value(), Some(&vec![String::from("1"), String::from("two")]));
+    }
+}
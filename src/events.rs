@@ -0,0 +1,486 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use super::{Listener, NON_BUBBLING_EVENTS, forms};
+
+/// Marks a DOM node as owned by the fiber with the given id, so delegated
+/// events can be routed back into the fiber tree. `key` is the Context's own
+/// symbol, so trees of other Contexts or fusion bundles nested in the same
+/// DOM never claim each other's nodes.
+pub fn tag_node(node: &JsValue, key: &JsValue, fiber_id: u32) {
+    js_sys::Reflect::set(node, key, &JsValue::from(fiber_id)).unwrap();
+}
+
+pub fn own_fiber_id(node: &JsValue, key: &JsValue) -> Option<u32> {
+    js_sys::Reflect::get(node, key).ok()
+        .and_then(|id| id.as_f64())
+        .map(|id| id as u32)
+}
+
+/// Finds the id of the fiber owning `target`, looking through its DOM
+/// ancestors when the target itself wasn't rendered by fusion.
+pub fn fiber_id_of(target: &web_sys::EventTarget, key: &JsValue) -> Option<u32> {
+    let mut node = target.dyn_ref::<web_sys::Node>().cloned();
+
+    while let Some(current) = node {
+        if let Some(fiber_id) = own_fiber_id(&current, key) {
+            return Some(fiber_id);
+        }
+
+        node = current.parent_node();
+    }
+
+    None
+}
+
+pub fn bubbles(event_type: &str) -> bool {
+    !NON_BUBBLING_EVENTS.contains(&event_type)
+}
+
+/// Which handler props a delegated root listener dispatches to
+#[derive(Clone, Copy, PartialEq)]
+pub enum DispatchPhase {
+    // `on*Capture` handlers, root first
+    Capture,
+    // `on*` handlers, target first
+    Bubble,
+    // Both in order, from a single capture-phase listener. Used for events
+    // that don't bubble, where the bubble pass only reaches the target.
+    Both,
+}
+
+impl DispatchPhase {
+    // Custom events are caught on the way down, since there is no telling
+    // whether they bubble before one is dispatched
+    pub fn for_listener(event_type: &str, listener: &Listener, use_capture: bool) -> Self {
+        match (bubbles(event_type) && !listener.custom(), use_capture) {
+            (false, _) => DispatchPhase::Both,
+            (true, true) => DispatchPhase::Capture,
+            (true, false) => DispatchPhase::Bubble,
+        }
+    }
+
+    pub fn use_capture(self) -> bool {
+        self != DispatchPhase::Bubble
+    }
+
+    pub fn includes_capture(self) -> bool {
+        self != DispatchPhase::Bubble
+    }
+
+    pub fn includes_bubble(self) -> bool {
+        self != DispatchPhase::Capture
+    }
+}
+
+/// Calls an event handler prop, reporting a thrown error instead of letting
+/// it escape into the browser's event dispatch and skip the other handlers.
+pub fn invoke_handler(handler: &js_sys::Function, event: &JsValue, extra: &JsValue, component_stack: &str) {
+    if let Err(error) = handler.call2(&JsValue::null(), event, extra) {
+        let message = format!("Reactron: an event handler threw an error{}", component_stack);
+        web_sys::console::error_2(&JsValue::from_str(&message), &error);
+    }
+}
+
+/// Dispatch state shared between a `SyntheticEvent` handed to JS and the
+/// delegation loop walking the fiber tree.
+pub struct DispatchState {
+    propagation_stopped: Cell<bool>,
+    current_target: RefCell<JsValue>,
+    current_target_fiber: Cell<Option<u32>>,
+}
+
+impl DispatchState {
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.propagation_stopped.get()
+    }
+
+    pub fn set_current_target(&self, current_target: JsValue, fiber_id: u32) {
+        self.current_target.replace(current_target);
+        self.current_target_fiber.set(Some(fiber_id));
+    }
+}
+
+/// Event object passed to handler props. Wraps the native event and follows
+/// the fiber tree rather than the DOM: `stopPropagation` halts the walk up
+/// the fibers and `currentTarget` is the node whose handler is running.
+#[wasm_bindgen]
+pub struct SyntheticEvent {
+    native: web_sys::Event,
+    state: Rc<DispatchState>,
+    // Value of the target form control, read once when the event is created
+    value: Option<String>,
+    target_fiber: Option<u32>,
+}
+
+impl SyntheticEvent {
+    pub fn new(native: web_sys::Event, target_fiber: Option<u32>) -> Self {
+        let state = DispatchState {
+            propagation_stopped: Cell::new(false),
+            current_target: RefCell::new(JsValue::NULL),
+            current_target_fiber: Cell::new(None),
+        };
+
+        let value = native.target()
+            .and_then(|target| target.dyn_into::<web_sys::Element>().ok())
+            .and_then(|target| forms::control_value(&target));
+
+        SyntheticEvent {
+            native,
+            state: Rc::new(state),
+            value,
+            target_fiber,
+        }
+    }
+
+    pub fn state(&self) -> Rc<DispatchState> {
+        Rc::clone(&self.state)
+    }
+
+    pub fn keyboard(&self) -> Option<KeyboardEvent> {
+        KeyboardEvent::from_event(&self.native)
+    }
+
+    pub fn mouse(&self) -> Option<MouseEvent> {
+        MouseEvent::from_event(&self.native)
+    }
+
+    pub fn pointer(&self) -> Option<PointerEvent> {
+        PointerEvent::from_event(&self.native)
+    }
+
+    pub fn drag(&self) -> Option<DragEvent> {
+        DragEvent::from_event(&self.native)
+    }
+
+    pub fn input(&self) -> Option<InputEvent> {
+        InputEvent::from_event(&self.native)
+    }
+
+    /// Value of the input, textarea or select the event was fired on
+    pub fn value(&self) -> Option<&str> {
+        self.value.as_deref()
+    }
+
+    pub fn modifiers(&self) -> Option<Modifiers> {
+        self.keyboard().map(|event| event.modifiers())
+            .or_else(|| self.mouse().map(|event| event.modifiers()))
+    }
+}
+
+/// State of the modifier keys when an input event fired
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Modifiers {
+    pub alt: bool,
+    pub ctrl: bool,
+    pub meta: bool,
+    pub shift: bool,
+}
+
+/// Typed view of a native `KeyboardEvent`
+pub struct KeyboardEvent(web_sys::KeyboardEvent);
+
+impl KeyboardEvent {
+    pub fn from_event(event: &web_sys::Event) -> Option<Self> {
+        event.dyn_ref::<web_sys::KeyboardEvent>().cloned().map(KeyboardEvent)
+    }
+
+    pub fn key(&self) -> String {
+        self.0.key()
+    }
+
+    pub fn code(&self) -> String {
+        self.0.code()
+    }
+
+    // Legacy numeric key code, still what most keydown handlers compare against
+    pub fn which(&self) -> u32 {
+        self.0.key_code()
+    }
+
+    pub fn repeat(&self) -> bool {
+        self.0.repeat()
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            alt: self.0.alt_key(),
+            ctrl: self.0.ctrl_key(),
+            meta: self.0.meta_key(),
+            shift: self.0.shift_key(),
+        }
+    }
+}
+
+/// Typed view of a native `MouseEvent`, including the pointer events
+/// inheriting from it
+pub struct MouseEvent(web_sys::MouseEvent);
+
+impl MouseEvent {
+    pub fn from_event(event: &web_sys::Event) -> Option<Self> {
+        event.dyn_ref::<web_sys::MouseEvent>().cloned().map(MouseEvent)
+    }
+
+    pub fn button(&self) -> i16 {
+        self.0.button()
+    }
+
+    pub fn buttons(&self) -> u16 {
+        self.0.buttons()
+    }
+
+    pub fn client_x(&self) -> i32 {
+        self.0.client_x()
+    }
+
+    pub fn client_y(&self) -> i32 {
+        self.0.client_y()
+    }
+
+    pub fn modifiers(&self) -> Modifiers {
+        Modifiers {
+            alt: self.0.alt_key(),
+            ctrl: self.0.ctrl_key(),
+            meta: self.0.meta_key(),
+            shift: self.0.shift_key(),
+        }
+    }
+}
+
+/// Typed view of a native `PointerEvent`. Coordinates and buttons come from
+/// its `MouseEvent` view.
+pub struct PointerEvent(web_sys::PointerEvent);
+
+impl PointerEvent {
+    pub fn from_event(event: &web_sys::Event) -> Option<Self> {
+        event.dyn_ref::<web_sys::PointerEvent>().cloned().map(PointerEvent)
+    }
+
+    pub fn pointer_id(&self) -> i32 {
+        self.0.pointer_id()
+    }
+
+    pub fn pointer_type(&self) -> String {
+        self.0.pointer_type()
+    }
+}
+
+#[wasm_bindgen]
+impl SyntheticEvent {
+    #[wasm_bindgen(getter = nativeEvent)]
+    pub fn native_event(&self) -> web_sys::Event {
+        self.native.clone()
+    }
+
+    #[wasm_bindgen(getter = type)]
+    pub fn event_type(&self) -> String {
+        self.native.type_()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn target(&self) -> JsValue {
+        self.native.target().map_or(JsValue::NULL, JsValue::from)
+    }
+
+    #[wasm_bindgen(getter = currentTarget)]
+    pub fn current_target(&self) -> JsValue {
+        self.state.current_target.borrow().clone()
+    }
+
+    #[wasm_bindgen(getter = defaultPrevented)]
+    pub fn default_prevented(&self) -> bool {
+        self.native.default_prevented()
+    }
+
+    #[wasm_bindgen(getter = timeStamp)]
+    pub fn time_stamp(&self) -> f64 {
+        self.native.time_stamp()
+    }
+
+    // Payload of a `CustomEvent`
+    #[wasm_bindgen(getter)]
+    pub fn detail(&self) -> JsValue {
+        self.native.dyn_ref::<web_sys::CustomEvent>()
+            .map_or(JsValue::UNDEFINED, |event| event.detail())
+    }
+
+    // Input fields read through the typed views below. They are undefined
+    // when the native event isn't of the matching kind.
+    #[wasm_bindgen(getter)]
+    pub fn key(&self) -> JsValue {
+        self.keyboard().map_or(JsValue::UNDEFINED, |event| event.key().into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn code(&self) -> JsValue {
+        self.keyboard().map_or(JsValue::UNDEFINED, |event| event.code().into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn which(&self) -> JsValue {
+        self.keyboard().map_or(JsValue::UNDEFINED, |event| event.which().into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn repeat(&self) -> JsValue {
+        self.keyboard().map_or(JsValue::UNDEFINED, |event| event.repeat().into())
+    }
+
+    #[wasm_bindgen(getter = altKey)]
+    pub fn alt_key(&self) -> JsValue {
+        self.modifiers().map_or(JsValue::UNDEFINED, |modifiers| modifiers.alt.into())
+    }
+
+    #[wasm_bindgen(getter = ctrlKey)]
+    pub fn ctrl_key(&self) -> JsValue {
+        self.modifiers().map_or(JsValue::UNDEFINED, |modifiers| modifiers.ctrl.into())
+    }
+
+    #[wasm_bindgen(getter = metaKey)]
+    pub fn meta_key(&self) -> JsValue {
+        self.modifiers().map_or(JsValue::UNDEFINED, |modifiers| modifiers.meta.into())
+    }
+
+    #[wasm_bindgen(getter = shiftKey)]
+    pub fn shift_key(&self) -> JsValue {
+        self.modifiers().map_or(JsValue::UNDEFINED, |modifiers| modifiers.shift.into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn button(&self) -> JsValue {
+        self.mouse().map_or(JsValue::UNDEFINED, |event| event.button().into())
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn buttons(&self) -> JsValue {
+        self.mouse().map_or(JsValue::UNDEFINED, |event| event.buttons().into())
+    }
+
+    #[wasm_bindgen(getter = clientX)]
+    pub fn client_x(&self) -> JsValue {
+        self.mouse().map_or(JsValue::UNDEFINED, |event| event.client_x().into())
+    }
+
+    #[wasm_bindgen(getter = clientY)]
+    pub fn client_y(&self) -> JsValue {
+        self.mouse().map_or(JsValue::UNDEFINED, |event| event.client_y().into())
+    }
+
+    #[wasm_bindgen(getter = pointerId)]
+    pub fn pointer_id(&self) -> JsValue {
+        self.pointer().map_or(JsValue::UNDEFINED, |event| event.pointer_id().into())
+    }
+
+    #[wasm_bindgen(getter = pointerType)]
+    pub fn pointer_type(&self) -> JsValue {
+        self.pointer().map_or(JsValue::UNDEFINED, |event| event.pointer_type().into())
+    }
+
+    /// Id of the fiber that rendered the target, or its closest rendered
+    /// ancestor
+    #[wasm_bindgen(getter = targetFiberId)]
+    pub fn target_fiber_id(&self) -> Option<u32> {
+        self.target_fiber
+    }
+
+    /// Id of the fiber whose handler is running
+    #[wasm_bindgen(getter = currentTargetFiberId)]
+    pub fn current_target_fiber_id(&self) -> Option<u32> {
+        self.state.current_target_fiber.get()
+    }
+
+    #[wasm_bindgen(getter = value)]
+    pub fn js_value(&self) -> JsValue {
+        self.value().map_or(JsValue::UNDEFINED, JsValue::from)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn data(&self) -> JsValue {
+        self.input().map_or(JsValue::UNDEFINED, |event| event.data().map_or(JsValue::NULL, JsValue::from))
+    }
+
+    #[wasm_bindgen(getter = inputType)]
+    pub fn input_type(&self) -> JsValue {
+        self.input().map_or(JsValue::UNDEFINED, |event| event.input_type().into())
+    }
+
+    #[wasm_bindgen(getter = isComposing)]
+    pub fn is_composing(&self) -> JsValue {
+        self.input().map_or(JsValue::UNDEFINED, |event| event.is_composing().into())
+    }
+
+    #[wasm_bindgen(getter = dataTransfer)]
+    pub fn data_transfer(&self) -> JsValue {
+        self.drag().and_then(|event| event.data_transfer()).map_or(JsValue::UNDEFINED, JsValue::from)
+    }
+
+    // Files dropped on the target, as an array of `File`
+    #[wasm_bindgen(getter)]
+    pub fn files(&self) -> JsValue {
+        self.drag().map_or(JsValue::UNDEFINED, |event| {
+            event.files().into_iter().map(JsValue::from).collect::<js_sys::Array>().into()
+        })
+    }
+
+    #[wasm_bindgen(js_name = preventDefault)]
+    pub fn prevent_default(&self) {
+        self.native.prevent_default();
+    }
+
+    #[wasm_bindgen(js_name = stopPropagation)]
+    pub fn stop_propagation(&self) {
+        self.state.propagation_stopped.set(true);
+        self.native.stop_propagation();
+    }
+
+    #[wasm_bindgen(js_name = isPropagationStopped)]
+    pub fn is_propagation_stopped(&self) -> bool {
+        self.state.is_propagation_stopped()
+    }
+}
+
+/// Typed view of a native `InputEvent`. The resulting value of the control is
+/// on the `SyntheticEvent` itself.
+pub struct InputEvent(web_sys::InputEvent);
+
+impl InputEvent {
+    pub fn from_event(event: &web_sys::Event) -> Option<Self> {
+        event.dyn_ref::<web_sys::InputEvent>().cloned().map(InputEvent)
+    }
+
+    // Inserted text, None for deletions and formatting
+    pub fn data(&self) -> Option<String> {
+        self.0.data()
+    }
+
+    pub fn input_type(&self) -> String {
+        self.0.input_type()
+    }
+
+    pub fn is_composing(&self) -> bool {
+        self.0.is_composing()
+    }
+}
+
+/// Typed view of a native `DragEvent`
+pub struct DragEvent(web_sys::DragEvent);
+
+impl DragEvent {
+    pub fn from_event(event: &web_sys::Event) -> Option<Self> {
+        event.dyn_ref::<web_sys::DragEvent>().cloned().map(DragEvent)
+    }
+
+    pub fn data_transfer(&self) -> Option<web_sys::DataTransfer> {
+        self.0.data_transfer()
+    }
+
+    /// Files carried by the drag. Browsers only expose them on `drop`.
+    pub fn files(&self) -> Vec<web_sys::File> {
+        let files = self.data_transfer().and_then(|data_transfer| data_transfer.files());
+
+        files.map_or_else(Vec::new, |files| {
+            (0..files.length()).filter_map(|index| files.get(index)).collect()
+        })
+    }
+}
@@ -1,11 +1,12 @@
 use wasm_bindgen::prelude::*;
 use std::cell::RefCell;
 use std::rc::Rc;
-use super::{Element, ElementProps, Node, TEXT_ELEMENT, FIBER_ROOT, FIBER_FUNCTIONAL};
+use super::{Element, ElementProps, HookCell, Node, AsyncResult, TEXT_ELEMENT, FIBER_ROOT, FIBER_FUNCTIONAL, SURFACE_ELEMENT, PORTAL_ELEMENT};
 
 pub type FiberCell = Rc<RefCell<Box<Fiber>>>;
 
 pub struct Fiber {
+    id: u32,
     _type: String,
     props: Option<Box<ElementProps>>,
     element_children: Option<Rc<RefCell<Vec<Box<Element>>>>>,
@@ -19,15 +20,18 @@ pub struct Fiber {
     // Functional
     component_function: Option<Rc<js_sys::Function>>,
     component_function_props: Option<Rc<JsValue>>,
+    async_result: Option<AsyncResult>,
+    owner: Option<Rc<str>>,
 
     // Hooks
-    hooks: Option<Vec<Rc<RefCell<JsValue>>>>,
+    hooks: Option<Vec<HookCell>>,
     hook_idx: u32,
 }
 
 impl Fiber {
     pub fn new(_type: &str) -> Self {
         Fiber {
+            id: 0u32,
             _type: String::from(_type),
             props: None,
             element_children: None,
@@ -39,6 +43,8 @@ impl Fiber {
             effect_tag: None,
             component_function: None,
             component_function_props: None,
+            async_result: None,
+            owner: None,
             hooks: None,
             hook_idx: 0u32,
         }
@@ -48,16 +54,56 @@ impl Fiber {
         Self::new(FIBER_ROOT)
     }
 
+    /// Identifies the component instance; carried over from the alternate on
+    /// every render.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn set_id(&mut self, id: u32) {
+        self.id = id;
+    }
+
     pub fn element_type(&self) -> &String {
         &self._type
     }
 
+    /// Component function name for functional fibers, element type otherwise
+    pub fn display_name(&self) -> String {
+        match self.component_function() {
+            Some(func) => func.name().as_string()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| String::from("Anonymous")),
+            None => self._type.clone(),
+        }
+    }
+
+    pub fn set_owner(&mut self, owner: Option<Rc<str>>) {
+        self.owner = owner;
+    }
+
+    /// Owner label of the closest component that has one, this fiber
+    /// included, for triaging diagnostics
+    pub fn owner(&self) -> Option<Rc<str>> {
+        self.owner.clone().or_else(|| self.parent().as_ref().and_then(|parent| parent.borrow().owner()))
+    }
+
     pub fn is_functional_tree(&self) -> bool {
-        &self._type == FIBER_FUNCTIONAL
+        self._type == FIBER_FUNCTIONAL
     }
 
     pub fn is_text_fiber(&self) -> bool {
-        &self._type == TEXT_ELEMENT
+        self._type == TEXT_ELEMENT
+    }
+
+    pub fn is_surface_fiber(&self) -> bool {
+        self._type == SURFACE_ELEMENT
+    }
+
+    /// Portals hold their target container as their DOM node, which belongs
+    /// to the page rather than to the fiber
+    pub fn is_portal_fiber(&self) -> bool {
+        self._type == PORTAL_ELEMENT
     }
 
     pub fn dom_node(&self) -> Option<&Rc<RefCell<Node>>> {
@@ -68,6 +114,30 @@ impl Fiber {
         self.dom_node.replace(dom_node);
     }
 
+    /// Names of the function components from this fiber outwards, innermost
+    /// first, formatted for error messages.
+    pub fn component_stack(&self) -> String {
+        let mut stack = String::new();
+
+        if self.is_functional_tree() {
+            stack.push_str(&format!("\n    in {}{}", self.display_name(), owner_note(self.owner.as_deref())));
+        }
+
+        if let Some(parent) = self.parent() {
+            let ancestors = std::iter::once(Rc::clone(parent)).chain(parent.parents());
+
+            for ancestor in ancestors {
+                let ancestor = ancestor.borrow();
+
+                if ancestor.is_functional_tree() {
+                    stack.push_str(&format!("\n    in {}{}", ancestor.display_name(), owner_note(ancestor.owner.as_deref())));
+                }
+            }
+        }
+
+        stack
+    }
+
     pub fn child(&self) -> &Option<FiberCell> {
         &self.child
     }
@@ -108,6 +178,10 @@ impl Fiber {
         self.alternate.replace(alternate);
     }
 
+    pub fn clear_alternate(&mut self) {
+        self.alternate = None;
+    }
+
     pub fn element_children(&self) -> &Option<Rc<RefCell<Vec<Box<Element>>>>> {
         &self.element_children
     }
@@ -148,20 +222,30 @@ impl Fiber {
         self.component_function_props = props;
     }
 
-    pub fn add_hook(&mut self, hook: Rc<RefCell<JsValue>>) {
+    pub fn async_result(&self) -> Option<&AsyncResult> {
+        self.async_result.as_ref()
+    }
+
+    pub fn set_async_result(&mut self, async_result: Option<AsyncResult>) {
+        self.async_result = async_result;
+    }
+
+    pub fn add_hook(&mut self, hook: HookCell) {
         if let Some(hooks) = &mut self.hooks {
             hooks.push(hook);
         }
     }
 
-    pub fn get_hook_at(&self, pos: usize) -> Option<Rc<RefCell<JsValue>>> {
-        self.hooks.as_ref().map_or(None, |hooks| {
-            hooks.get(pos).map_or(None, |hook| {
-                Some(Rc::clone(hook))
-            })
+    pub fn get_hook_at(&self, pos: usize) -> Option<HookCell> {
+        self.hooks.as_ref().and_then(|hooks| {
+            hooks.get(pos).map(Rc::clone)
         })
     }
 
+    pub fn hooks(&self) -> Option<&Vec<HookCell>> {
+        self.hooks.as_ref()
+    }
+
     pub fn hook_idx(&self) -> u32 {
         self.hook_idx
     }
@@ -170,7 +254,7 @@ impl Fiber {
         self.hook_idx += 1;
     }
 
-    pub fn set_hooks(&mut self, hooks: Option<Vec<Rc<RefCell<JsValue>>>>) {
+    pub fn set_hooks(&mut self, hooks: Option<Vec<HookCell>>) {
         self.hooks = hooks;
     }
 }
@@ -204,7 +288,108 @@ impl Iterator for FiberParentsIter {
         } else { None };
 
         self.next = next;
-        return result;
+        result
+    }
+}
+
+/// ` (owner: payments)` after a name in diagnostics, nothing for unowned
+/// components
+pub fn owner_note(owner: Option<&str>) -> String {
+    owner.map_or_else(String::new, |owner| format!(" (owner: {})", owner))
+}
+
+fn owner_value(fiber: &Fiber) -> JsValue {
+    fiber.owner().map_or(JsValue::NULL, |owner| JsValue::from_str(&owner))
+}
+
+/// Plain JS snapshot of a fiber and its descendants:
+/// `{ id, type, name, owner, children }`.
+pub fn describe_tree(fiber: &FiberCell) -> JsValue {
+    let fiber = fiber.borrow();
+    let children = js_sys::Array::new();
+
+    let mut child = fiber.child().as_ref().map(Rc::clone);
+    while let Some(current) = child {
+        children.push(&describe_tree(&current));
+        child = current.borrow().sibling().as_ref().map(Rc::clone);
+    }
+
+    let description = js_sys::Object::new();
+    js_sys::Reflect::set(&description, &"id".into(), &fiber.id().into()).unwrap();
+    js_sys::Reflect::set(&description, &"type".into(), &fiber.element_type().into()).unwrap();
+    js_sys::Reflect::set(&description, &"name".into(), &fiber.display_name().into()).unwrap();
+    js_sys::Reflect::set(&description, &"owner".into(), &owner_value(&fiber)).unwrap();
+    js_sys::Reflect::set(&description, &"children".into(), &children).unwrap();
+
+    description.into()
+}
+
+/// Plain JS description of a fiber without its descendants:
+/// `{ id, type, name, owner, parentId }`.
+pub fn describe_fiber(fiber: &FiberCell) -> JsValue {
+    let fiber = fiber.borrow();
+    let parent_id = fiber.parent().as_ref().map_or(JsValue::NULL, |parent| parent.borrow().id().into());
+
+    let description = js_sys::Object::new();
+    js_sys::Reflect::set(&description, &"id".into(), &fiber.id().into()).unwrap();
+    js_sys::Reflect::set(&description, &"type".into(), &fiber.element_type().into()).unwrap();
+    js_sys::Reflect::set(&description, &"name".into(), &fiber.display_name().into()).unwrap();
+    js_sys::Reflect::set(&description, &"owner".into(), &owner_value(&fiber)).unwrap();
+    js_sys::Reflect::set(&description, &"parentId".into(), &parent_id).unwrap();
+
+    description.into()
+}
+
+pub trait FiberSubtreeIterator {
+    fn subtree(&self) -> FiberSubtreeIter;
+}
+
+impl FiberSubtreeIterator for FiberCell {
+    fn subtree(&self) -> FiberSubtreeIter {
+        FiberSubtreeIter {
+            root: Rc::clone(self),
+            next: Some(Rc::clone(self)),
+        }
+    }
+}
+
+/// Depth-first walk over a fiber and all of its descendants, never leaving
+/// the subtree through the root's siblings.
+pub struct FiberSubtreeIter {
+    root: FiberCell,
+    next: Option<FiberCell>,
+}
+
+impl Iterator for FiberSubtreeIter {
+    type Item = FiberCell;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.next.take()?;
+
+        let child = current.borrow().child().as_ref().map(Rc::clone);
+
+        if child.is_some() {
+            self.next = child;
+            return Some(current);
+        }
+
+        let mut fiber = Rc::clone(&current);
+
+        while !Rc::ptr_eq(&fiber, &self.root) {
+            if let Some(sibling) = fiber.borrow().sibling().as_ref() {
+                self.next = Some(Rc::clone(sibling));
+                break;
+            }
+
+            let parent = match fiber.borrow().parent().as_ref() {
+                Some(parent) => Rc::clone(parent),
+                None => break,
+            };
+
+            fiber = parent;
+        }
+
+        Some(current)
     }
 }
 
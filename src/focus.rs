@@ -0,0 +1,76 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as HTMLElement, Document, HtmlInputElement, HtmlTextAreaElement, ShadowRoot};
+use super::events;
+
+/// The focused element and its text selection, taken before the commit
+/// mutates the DOM.
+pub struct FocusSnapshot {
+    fiber_id: u32,
+    selection: Option<(u32, u32)>,
+}
+
+impl FocusSnapshot {
+    pub fn capture(document: &Document, shadow_root: Option<&ShadowRoot>, fiber_id_key: &JsValue) -> Option<Self> {
+        let active = active_element(document, shadow_root)?;
+
+        // Only nodes rendered by fusion themselves can be found again
+        let fiber_id = events::own_fiber_id(&active, fiber_id_key)?;
+
+        Some(FocusSnapshot {
+            fiber_id,
+            selection: selection_of(&active),
+        })
+    }
+
+    pub fn fiber_id(&self) -> u32 {
+        self.fiber_id
+    }
+
+    /// Puts focus and selection back on the surviving node of the fiber,
+    /// unless the commit left focus somewhere other than the body.
+    pub fn restore(&self, document: &Document, shadow_root: Option<&ShadowRoot>, node: &HTMLElement) {
+        let active = active_element(document, shadow_root);
+
+        if active.as_ref() == Some(node) || !node.is_connected() {
+            return;
+        }
+
+        if active.is_some_and(|active| document.body().is_none_or(|body| *body != active)) {
+            return;
+        }
+
+        if let Some(node) = node.dyn_ref::<web_sys::HtmlElement>() {
+            let _ = node.focus();
+        }
+
+        if let Some((start, end)) = self.selection {
+            set_selection_of(node, start, end);
+        }
+    }
+}
+
+// The document only sees the host of a shadow tree as focused
+fn active_element(document: &Document, shadow_root: Option<&ShadowRoot>) -> Option<HTMLElement> {
+    shadow_root.and_then(|shadow_root| shadow_root.active_element())
+        .or_else(|| document.active_element())
+}
+
+// Inputs like number or email have no selection and throw when asked for it
+fn selection_of(node: &HTMLElement) -> Option<(u32, u32)> {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        Some((input.selection_start().ok()??, input.selection_end().ok()??))
+    } else if let Some(textarea) = node.dyn_ref::<HtmlTextAreaElement>() {
+        Some((textarea.selection_start().ok()??, textarea.selection_end().ok()??))
+    } else {
+        None
+    }
+}
+
+fn set_selection_of(node: &HTMLElement, start: u32, end: u32) {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        let _ = input.set_selection_range(start, end);
+    } else if let Some(textarea) = node.dyn_ref::<HtmlTextAreaElement>() {
+        let _ = textarea.set_selection_range(start, end);
+    }
+}
@@ -0,0 +1,152 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as HTMLElement, HtmlFormElement, HtmlInputElement, HtmlOptionElement, HtmlSelectElement, HtmlTextAreaElement};
+use super::ElementProps;
+
+// Native events after which a controlled element is put back in line with
+// its props. Composition events bracket IME input, during which the value is
+// left alone.
+pub static CONTROL_EVENTS: &[&str] = &["input", "change", "click", "compositionstart", "compositionend"];
+
+/// Text fields report every edit through `input`, while checkboxes, radios,
+/// selects and file inputs only settle on a value with `change`.
+pub fn is_text_field(target: &JsValue) -> bool {
+    if let Some(input) = target.dyn_ref::<HtmlInputElement>() {
+        !matches!(input.type_().as_str(), "checkbox" | "radio" | "file")
+    } else {
+        target.is_instance_of::<HtmlTextAreaElement>()
+    }
+}
+
+/// Handler props a native event is dispatched to. `onChange` is normalized to
+/// fire on every edit of a text field and on `change` everywhere else.
+pub fn listener_types(event_type: &str, target: &JsValue) -> Vec<String> {
+    match event_type {
+        "input" if is_text_field(target) => vec![String::from("input"), String::from("change")],
+        "change" if is_text_field(target) => vec![],
+        _ => vec![String::from(event_type)],
+    }
+}
+
+/// Native events the root must listen to for a handler prop
+pub fn native_events(listener_type: &str) -> Vec<&str> {
+    match listener_type {
+        "change" => vec!["input", "change"],
+        _ => vec![listener_type],
+    }
+}
+
+pub fn control_value(node: &HTMLElement) -> Option<String> {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        Some(input.value())
+    } else if let Some(textarea) = node.dyn_ref::<HtmlTextAreaElement>() {
+        Some(textarea.value())
+    } else {
+        node.dyn_ref::<HtmlSelectElement>().map(|select| select.value())
+    }
+}
+
+/// Writes the `value` IDL property of an input, textarea or select. Skipped
+/// when the DOM already holds the value so the caret doesn't jump.
+pub fn set_control_value(node: &HTMLElement, value: &str) {
+    if control_value(node).as_deref() == Some(value) {
+        return;
+    }
+
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        input.set_value(value);
+    } else if let Some(textarea) = node.dyn_ref::<HtmlTextAreaElement>() {
+        textarea.set_value(value);
+    } else if let Some(select) = node.dyn_ref::<HtmlSelectElement>() {
+        select.set_value(value);
+    }
+}
+
+/// Selects the options of a select whose value is in `values` and deselects
+/// the others. Options are written one by one so that a multiple select can
+/// hold several.
+pub fn set_select_values(node: &HTMLElement, values: &[String]) {
+    let options = match node.dyn_ref::<HtmlSelectElement>() {
+        Some(select) => select.options(),
+        None => return,
+    };
+
+    for index in 0..options.length() {
+        if let Some(option) = options.item(index).and_then(|option| option.dyn_into::<HtmlOptionElement>().ok()) {
+            let selected = values.contains(&option.value());
+
+            if option.selected() != selected {
+                option.set_selected(selected);
+            }
+        }
+    }
+}
+
+pub fn set_control_checked(node: &HTMLElement, checked: bool) {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        if input.checked() != checked {
+            input.set_checked(checked);
+        }
+    }
+}
+
+/// Hands an element that stops being controlled back its default value, as
+/// given by its `value` attribute or its initial content.
+pub fn reset_control_value(node: &HTMLElement) {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        input.set_value(&input.default_value());
+    } else if let Some(textarea) = node.dyn_ref::<HtmlTextAreaElement>() {
+        textarea.set_value(&textarea.default_value().unwrap_or_default());
+    }
+}
+
+pub fn reset_control_checked(node: &HTMLElement) {
+    if let Some(input) = node.dyn_ref::<HtmlInputElement>() {
+        input.set_checked(input.default_checked());
+    }
+}
+
+/// Puts a controlled element back to the `value`/`checked` of its committed
+/// props, undoing whatever the user did that state didn't pick up.
+pub fn restore_control(node: &HTMLElement, props: &ElementProps) {
+    if let Some(value) = props.input_value() {
+        set_control_value(node, value);
+    }
+
+    if let Some(checked) = props.input_checked() {
+        set_control_checked(node, checked);
+    }
+
+    if let Some(values) = props.select_value() {
+        set_select_values(node, values);
+    }
+}
+
+/// Named fields of a submitted form as a plain object. Names used by several
+/// fields (checkbox groups, multiple selects) map to an array of values.
+pub fn form_values(form: &HtmlFormElement) -> JsValue {
+    let values = js_sys::Object::new();
+    let form_data = match web_sys::FormData::new_with_form(form) {
+        Ok(form_data) => form_data,
+        Err(_) => return values.into(),
+    };
+
+    for entry in js_sys::try_iter(&form_data).ok().flatten().into_iter().flatten().flatten() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let name = entry.get(0);
+        let value = entry.get(1);
+
+        let value = match js_sys::Reflect::get(&values, &name).ok().filter(|prev| !prev.is_undefined()) {
+            Some(prev) if js_sys::Array::is_array(&prev) => {
+                prev.unchecked_ref::<js_sys::Array>().push(&value);
+                prev
+            },
+            Some(prev) => js_sys::Array::of2(&prev, &value).into(),
+            None => value,
+        };
+
+        js_sys::Reflect::set(&values, &name, &value).unwrap();
+    }
+
+    values.into()
+}
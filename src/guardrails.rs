@@ -0,0 +1,125 @@
+use wasm_bindgen::prelude::*;
+use super::{Element, element, PORTAL_ELEMENT, GUARDRAIL_PLACEHOLDER};
+
+/// What happens to a render that goes over a limit
+#[derive(Clone, Copy, PartialEq)]
+pub enum GuardrailAction {
+    // Everything renders, with a warning
+    Warn,
+    // Children past the limit are replaced with a placeholder element
+    Truncate,
+    // The children of the component or element that went over the limit
+    // aren't rendered, and the error handler is told
+    Error,
+}
+
+/// Limits on the output of a render, for embedders rendering element trees
+/// they don't control. Counts start over with every render from the root.
+pub struct Guardrails {
+    max_fibers: Option<usize>,
+    max_dom_nodes: Option<usize>,
+    action: GuardrailAction,
+    fibers: usize,
+    dom_nodes: usize,
+    breached: bool,
+}
+
+impl Guardrails {
+    pub fn new() -> Self {
+        Guardrails {
+            max_fibers: None,
+            max_dom_nodes: None,
+            action: GuardrailAction::Warn,
+            fibers: 0,
+            dom_nodes: 0,
+            breached: false,
+        }
+    }
+
+    /// `{ maxFibersPerCommit, maxDomNodes, action }`, `action` being "warn"
+    /// (the default), "truncate" or "error". Null or undefined lifts the
+    /// limits.
+    pub fn configure(&mut self, options: &JsValue) -> Result<(), JsValue> {
+        let limit = |name: &str| -> Result<Option<usize>, JsValue> {
+            if !options.is_object() {
+                return Ok(None);
+            }
+
+            Ok(js_sys::Reflect::get(options, &name.into())?.as_f64().map(|limit| limit.max(0.0) as usize))
+        };
+
+        let action = match options.is_object() {
+            true => js_sys::Reflect::get(options, &"action".into())?.as_string(),
+            false => None,
+        };
+
+        self.action = match action.as_deref() {
+            None | Some("warn") => GuardrailAction::Warn,
+            Some("truncate") => GuardrailAction::Truncate,
+            Some("error") => GuardrailAction::Error,
+            Some(action) => return Err(js_sys::Error::new(&format!("Reactron: unknown guardrail action '{}'", action)).into()),
+        };
+        self.max_fibers = limit("maxFibersPerCommit")?;
+        self.max_dom_nodes = limit("maxDomNodes")?;
+
+        Ok(())
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_fibers.is_some() || self.max_dom_nodes.is_some()
+    }
+
+    pub fn action(&self) -> GuardrailAction {
+        self.action
+    }
+
+    pub fn reset(&mut self) {
+        self.fibers = 0;
+        self.dom_nodes = 0;
+        self.breached = false;
+    }
+
+    /// Counts `children` against the limits. Returns how many of them fit,
+    /// and the message for the first limit the render goes over. Warnings
+    /// keep counting past the limit; the other actions stop there.
+    pub fn admit(&mut self, children: &[Box<Element>]) -> (usize, Option<String>) {
+        let mut admitted = 0;
+        let mut message = None;
+
+        for child in children {
+            let is_dom_node = child.component_function().is_none() && *child.element_type() != PORTAL_ELEMENT;
+            let fibers = self.fibers + 1;
+            let dom_nodes = self.dom_nodes + is_dom_node as usize;
+
+            let breach = match (self.max_fibers, self.max_dom_nodes) {
+                (Some(max_fibers), _) if fibers > max_fibers => Some(format!("more than {} fibers", max_fibers)),
+                (_, Some(max_dom_nodes)) if dom_nodes > max_dom_nodes => Some(format!("more than {} DOM nodes", max_dom_nodes)),
+                _ => None,
+            };
+
+            if let Some(breach) = breach {
+                if !self.breached {
+                    self.breached = true;
+                    message = Some(format!("Reactron: the render produced {}", breach));
+                }
+
+                if self.action != GuardrailAction::Warn {
+                    break;
+                }
+            }
+
+            self.fibers = fibers;
+            self.dom_nodes = dom_nodes;
+            admitted += 1;
+        }
+
+        (admitted, message)
+    }
+}
+
+/// Empty element standing in for truncated children
+pub fn placeholder() -> Box<Element> {
+    let props_ptr = element::create_props_from_object(&js_sys::Object::new().into(), Some(String::from(GUARDRAIL_PLACEHOLDER)));
+
+    Element::from_ptr(element::create_element(String::from(GUARDRAIL_PLACEHOLDER), props_ptr, &[]))
+}
@@ -0,0 +1,379 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use super::{WorkerJob, HotkeyBinding, DisplayModeBinding, ChannelBinding, UndoableState, ContainerQuery};
+
+pub type HookCell = Rc<RefCell<Hook>>;
+
+pub enum Hook {
+    State(JsValue),
+    EventCallback(EventCallback),
+    Effect(Effect),
+    Announcer(js_sys::Function),
+    Worker(WorkerJob),
+    Hotkey(HotkeyBinding),
+    DisplayMode(DisplayModeBinding),
+    WakeLock,
+    Idle(u32),
+    BroadcastChannel(ChannelBinding),
+    Blocker(u32),
+    Undoable(UndoableState),
+    ContainerQuery(ContainerQuery),
+    Instance(Instance),
+    ImperativeHandle(ImperativeHandle),
+    // A hook that couldn't set up, such as one whose setup threw. It keeps
+    // the slot, so the hooks after it still line up, and sets up from
+    // scratch on the next render.
+    Failed,
+}
+
+impl Hook {
+    pub fn state(&self) -> Option<&JsValue> {
+        match self {
+            Hook::State(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn event_callback(&self) -> Option<&EventCallback> {
+        match self {
+            Hook::EventCallback(callback) => Some(callback),
+            _ => None,
+        }
+    }
+
+    pub fn effect(&self) -> Option<&Effect> {
+        match self {
+            Hook::Effect(effect) => Some(effect),
+            _ => None,
+        }
+    }
+
+    pub fn announcer(&self) -> Option<&js_sys::Function> {
+        match self {
+            Hook::Announcer(announce) => Some(announce),
+            _ => None,
+        }
+    }
+
+    pub fn worker_job(&self) -> Option<&WorkerJob> {
+        match self {
+            Hook::Worker(job) => Some(job),
+            _ => None,
+        }
+    }
+
+    pub fn hotkey_binding(&self) -> Option<&HotkeyBinding> {
+        match self {
+            Hook::Hotkey(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    pub fn display_mode(&self) -> Option<&DisplayModeBinding> {
+        match self {
+            Hook::DisplayMode(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    pub fn is_wake_lock(&self) -> bool {
+        matches!(self, Hook::WakeLock)
+    }
+
+    /// Id of the watcher in the Context's idle tracker
+    pub fn idle_watcher(&self) -> Option<u32> {
+        match self {
+            Hook::Idle(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn broadcast_channel(&self) -> Option<&ChannelBinding> {
+        match self {
+            Hook::BroadcastChannel(binding) => Some(binding),
+            _ => None,
+        }
+    }
+
+    /// Id of the blocker in the Context's registry
+    pub fn blocker(&self) -> Option<u32> {
+        match self {
+            Hook::Blocker(id) => Some(*id),
+            _ => None,
+        }
+    }
+
+    pub fn undoable_state(&self) -> Option<&UndoableState> {
+        match self {
+            Hook::Undoable(state) => Some(state),
+            _ => None,
+        }
+    }
+
+    pub fn container_query(&self) -> Option<&ContainerQuery> {
+        match self {
+            Hook::ContainerQuery(query) => Some(query),
+            _ => None,
+        }
+    }
+
+    pub fn instance(&self) -> Option<&Instance> {
+        match self {
+            Hook::Instance(instance) => Some(instance),
+            _ => None,
+        }
+    }
+
+    pub fn imperative_handle(&self) -> Option<&ImperativeHandle> {
+        match self {
+            Hook::ImperativeHandle(handle) => Some(handle),
+            _ => None,
+        }
+    }
+
+    pub fn effect_mut(&mut self) -> Option<&mut Effect> {
+        match self {
+            Hook::Effect(effect) => Some(effect),
+            _ => None,
+        }
+    }
+
+    pub fn type_tag(&self) -> &'static str {
+        match self {
+            Hook::State(_) => "state",
+            Hook::EventCallback(_) => "event_callback",
+            Hook::Effect(_) => "effect",
+            Hook::Announcer(_) => "announcer",
+            Hook::Worker(_) => "worker",
+            Hook::Hotkey(_) => "hotkey",
+            Hook::DisplayMode(_) => "display_mode",
+            Hook::WakeLock => "wake_lock",
+            Hook::Idle(_) => "idle",
+            Hook::BroadcastChannel(_) => "broadcast_channel",
+            Hook::Blocker(_) => "blocker",
+            Hook::Undoable(_) => "undoable_state",
+            Hook::ContainerQuery(_) => "container_query",
+            Hook::Instance(_) => "instance",
+            Hook::ImperativeHandle(_) => "imperative_handle",
+            Hook::Failed => "failed",
+        }
+    }
+
+    /// Plain JS description of the hook: `{ type, value, deps }`. `deps` is
+    /// null for hooks that don't take dependencies.
+    pub fn describe(&self) -> JsValue {
+        let value = match self {
+            Hook::State(value) => value.clone(),
+            Hook::EventCallback(callback) => callback.latest()
+                .borrow()
+                .as_ref()
+                .map_or(JsValue::NULL, |callback| callback.clone().into()),
+            Hook::Effect(effect) => effect.cleanup()
+                .map_or(JsValue::NULL, |cleanup| cleanup.clone().into()),
+            Hook::Announcer(announce) => announce.clone().into(),
+            Hook::Worker(job) => job.state().borrow().value.clone(),
+            Hook::Hotkey(binding) => binding.callback().latest()
+                .borrow()
+                .as_ref()
+                .map_or(JsValue::NULL, |callback| callback.clone().into()),
+            Hook::DisplayMode(binding) => binding.is_active().into(),
+            Hook::WakeLock | Hook::Idle(_) | Hook::Blocker(_) | Hook::Failed => JsValue::NULL,
+            Hook::BroadcastChannel(binding) => binding.latest(),
+            Hook::Undoable(state) => state.history().borrow().present().clone(),
+            Hook::ContainerQuery(query) => query.active().map_or(JsValue::NULL, JsValue::from),
+            Hook::Instance(instance) => instance.value().clone(),
+            Hook::ImperativeHandle(handle) => handle.handle().clone(),
+        };
+
+        let deps = match self {
+            Hook::Effect(effect) => effect.deps().map_or(JsValue::NULL, |deps| deps.clone().into()),
+            Hook::Worker(job) => job.deps().map_or(JsValue::NULL, |deps| deps.clone().into()),
+            Hook::ImperativeHandle(handle) => handle.deps().map_or(JsValue::NULL, |deps| deps.clone().into()),
+            _ => JsValue::NULL,
+        };
+
+        let description = js_sys::Object::new();
+        js_sys::Reflect::set(&description, &"type".into(), &self.type_tag().into()).unwrap();
+        js_sys::Reflect::set(&description, &"value".into(), &value).unwrap();
+        js_sys::Reflect::set(&description, &"deps".into(), &deps).unwrap();
+
+        description.into()
+    }
+}
+
+/// A function whose identity is kept for the whole lifetime of a fiber while
+/// forwarding to the callback from the latest committed render.
+pub struct EventCallback {
+    stable: js_sys::Function,
+    latest: Rc<RefCell<Option<js_sys::Function>>>,
+}
+
+impl EventCallback {
+    pub fn new() -> Self {
+        let latest: Rc<RefCell<Option<js_sys::Function>>> = Rc::new(RefCell::new(None));
+        let latest_slot = Rc::clone(&latest);
+
+        let stable = Closure::wrap(Box::new(move |arg: JsValue| -> Result<JsValue, JsValue> {
+            match latest_slot.borrow().as_ref() {
+                Some(callback) => callback.call1(&JsValue::null(), &arg),
+                None => Ok(JsValue::UNDEFINED),
+            }
+        }) as Box<dyn FnMut(JsValue) -> Result<JsValue, JsValue>>)
+            .into_js_value()
+            .into();
+
+        EventCallback { stable, latest }
+    }
+
+    pub fn stable(&self) -> &js_sys::Function {
+        &self.stable
+    }
+
+    pub fn latest(&self) -> &Rc<RefCell<Option<js_sys::Function>>> {
+        &self.latest
+    }
+}
+
+impl Clone for EventCallback {
+    fn clone(&self) -> Self {
+        EventCallback {
+            stable: self.stable.clone(),
+            latest: Rc::clone(&self.latest),
+        }
+    }
+}
+
+pub struct Effect {
+    deps: Option<js_sys::Array>,
+    cleanup: Option<js_sys::Function>,
+}
+
+impl Effect {
+    pub fn new(deps: Option<js_sys::Array>, cleanup: Option<js_sys::Function>) -> Self {
+        Effect { deps, cleanup }
+    }
+
+    pub fn deps(&self) -> Option<&js_sys::Array> {
+        self.deps.as_ref()
+    }
+
+    pub fn cleanup(&self) -> Option<&js_sys::Function> {
+        self.cleanup.as_ref()
+    }
+
+    pub fn set_cleanup(&mut self, cleanup: Option<js_sys::Function>) {
+        self.cleanup = cleanup;
+    }
+}
+
+/// An effect must run again unless both renders passed the same number of
+/// dependencies and each of them is `Object.is` equal to its predecessor.
+pub fn deps_changed(prev_deps: Option<&js_sys::Array>, next_deps: Option<&js_sys::Array>) -> bool {
+    match (prev_deps, next_deps) {
+        (Some(prev), Some(next)) => {
+            prev.length() != next.length() ||
+            prev.iter().zip(next.iter()).any(|(prev, next)| !js_sys::Object::is(&prev, &next))
+        },
+        (_, _) => true,
+    }
+}
+
+/// An imperative object owned by a fiber, created on mount and handed to
+/// `dispose` once the fiber unmounts.
+pub struct Instance {
+    value: JsValue,
+    dispose: Option<js_sys::Function>,
+}
+
+impl Instance {
+    pub fn new(value: JsValue, dispose: Option<js_sys::Function>) -> Self {
+        Instance { value, dispose }
+    }
+
+    pub fn value(&self) -> &JsValue {
+        &self.value
+    }
+
+    /// `dispose` bound to the value, ready for the passive effect flush
+    pub fn destroy(&self) -> Option<js_sys::Function> {
+        self.dispose.as_ref().map(|dispose| dispose.bind1(&JsValue::NULL, &self.value).unchecked_into())
+    }
+}
+
+/// Object of methods a component exposes to the host page, which reaches
+/// them through `send_command`
+pub struct ImperativeHandle {
+    handle: JsValue,
+    deps: Option<js_sys::Array>,
+}
+
+impl ImperativeHandle {
+    pub fn new(handle: JsValue, deps: Option<js_sys::Array>) -> Self {
+        ImperativeHandle { handle, deps }
+    }
+
+    pub fn handle(&self) -> &JsValue {
+        &self.handle
+    }
+
+    pub fn deps(&self) -> Option<&js_sys::Array> {
+        self.deps.as_ref()
+    }
+}
+
+/// One call of the passive effect flush. The cleanups of a commit all come
+/// before its effects.
+pub enum PassiveStep {
+    Destroy(js_sys::Function, String),
+    Create(HookCell, js_sys::Function, String),
+}
+
+impl PassiveStep {
+    pub fn from_effects(effects: Vec<PassiveEffect>) -> Vec<PassiveStep> {
+        let destroys = effects.iter()
+            .filter_map(|effect| Some(PassiveStep::Destroy(effect.destroy()?.clone(), String::from(effect.component_stack()))));
+
+        let creates = effects.iter()
+            .filter_map(|effect| Some(PassiveStep::Create(Rc::clone(effect.hook()), effect.create()?.clone(), String::from(effect.component_stack()))));
+
+        destroys.chain(creates).collect()
+    }
+}
+
+/// Work queued for the passive effect flush that follows a commit: the
+/// previous cleanup to run and/or the new effect callback to run.
+pub struct PassiveEffect {
+    hook: HookCell,
+    create: Option<js_sys::Function>,
+    destroy: Option<js_sys::Function>,
+    component_stack: String,
+}
+
+impl PassiveEffect {
+    pub fn new(
+        hook: HookCell,
+        create: Option<js_sys::Function>,
+        destroy: Option<js_sys::Function>,
+        component_stack: String,
+    ) -> Self {
+        PassiveEffect { hook, create, destroy, component_stack }
+    }
+
+    pub fn hook(&self) -> &HookCell {
+        &self.hook
+    }
+
+    pub fn create(&self) -> Option<&js_sys::Function> {
+        self.create.as_ref()
+    }
+
+    pub fn destroy(&self) -> Option<&js_sys::Function> {
+        self.destroy.as_ref()
+    }
+
+    pub fn component_stack(&self) -> &str {
+        &self.component_stack
+    }
+}
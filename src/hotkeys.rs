@@ -0,0 +1,155 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+use super::EventCallback;
+
+/// A key combination such as `ctrl+k` or `shift+alt+ArrowUp`. Modifiers must
+/// match exactly, the key is compared case-insensitively with `event.key`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Hotkey {
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+    key: String,
+}
+
+impl Hotkey {
+    pub fn parse(combo: &str) -> Option<Self> {
+        let mut hotkey = Hotkey {
+            ctrl: false,
+            alt: false,
+            shift: false,
+            meta: false,
+            key: String::new(),
+        };
+
+        for part in combo.split('+').map(str::trim) {
+            match part.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => hotkey.ctrl = true,
+                "alt" | "option" => hotkey.alt = true,
+                "shift" => hotkey.shift = true,
+                "meta" | "cmd" | "command" => hotkey.meta = true,
+                "" => return None,
+                key if hotkey.key.is_empty() => hotkey.key = String::from(key),
+                _ => return None,
+            }
+        }
+
+        if hotkey.key.is_empty() { None } else { Some(hotkey) }
+    }
+
+    fn matches(&self, event: &web_sys::KeyboardEvent) -> bool {
+        event.ctrl_key() == self.ctrl &&
+            event.alt_key() == self.alt &&
+            event.shift_key() == self.shift &&
+            event.meta_key() == self.meta &&
+            event.key().to_ascii_lowercase() == self.key
+    }
+}
+
+/// Hotkeys registered by mounted `use_hotkey` hooks, all served by a single
+/// `keydown` listener on the document.
+pub struct HotkeyRegistry {
+    document: Document,
+    listener: Option<js_sys::Function>,
+    bindings: Vec<(u32, Hotkey, js_sys::Function)>,
+    next_id: u32,
+    warnings: bool,
+}
+
+impl HotkeyRegistry {
+    pub fn new(document: Document) -> Self {
+        HotkeyRegistry {
+            document,
+            listener: None,
+            bindings: Vec::new(),
+            next_id: 1,
+            warnings: cfg!(debug_assertions),
+        }
+    }
+
+    /// Whether registering a combo twice is reported
+    pub fn set_warnings(&mut self, warnings: bool) {
+        self.warnings = warnings;
+    }
+
+    pub fn register(registry: &Rc<RefCell<HotkeyRegistry>>, hotkey: Hotkey, handler: js_sys::Function) -> u32 {
+        let mut this = registry.borrow_mut();
+
+        if this.listener.is_none() {
+            let weak = Rc::downgrade(registry);
+
+            let listener: js_sys::Function = Closure::wrap(Box::new(move |event: web_sys::KeyboardEvent| {
+                if let Some(registry) = weak.upgrade() {
+                    // Handlers may register or unregister hotkeys themselves
+                    let handlers: Vec<js_sys::Function> = registry.borrow().bindings.iter()
+                        .filter(|(_, hotkey, _)| hotkey.matches(&event))
+                        .map(|(_, _, handler)| handler.clone())
+                        .collect();
+
+                    for handler in handlers {
+                        event.prevent_default();
+
+                        if let Err(error) = handler.call1(&JsValue::null(), &event) {
+                            web_sys::console::error_2(&"Reactron: a hotkey handler threw an error".into(), &error);
+                        }
+                    }
+                }
+            }) as Box<dyn FnMut(web_sys::KeyboardEvent)>).into_js_value().unchecked_into();
+
+            this.document.add_event_listener_with_callback("keydown", &listener).unwrap();
+            this.listener = Some(listener);
+        }
+
+        if this.warnings && this.bindings.iter().any(|(_, bound, _)| *bound == hotkey) {
+            let message = format!("Reactron: hotkey {:?} is registered more than once, every handler will run", hotkey);
+            web_sys::console::warn_1(&message.into());
+        }
+
+        let id = this.next_id;
+        this.next_id += 1;
+        this.bindings.push((id, hotkey, handler));
+
+        id
+    }
+
+    pub fn rebind(&mut self, id: u32, hotkey: Hotkey) {
+        if let Some(binding) = self.bindings.iter_mut().find(|(bound_id, _, _)| *bound_id == id) {
+            binding.1 = hotkey;
+        }
+    }
+
+    pub fn unregister(&mut self, id: u32) {
+        self.bindings.retain(|(bound_id, _, _)| *bound_id != id);
+    }
+}
+
+/// State of a `use_hotkey` hook: its registry entry and the stable function
+/// forwarding to the handler of the latest committed render.
+#[derive(Clone)]
+pub struct HotkeyBinding {
+    id: u32,
+    hotkey: Hotkey,
+    callback: EventCallback,
+}
+
+impl HotkeyBinding {
+    pub fn new(id: u32, hotkey: Hotkey, callback: EventCallback) -> Self {
+        HotkeyBinding { id, hotkey, callback }
+    }
+
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn hotkey(&self) -> &Hotkey {
+        &self.hotkey
+    }
+
+    pub fn callback(&self) -> &EventCallback {
+        &self.callback
+    }
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+use super::timers::set_timeout;
+use super::IDLE_ACTIVITY_EVENTS;
+
+struct IdleWatcher {
+    timeout: f64,
+    idle: bool,
+    timer: Option<i32>,
+    on_change: Rc<dyn Fn()>,
+}
+
+/// User activity seen by every `use_idle` of a Context. A single set of
+/// passive document listeners records the time of the last activity; each
+/// watcher only keeps one timer, checking that time when it fires instead of
+/// being reset on every mouse move.
+pub struct IdleTracker {
+    document: Document,
+    last_activity: f64,
+    watchers: HashMap<u32, IdleWatcher>,
+    next_id: u32,
+    on_activity: Option<js_sys::Function>,
+}
+
+impl IdleTracker {
+    pub fn new(document: Document) -> Self {
+        IdleTracker {
+            document,
+            last_activity: js_sys::Date::now(),
+            watchers: HashMap::new(),
+            next_id: 0,
+            on_activity: None,
+        }
+    }
+
+    /// Starts watching for `timeout` milliseconds without activity.
+    /// `on_change` is called whenever the watcher goes idle or active again.
+    pub fn watch(this: &Rc<RefCell<Self>>, timeout: f64, on_change: impl Fn() + 'static) -> u32 {
+        let mut tracker = this.borrow_mut();
+
+        if tracker.on_activity.is_none() {
+            let weak_tracker = Rc::downgrade(this);
+            let on_activity: js_sys::Function = Closure::wrap(Box::new(move || {
+                IdleTracker::record_activity(&weak_tracker);
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into();
+
+            let options = web_sys::AddEventListenerOptions::new();
+            options.set_capture(true);
+            options.set_passive(true);
+
+            for event_type in IDLE_ACTIVITY_EVENTS {
+                tracker.document
+                    .add_event_listener_with_callback_and_add_event_listener_options(event_type, &on_activity, &options)
+                    .unwrap();
+            }
+
+            tracker.on_activity = Some(on_activity);
+            tracker.last_activity = js_sys::Date::now();
+        }
+
+        let id = tracker.next_id;
+        tracker.next_id += 1;
+        tracker.watchers.insert(id, IdleWatcher { timeout, idle: false, timer: None, on_change: Rc::new(on_change) });
+
+        let delay = timeout - (js_sys::Date::now() - tracker.last_activity);
+        tracker.schedule_check(this, id, delay);
+
+        id
+    }
+
+    pub fn is_idle(&self, id: u32) -> bool {
+        self.watchers.get(&id).is_some_and(|watcher| watcher.idle)
+    }
+
+    pub fn set_timeout(this: &Rc<RefCell<Self>>, id: u32, timeout: f64) {
+        let mut tracker = this.borrow_mut();
+
+        match tracker.watchers.get_mut(&id) {
+            Some(watcher) if watcher.timeout != timeout => watcher.timeout = timeout,
+            _ => return,
+        }
+
+        let delay = timeout - (js_sys::Date::now() - tracker.last_activity);
+        tracker.schedule_check(this, id, delay);
+    }
+
+    pub fn unwatch(&mut self, id: u32) {
+        if let Some(timer) = self.watchers.remove(&id).and_then(|watcher| watcher.timer) {
+            web_sys::window().unwrap().clear_timeout_with_handle(timer);
+        }
+
+        if self.watchers.is_empty() {
+            if let Some(on_activity) = self.on_activity.take() {
+                for event_type in IDLE_ACTIVITY_EVENTS {
+                    let _ = self.document.remove_event_listener_with_callback_and_bool(event_type, &on_activity, true);
+                }
+            }
+        }
+    }
+
+    fn schedule_check(&mut self, this: &Rc<RefCell<Self>>, id: u32, delay: f64) {
+        let watcher = match self.watchers.get_mut(&id) {
+            Some(watcher) => watcher,
+            None => return,
+        };
+
+        if let Some(timer) = watcher.timer.take() {
+            web_sys::window().unwrap().clear_timeout_with_handle(timer);
+        }
+
+        let weak_tracker = Rc::downgrade(this);
+        watcher.timer = Some(set_timeout(delay.max(0.0).ceil() as i32, move || {
+            IdleTracker::check(&weak_tracker, id);
+        }));
+    }
+
+    fn check(this: &Weak<RefCell<Self>>, id: u32) {
+        let this = match this.upgrade() {
+            Some(this) => this,
+            None => return,
+        };
+
+        let on_change = {
+            let mut tracker = this.borrow_mut();
+            let remaining = {
+                let last_activity = tracker.last_activity;
+                let watcher = match tracker.watchers.get_mut(&id) {
+                    Some(watcher) => watcher,
+                    None => return,
+                };
+                watcher.timer = None;
+
+                watcher.timeout - (js_sys::Date::now() - last_activity)
+            };
+
+            if remaining > 0.0 {
+                tracker.schedule_check(&this, id, remaining);
+                return;
+            }
+
+            let watcher = tracker.watchers.get_mut(&id).unwrap();
+            watcher.idle = true;
+            Rc::clone(&watcher.on_change)
+        };
+
+        on_change();
+    }
+
+    fn record_activity(this: &Weak<RefCell<Self>>) {
+        let this = match this.upgrade() {
+            Some(this) => this,
+            None => return,
+        };
+
+        let on_changes: Vec<Rc<dyn Fn()>> = {
+            let mut tracker = this.borrow_mut();
+            tracker.last_activity = js_sys::Date::now();
+
+            let woken: Vec<(u32, f64)> = tracker.watchers.iter_mut()
+                .filter(|(_, watcher)| watcher.idle)
+                .map(|(id, watcher)| {
+                    watcher.idle = false;
+                    (*id, watcher.timeout)
+                })
+                .collect();
+
+            woken.into_iter()
+                .map(|(id, timeout)| {
+                    tracker.schedule_check(&this, id, timeout);
+                    Rc::clone(&tracker.watchers[&id].on_change)
+                })
+                .collect()
+        };
+
+        for on_change in on_changes {
+            on_change();
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Internal consistency checks run before every commit when the crate is
+//! built with the `invariants` feature or dev mode turns assertions on. A
+//! failure means the reconciler produced a tree it can't commit safely, so
+//! these panic instead of returning errors.
+
+use std::rc::Rc;
+use super::{FiberCell, FiberEffect, FiberParentIterator, FiberSubtreeIterator};
+
+fn root_of(fiber: &FiberCell) -> FiberCell {
+    fiber.parents().last().unwrap_or_else(|| Rc::clone(fiber))
+}
+
+fn describe(fiber: &FiberCell) -> String {
+    let fiber = fiber.borrow();
+    format!("<{}> (fiber #{}){}", fiber.element_type(), fiber.id(), fiber.component_stack())
+}
+
+pub fn assert_commit_invariants(wip_root: &FiberCell, current_root: Option<&FiberCell>, effects: &[FiberCell]) {
+    for effect in effects {
+        let tag = effect.borrow().effect_tag().map(|tag| format!("{:?}", tag));
+
+        match effect.borrow().effect_tag() {
+            Some(FiberEffect::Placement) => {
+                let fiber = effect.borrow();
+                assert!(
+                    fiber.is_functional_tree() || fiber.dom_node().is_some(),
+                    "fusion invariant: Placement of {} has no DOM node to insert", describe(effect)
+                );
+            },
+            Some(FiberEffect::Deletion) => {
+                assert!(
+                    !Rc::ptr_eq(&root_of(effect), wip_root),
+                    "fusion invariant: {} is tagged for Deletion but still belongs to the new tree", describe(effect)
+                );
+
+                if let Some(child) = effect.borrow().child() {
+                    assert!(
+                        !Rc::ptr_eq(&root_of(child), wip_root),
+                        "fusion invariant: deleted {} has a child linked into the new tree", describe(effect)
+                    );
+                }
+            },
+            _ => {
+                assert!(
+                    Rc::ptr_eq(&root_of(effect), wip_root),
+                    "fusion invariant: {:?} effect of {} is not part of the tree being committed", tag, describe(effect)
+                );
+            }
+        }
+    }
+
+    for fiber in wip_root.subtree() {
+        let alternate = match fiber.borrow().alternate() {
+            Some(alternate) => Rc::clone(alternate),
+            None => continue,
+        };
+
+        let fiber_ref = fiber.borrow();
+        let alternate_ref = alternate.borrow();
+
+        assert!(
+            fiber_ref.element_type() == alternate_ref.element_type() && fiber_ref.id() == alternate_ref.id(),
+            "fusion invariant: {} has an alternate of a different instance, {}", describe(&fiber), describe(&alternate)
+        );
+
+        assert!(
+            !alternate_ref.alternate().is_some_and(|older| Rc::ptr_eq(older, &fiber)),
+            "fusion invariant: {} is its own alternate's alternate", describe(&fiber)
+        );
+
+        if let Some(current_root) = current_root {
+            assert!(
+                Rc::ptr_eq(&root_of(&alternate), current_root),
+                "fusion invariant: alternate of {} is not part of the committed tree", describe(&fiber)
+            );
+        }
+    }
+}
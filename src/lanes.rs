@@ -0,0 +1,227 @@
+use super::{USER_INPUT_LANE_TIMEOUT_MS, DEFAULT_LANE_TIMEOUT_MS, TRANSITION_LANE_TIMEOUT_MS};
+
+/// Priority of an update, highest first. Every render covers the whole
+/// tree, so lanes decide when a render starts, whether it may yield to the
+/// browser and whether it gives way to a more urgent one.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Lane {
+    Sync,
+    UserInput,
+    Default,
+    Transition,
+    Idle,
+}
+
+impl Lane {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sync" => Some(Lane::Sync),
+            "user-input" => Some(Lane::UserInput),
+            "default" => Some(Lane::Default),
+            "transition" => Some(Lane::Transition),
+            "idle" => Some(Lane::Idle),
+            _ => None,
+        }
+    }
+
+    /// Renders in these lanes run to the end instead of yielding once the
+    /// idle period is over
+    pub fn is_blocking(self) -> bool {
+        matches!(self, Lane::Sync | Lane::UserInput)
+    }
+
+    /// How long updates in this lane may wait for a commit before their
+    /// render turns synchronous. Idle updates may wait forever.
+    pub fn timeout(self) -> Option<f64> {
+        match self {
+            Lane::Sync | Lane::Idle => None,
+            Lane::UserInput => Some(USER_INPUT_LANE_TIMEOUT_MS),
+            Lane::Default => Some(DEFAULT_LANE_TIMEOUT_MS),
+            Lane::Transition => Some(TRANSITION_LANE_TIMEOUT_MS),
+        }
+    }
+
+    fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+static ALL_LANES: [Lane; 5] = [Lane::Sync, Lane::UserInput, Lane::Default, Lane::Transition, Lane::Idle];
+
+/// Lanes with updates waiting for a render
+#[derive(Clone, Copy, Default)]
+pub struct Lanes(u8);
+
+impl Lanes {
+    pub fn insert(&mut self, lane: Lane) {
+        self.0 |= lane.bit();
+    }
+
+    pub fn merge(&mut self, other: Lanes) {
+        self.0 |= other.0;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn contains(&self, lane: Lane) -> bool {
+        self.0 & lane.bit() != 0
+    }
+
+    fn without(self, other: Lanes) -> Lanes {
+        Lanes(self.0 & !other.0)
+    }
+
+    pub fn highest(&self) -> Option<Lane> {
+        self.iter().next()
+    }
+
+    fn iter(self) -> impl Iterator<Item = Lane> {
+        ALL_LANES.iter().copied().filter(move |lane| self.contains(*lane))
+    }
+}
+
+/// When the oldest update still waiting in each lane was scheduled, so work
+/// starved by more urgent updates, or by a main thread that's never idle,
+/// is eventually forced through.
+#[derive(Default)]
+pub struct LaneTimes([Option<f64>; 5]);
+
+impl LaneTimes {
+    pub fn mark(&mut self, lane: Lane, now: f64) {
+        self.0[lane as usize].get_or_insert(now);
+    }
+
+    /// Forgets the times of the lanes a commit rendered, except for lanes
+    /// that got another update while it ran: those keep waiting from their
+    /// oldest update's time.
+    pub fn committed(&mut self, rendered: Lanes, pending: Lanes) {
+        for lane in rendered.without(pending).iter() {
+            self.0[lane as usize] = None;
+        }
+    }
+
+    /// Earliest time one of `lanes` expires
+    pub fn next_expiration(&self, lanes: Lanes) -> Option<f64> {
+        lanes.iter()
+            .filter_map(|lane| Some(self.0[lane as usize]? + lane.timeout()?))
+            .reduce(f64::min)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::{USER_INPUT_LANE_TIMEOUT_MS, DEFAULT_LANE_TIMEOUT_MS, TRANSITION_LANE_TIMEOUT_MS};
+
+    fn lanes(lanes: &[Lane]) -> Lanes {
+        let mut set = Lanes::default();
+
+        for lane in lanes {
+            set.insert(*lane);
+        }
+
+        set
+    }
+
+    #[test]
+    fn lane_names() {
+        assert_eq!(Lane::from_name("sync"), Some(Lane::Sync));
+        assert_eq!(Lane::from_name("user-input"), Some(Lane::UserInput));
+        assert_eq!(Lane::from_name("default"), Some(Lane::Default));
+        assert_eq!(Lane::from_name("transition"), Some(Lane::Transition));
+        assert_eq!(Lane::from_name("idle"), Some(Lane::Idle));
+        assert_eq!(Lane::from_name("urgent"), None);
+    }
+
+    #[test]
+    fn only_sync_and_user_input_block() {
+        let blocking: Vec<Lane> = ALL_LANES.iter().copied().filter(|lane| lane.is_blocking()).collect();
+
+        assert_eq!(blocking, vec![Lane::Sync, Lane::UserInput]);
+    }
+
+    #[test]
+    fn highest_is_the_most_urgent_lane() {
+        assert_eq!(Lanes::default().highest(), None);
+        assert_eq!(lanes(&[Lane::Idle, Lane::Transition]).highest(), Some(Lane::Transition));
+        assert_eq!(lanes(&[Lane::Idle, Lane::Default, Lane::UserInput]).highest(), Some(Lane::UserInput));
+        assert_eq!(lanes(&[Lane::Sync, Lane::Idle]).highest(), Some(Lane::Sync));
+    }
+
+    #[test]
+    fn merged_lanes_keep_both_sides() {
+        let mut merged = lanes(&[Lane::Transition]);
+        merged.merge(lanes(&[Lane::Default, Lane::Idle]));
+
+        assert!(merged.contains(Lane::Transition) && merged.contains(Lane::Default) && merged.contains(Lane::Idle));
+        assert!(!merged.contains(Lane::Sync) && !merged.contains(Lane::UserInput));
+        assert_eq!(merged.iter().collect::<Vec<_>>(), vec![Lane::Default, Lane::Transition, Lane::Idle]);
+    }
+
+    #[test]
+    fn earliest_expiration_wins() {
+        let mut times = LaneTimes::default();
+        times.mark(Lane::Transition, 0.0);
+        times.mark(Lane::UserInput, 100.0);
+
+        let waiting = lanes(&[Lane::UserInput, Lane::Transition]);
+
+        assert_eq!(times.next_expiration(waiting), Some(100.0 + USER_INPUT_LANE_TIMEOUT_MS));
+        assert_eq!(times.next_expiration(lanes(&[Lane::Transition])), Some(TRANSITION_LANE_TIMEOUT_MS));
+    }
+
+    #[test]
+    fn the_oldest_update_sets_the_time() {
+        let mut times = LaneTimes::default();
+        times.mark(Lane::Default, 10.0);
+        times.mark(Lane::Default, 20.0);
+
+        assert_eq!(times.next_expiration(lanes(&[Lane::Default])), Some(10.0 + DEFAULT_LANE_TIMEOUT_MS));
+    }
+
+    #[test]
+    fn sync_and_idle_never_expire() {
+        let mut times = LaneTimes::default();
+        times.mark(Lane::Sync, 0.0);
+        times.mark(Lane::Idle, 0.0);
+
+        assert_eq!(times.next_expiration(lanes(&[Lane::Sync, Lane::Idle])), None);
+        assert_eq!(times.next_expiration(lanes(&[Lane::Default])), None);
+    }
+
+    #[test]
+    fn repeatedly_interrupted_transition_still_expires() {
+        let mut times = LaneTimes::default();
+        let mut pending = Lanes::default();
+
+        pending.insert(Lane::Transition);
+        times.mark(Lane::Transition, 0.0);
+
+        // Every render is committed with a newer transition update waiting
+        for i in 1..10 {
+            let rendered = std::mem::take(&mut pending);
+
+            pending.insert(Lane::Transition);
+            times.mark(Lane::Transition, i as f64 * 1000.0);
+
+            times.committed(rendered, pending);
+        }
+
+        assert_eq!(times.next_expiration(pending), Some(TRANSITION_LANE_TIMEOUT_MS));
+    }
+
+    #[test]
+    fn commit_clears_lanes_without_pending_updates() {
+        let mut times = LaneTimes::default();
+        times.mark(Lane::Default, 0.0);
+        times.mark(Lane::Transition, 0.0);
+
+        let pending = lanes(&[Lane::Transition]);
+        times.committed(lanes(&[Lane::Default, Lane::Transition]), pending);
+
+        assert_eq!(times.next_expiration(lanes(&[Lane::Default])), None);
+        assert_eq!(times.next_expiration(pending), Some(TRANSITION_LANE_TIMEOUT_MS));
+    }
+}
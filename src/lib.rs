@@ -1,16 +1,92 @@
+#![allow(clippy::vec_box, clippy::borrowed_box, clippy::not_unsafe_ptr_arg_deref)]
+
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use web_sys::{Element as HTMLElement, Text as HTMLText, Window, Document};
 use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::rc::Rc;
 use std::mem;
 
 mod element;
 mod fiber;
 mod constants;
-use element::{Element, ElementProps, Node};
-use fiber::{Fiber, FiberCell, FiberEffect, FiberParentIterator};
-use constants::{TEXT_ELEMENT, FIBER_ROOT, FIBER_FUNCTIONAL};
+mod pool;
+mod events;
+mod hooks;
+mod forms;
+mod announcer;
+mod navigation;
+mod focus;
+mod async_component;
+mod timers;
+mod worker;
+mod hotkeys;
+mod surface;
+mod media;
+mod display_mode;
+mod observers;
+mod wake_lock;
+mod idle;
+mod broadcast;
+mod blocker;
+mod style;
+mod undo;
+mod aria;
+mod prefetch;
+mod dom_props;
+mod container_query;
+mod scoped_css;
+mod validate;
+mod url_policy;
+mod schema;
+mod before_delete;
+mod invariants;
+mod dev_mode;
+mod lanes;
+mod scheduler;
+mod guardrails;
+mod direction;
+mod promise;
+use element::{Element, ElementProps, Listener, Node};
+use fiber::{Fiber, FiberCell, FiberEffect, FiberParentIterator, FiberSubtreeIterator, owner_note};
+use constants::{
+    TEXT_ELEMENT, FIBER_ROOT, FIBER_FUNCTIONAL, SURFACE_ELEMENT, PORTAL_ELEMENT, RECYCLE_POOL_LIMIT, UNRECYCLABLE_ELEMENTS, FIBER_ID_KEY, NON_BUBBLING_EVENTS, RESERVED_PROPS,
+    ANNOUNCE_THROTTLE_MS, ANNOUNCE_DEDUPE_MS, ANNOUNCE_CLEAR_MS, WORKER_POOL_LIMIT, IDLE_ACTIVITY_EVENTS,
+    BLOCKER_MESSAGE, UNITLESS_STYLE_PROPERTIES, BOOLEAN_ATTRIBUTES, BOOLEAN_PROPERTIES, ENUMERATED_ATTRIBUTES,
+    PASSIVE_EFFECT_BUDGET_MS, UNDO_HISTORY_LIMIT, UNDO_HOTKEYS, REDO_HOTKEYS, SVG_NAMESPACE, XLINK_NAMESPACE, XML_NAMESPACE,
+    ARIA_ATTRIBUTES, ARIA_HIDDEN_ELEMENTS, PREFETCH_CONCURRENCY, DOM_PROPERTIES, PASSIVE_EVENTS,
+    VALIDATION_MAX_DEPTH, VALIDATION_MAX_NODES, SCHEMA_BLOCKED_ELEMENTS, SCHEMA_BLOCKED_PROPS, BEFORE_DELETE_GRACE_MS,
+    HIGHLIGHT_UPDATES_MS, HIGHLIGHT_OUTLINE, HIGHLIGHT_TIMER_KEY, HIGHLIGHT_OUTLINE_KEY, SYNC_FLUSH_LIMIT, USER_INPUT_LANE_TIMEOUT_MS, DEFAULT_LANE_TIMEOUT_MS, TRANSITION_LANE_TIMEOUT_MS, FRAME_BUDGET_MS, FRAME_STATS_SMOOTHING, GUARDRAIL_PLACEHOLDER, LOGICAL_STYLE_PROPERTIES,
+};
+use pool::NodePool;
+use events::{SyntheticEvent, DispatchPhase};
+use hooks::{Hook, HookCell, EventCallback, Effect, Instance, ImperativeHandle, PassiveEffect, PassiveStep};
+use announcer::{Announcer, Politeness};
+use navigation::NavigationFocus;
+use focus::FocusSnapshot;
+use async_component::{AsyncResult, ElementChildren};
+use worker::{WorkerPool, WorkerJob};
+use hotkeys::{Hotkey, HotkeyRegistry, HotkeyBinding};
+use surface::Surface;
+use display_mode::{DisplayMode, DisplayModeBinding};
+use observers::ElementObservers;
+use wake_lock::ScreenWakeLock;
+use idle::IdleTracker;
+use broadcast::ChannelBinding;
+use blocker::BlockerRegistry;
+use undo::UndoableState;
+use prefetch::PrefetchQueue;
+use dom_props::PropTarget;
+use container_query::ContainerQuery;
+use scoped_css::StyleRegistry;
+use url_policy::UrlPolicy;
+use schema::ComponentRegistry;
+use dev_mode::{DevMode, HighlightKeys};
+use lanes::{Lane, Lanes, LaneTimes};
+use scheduler::{Scheduler, SchedulerOptions, FrameStats};
+use guardrails::{Guardrails, GuardrailAction};
+use direction::Direction;
 
 #[wasm_bindgen]
 pub struct Context {
@@ -19,11 +95,87 @@ pub struct Context {
     next_unit_of_work: Option<FiberCell>,
     wip_functional_fiber: Option<FiberCell>,
     effects: Vec<FiberCell>,
+    node_pool: Option<RefCell<NodePool>>,
+    component_replacements: Vec<(Rc<js_sys::Function>, Rc<js_sys::Function>)>,
+    component_owners: Vec<(Rc<js_sys::Function>, Rc<str>)>,
+    pending_event_callbacks: Vec<(EventCallback, js_sys::Function)>,
+    next_fiber_id: u32,
+    passive_effects: Vec<PassiveEffect>,
+    passive_queue: VecDeque<PassiveStep>,
+    error_handler: Option<js_sys::Function>,
+    fiber_registry: HashMap<u32, FiberCell>,
+    container: Option<HTMLElement>,
+    // Where `activate` moves a prerendered tree, the container being the
+    // detached element it's built in until then
+    prerender_target: Option<HTMLElement>,
+    // Rendered into instead of the container when set
+    shadow_root: Option<web_sys::ShadowRoot>,
+    // Containers of portals, which get the delegated listeners as well
+    portal_targets: RefCell<Vec<HTMLElement>>,
+    delegated_events: RefCell<HashMap<(String, bool, bool), js_sys::Function>>,
+    spent_once_listeners: RefCell<HashSet<(u32, String, bool)>>,
+    aria_warnings: RefCell<HashSet<(String, String)>>,
+    content_editable_warnings: RefCell<HashSet<String>>,
+    pending_control_restores: Vec<u32>,
+    // Roots of subtrees the next render refreshes whatever their props
+    invalidated: HashSet<u32>,
+    // Host fibers whose `dir` changed in the render in progress
+    redirected: HashSet<u32>,
+    announcer: Rc<RefCell<Announcer>>,
+    pending_navigation_focus: Option<NavigationFocus>,
+    fiber_id_key: JsValue,
+    highlight_keys: HighlightKeys,
+    composing_fiber: Option<u32>,
+    is_rendering: bool,
+    root_update_deferred: bool,
+    flush_scheduled: bool,
+    batch_depth: u32,
+    // Finished renders wait for an animation frame to commit
+    commit_on_frame: bool,
+    commit_frame_requested: bool,
+    may_commit: bool,
+
+    // Lanes of the updates no render covers yet, of the render in progress
+    // and of updates being scheduled right now. A render started by `render`
+    // or `unmount` has no lane and is never thrown away.
+    pending_lanes: Lanes,
+    render_lanes: Lanes,
+    // Components the render in progress went through, and whether one of
+    // them got an update since
+    rendered_fibers: HashSet<u32>,
+    wip_superseded: bool,
+    wip_lane: Option<Lane>,
+    update_lane: Lane,
+    lane_times: LaneTimes,
+    expiration_timer: Option<i32>,
+    queued_dispatches: Vec<(web_sys::Event, DispatchPhase, bool)>,
+    worker_pool: Rc<RefCell<WorkerPool>>,
+    hotkeys: Rc<RefCell<HotkeyRegistry>>,
+    surfaces: HashMap<u32, Surface>,
+    observers: HashMap<u32, ElementObservers>,
+    wake_lock: Rc<RefCell<ScreenWakeLock>>,
+    idle_tracker: Rc<RefCell<IdleTracker>>,
+    blockers: BlockerRegistry,
+    prefetch_queue: Rc<RefCell<PrefetchQueue>>,
+    styles: StyleRegistry,
+    url_policy: UrlPolicy,
+    schema_components: ComponentRegistry,
+    dev_mode: DevMode,
+    scheduler: Scheduler,
+    frame_stats: FrameStats,
+    guardrails: Guardrails,
+    self_ptr: *mut Context,
     document: Document
 }
 
+impl Default for Context {
+    fn default() -> Self {
+        Self::new(SchedulerOptions::default())
+    }
+}
+
 impl Context {
-    pub fn new() -> Self {
+    pub fn new(scheduler_options: SchedulerOptions) -> Self {
         let window: Window = web_sys::window().unwrap();
         let document: Document = window.document().unwrap();
 
@@ -33,10 +185,111 @@ impl Context {
             next_unit_of_work: None,
             wip_functional_fiber: None,
             effects: Vec::new(),
+            node_pool: None,
+            component_replacements: Vec::new(),
+            component_owners: Vec::new(),
+            pending_event_callbacks: Vec::new(),
+            next_fiber_id: 1,
+            passive_effects: Vec::new(),
+            passive_queue: VecDeque::new(),
+            error_handler: None,
+            fiber_registry: HashMap::new(),
+            container: None,
+            prerender_target: None,
+            shadow_root: None,
+            portal_targets: RefCell::new(Vec::new()),
+            delegated_events: RefCell::new(HashMap::new()),
+            spent_once_listeners: RefCell::new(HashSet::new()),
+            aria_warnings: RefCell::new(HashSet::new()),
+            content_editable_warnings: RefCell::new(HashSet::new()),
+            pending_control_restores: Vec::new(),
+            invalidated: HashSet::new(),
+            redirected: HashSet::new(),
+            announcer: Rc::new(RefCell::new(Announcer::new(document.clone()))),
+            pending_navigation_focus: None,
+            fiber_id_key: JsValue::symbol(Some(FIBER_ID_KEY)),
+            highlight_keys: HighlightKeys::new(),
+            composing_fiber: None,
+            is_rendering: false,
+            root_update_deferred: false,
+            flush_scheduled: false,
+            batch_depth: 0,
+            commit_on_frame: false,
+            commit_frame_requested: false,
+            may_commit: false,
+            pending_lanes: Lanes::default(),
+            render_lanes: Lanes::default(),
+            rendered_fibers: HashSet::new(),
+            wip_superseded: false,
+            wip_lane: None,
+            update_lane: Lane::Default,
+            lane_times: LaneTimes::default(),
+            expiration_timer: None,
+            queued_dispatches: Vec::new(),
+            worker_pool: Rc::new(RefCell::new(WorkerPool::new())),
+            hotkeys: Rc::new(RefCell::new(HotkeyRegistry::new(document.clone()))),
+            surfaces: HashMap::new(),
+            observers: HashMap::new(),
+            wake_lock: Rc::new(RefCell::new(ScreenWakeLock::new(document.clone()))),
+            idle_tracker: Rc::new(RefCell::new(IdleTracker::new(document.clone()))),
+            blockers: BlockerRegistry::new(window.clone()),
+            prefetch_queue: Rc::new(RefCell::new(PrefetchQueue::new(document.clone()))),
+            styles: StyleRegistry::new(document.clone()),
+            url_policy: UrlPolicy::default_for_build(),
+            schema_components: ComponentRegistry::new(),
+            dev_mode: DevMode::for_build(),
+            scheduler: Scheduler::new(scheduler_options),
+            frame_stats: FrameStats::default(),
+            guardrails: Guardrails::new(),
+            self_ptr: std::ptr::null_mut(),
             document
         }
     }
 
+    fn allocate_fiber_id(&mut self) -> u32 {
+        let id = self.next_fiber_id;
+        self.next_fiber_id += 1;
+        id
+    }
+
+    fn find_fiber(&self, id: u32) -> Option<FiberCell> {
+        self.fiber_registry.get(&id).map(Rc::clone)
+    }
+
+    /// Calls the `name` method of the imperative handle closest to `target`
+    /// with `payload`. `target` is a fiber id, or the `data-testid` of a
+    /// host element rendered by the component; the handle is looked up on
+    /// the target fiber and then on its ancestors.
+    pub fn send_command(&self, target: &JsValue, name: &str, payload: &JsValue) -> Result<JsValue, JsValue> {
+        let fiber = match (target.as_f64(), target.as_string()) {
+            (Some(id), _) => self.find_fiber(id as u32),
+            (None, Some(test_id)) => self.fiber_registry.values()
+                .find(|fiber| {
+                    fiber.borrow().props()
+                        .and_then(|props| props.attributes().get("data-testid"))
+                        .is_some_and(|value| *value == test_id)
+                })
+                .map(Rc::clone),
+            (None, None) => None,
+        };
+
+        let fiber = fiber.ok_or_else(|| js_sys::Error::new("Reactron: no mounted component matches the command target"))?;
+
+        let handle = std::iter::once(Rc::clone(&fiber))
+            .chain(fiber.parents())
+            .find_map(|fiber| {
+                fiber.borrow().hooks().into_iter().flatten()
+                    .find_map(|hook| hook.borrow().imperative_handle().map(|handle| handle.handle().clone()))
+            })
+            .ok_or_else(|| js_sys::Error::new("Reactron: the command target has no imperative handle"))?;
+
+        let method = js_sys::Reflect::get(&handle, &name.into())?
+            .dyn_into::<js_sys::Function>()
+            .map_err(|_| js_sys::Error::new(&format!("Reactron: the imperative handle has no '{}' command", name)))?;
+
+        method.call1(&handle, payload)
+    }
+
     fn add_effect(&mut self, effect: FiberCell) {
         self.effects.push(effect);
     }
@@ -45,48 +298,263 @@ impl Context {
         unsafe { Box::from_raw(ptr) }
     }
 
-    fn work_loop(&mut self, did_timeout: bool) -> Result<(), JsValue> {
+    // Events fired and updates scheduled while fibers are being rendered or
+    // committed (a `focus()` in the commit, a `set_state` in a component body)
+    // are held back until the work is done, so they never run against a
+    // half-built tree.
+    //
+    // Passive effects run in batches bounded by PASSIVE_EFFECT_BUDGET_MS, one
+    // per call, so hundreds of them don't make a long task. Whatever is left
+    // of the previous commit's effects runs before the next render starts.
+    //
+    // The render yields once `time_remaining` milliseconds are spent, the
+    // frame budget when none is given, so a call never blocks the main
+    // thread for a whole tree.
+    fn work_loop(&mut self, time_remaining: Option<f64>) -> Result<(), JsValue> {
+        if self.next_unit_of_work.is_some() {
+            self.flush_passive_effects(None);
+        }
+
+        let time_remaining = time_remaining.unwrap_or_else(|| self.scheduler.frame_budget());
+        let started = now();
+        let deadline = started + time_remaining;
+        let is_blocking = self.wip_lane.is_some_and(Lane::is_blocking);
+
+        self.is_rendering = true;
+        self.frame_stats.pass_started();
+        let committed = self.render_and_commit(deadline);
+        self.is_rendering = false;
+
+        // Blocking renders ignore the deadline, so they can't overrun it
+        if !is_blocking {
+            self.frame_stats.pass_finished(time_remaining, now() - started);
+        }
+
+        if committed? {
+            let passive_effects = mem::take(&mut self.passive_effects);
+            self.passive_queue.extend(PassiveStep::from_effects(passive_effects));
+        }
+
+        if !self.passive_queue.is_empty() {
+            self.flush_passive_effects(Some(now() + PASSIVE_EFFECT_BUDGET_MS));
+        }
+
+        // Updates that came in during the render, or that had to wait for a
+        // render of the same or a higher priority to finish
+        if mem::take(&mut self.root_update_deferred) || !self.pending_lanes.is_empty() {
+            self.ensure_render();
+        }
+
+        for (event, phase, passive) in mem::take(&mut self.queued_dispatches) {
+            Context::dispatch_event(self.self_ptr, &event, phase, passive);
+        }
+
+        self.schedule_work();
+
+        Ok(())
+    }
+
+    /// Renders and commits every pending update, whatever its lane, without
+    /// yielding to the browser. For tests, layout measurements right after
+    /// an update and imperative integrations.
+    pub fn flush_sync(&mut self) -> Result<(), JsValue> {
+        let may_commit = mem::replace(&mut self.may_commit, true);
+        let mut result = Ok(());
+
+        for _ in 0..SYNC_FLUSH_LIMIT {
+            if self.wip_root.is_none() && self.pending_lanes.is_empty() && !self.root_update_deferred {
+                break;
+            }
+
+            result = self.work_loop(Some(f64::INFINITY));
+            if result.is_err() {
+                break;
+            }
+        }
+
+        self.may_commit = may_commit;
+        result
+    }
+
+    /// Starts the built-in scheduler: from now on renders, commits and
+    /// passive effects run by themselves, in passes of at most
+    /// `frame_budget` milliseconds. Starting it again only changes the
+    /// budget.
+    pub fn start(&mut self, frame_budget: Option<f64>) -> Result<(), JsValue> {
+        if let Some(frame_budget) = frame_budget {
+            self.scheduler.set_frame_budget(frame_budget);
+        }
+
+        let context_ptr = self.self_ptr;
+        self.scheduler.start(move |pass_time_remaining, from_idle_callback| {
+            let mut context = Context::from_ptr(context_ptr);
+            context.scheduler.pass_started(from_idle_callback);
+
+            let time_remaining = context.scheduler.pass_budget(pass_time_remaining);
+
+            if let Err(error) = context.work_loop(Some(time_remaining)) {
+                web_sys::console::error_2(&"Reactron: scheduled work failed".into(), &error);
+            }
+
+            let _ = Box::into_raw(context);
+        })?;
+
+        self.schedule_work();
+
+        Ok(())
+    }
+
+    // Asks the built-in scheduler, if started, for a pass while there's work
+    // left. Renders in a blocking lane don't wait for an idle period.
+    fn schedule_work(&mut self) {
+        if !self.scheduler.is_started() || !self.has_pending_work() {
+            return;
+        }
+
+        // A render waiting for its commit frame has nothing to do until then
+        let waits_for_frame = self.commit_frame_requested && self.next_unit_of_work.is_none() &&
+            self.pending_lanes.is_empty() && self.passive_queue.is_empty();
+
+        if waits_for_frame {
+            return;
+        }
+
+        let urgent = self.wip_lane.or(self.pending_lanes.highest()).is_some_and(Lane::is_blocking);
+        self.scheduler.request(urgent);
+    }
+
+    // Blocking lanes render to the end. Others check the deadline between
+    // units of work and carry on with the next call once another unit, as
+    // long as they've been taking, would run past it; each call performs at
+    // least one unit, so an exhausted deadline still makes progress.
+    fn render_and_commit(&mut self, deadline: f64) -> Result<bool, JsValue> {
         let mut no_next_unit_of_work = self.next_unit_of_work.is_none();
+        let is_blocking = self.wip_lane.is_some_and(Lane::is_blocking);
+        let performance = web_sys::window().unwrap().performance().unwrap();
 
         loop {
-            if did_timeout || no_next_unit_of_work {
+            if no_next_unit_of_work {
                 break;
             }
 
-            let wip_fiber = Rc::clone(&self.next_unit_of_work.as_ref().unwrap());
+            let started = performance.now();
+            let wip_fiber = Rc::clone(self.next_unit_of_work.as_ref().unwrap());
             self.next_unit_of_work = self.perform_unit_of_work(wip_fiber);
-            
+
+            let finished = performance.now();
+            self.frame_stats.record_unit(finished - started);
+
             no_next_unit_of_work = self.next_unit_of_work.is_none();
+
+            if !no_next_unit_of_work && !is_blocking && !self.frame_stats.fits(finished, deadline) {
+                break;
+            }
         }
 
         if no_next_unit_of_work && self.wip_root.is_some() {
+            if self.commit_on_frame && !is_blocking && !self.may_commit {
+                self.request_commit_frame();
+                return Ok(false);
+            }
+
             self.commit_root()?;
+            return Ok(true);
         }
 
-        Ok(())
+        Ok(false)
+    }
+
+    // Commits the finished render in the next animation frame, so the DOM is
+    // written once per frame however many renders finished in between
+    fn request_commit_frame(&mut self) {
+        if mem::replace(&mut self.commit_frame_requested, true) {
+            return;
+        }
+
+        let context_ptr = self.self_ptr;
+        let commit: js_sys::Function = Closure::once_into_js(move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.commit_frame_requested = false;
+
+            context.may_commit = true;
+            let result = context.work_loop(None);
+            context.may_commit = false;
+
+            if let Err(error) = result {
+                web_sys::console::error_2(&"Reactron: commit failed".into(), &error);
+            }
+
+            let _ = Box::into_raw(context);
+        }).unchecked_into();
+
+        if web_sys::window().unwrap().request_animation_frame(&commit).is_err() {
+            self.commit_frame_requested = false;
+            self.commit_on_frame = false;
+        }
     }
 
     fn perform_unit_of_work(&mut self, wip_fiber: FiberCell) -> Option<FiberCell> {
         let is_functional_tree = wip_fiber.borrow().is_functional_tree();
+        let mut is_frozen = false;
 
         if is_functional_tree {
+            let owner = self.component_owner(wip_fiber.borrow().component_function().unwrap());
+            wip_fiber.borrow_mut().set_owner(owner);
+            self.rendered_fibers.insert(wip_fiber.borrow().id());
+
             let fiber = wip_fiber.borrow();
 
-            let func = Rc::clone(&fiber.component_function().unwrap());
-            let props = Rc::clone(&fiber.component_function_props().unwrap());
+            let func = Rc::clone(fiber.component_function().unwrap());
+            let props = Rc::clone(fiber.component_function_props().unwrap());
+
+            // An async component isn't called again while its props are the same
+            let async_result = fiber.alternate()
+                .and_then(|alternate| alternate.borrow().async_result().cloned())
+                .filter(|async_result| async_result.props_match(&props) && !self.is_invalidated(&fiber));
 
             // Drop the borrow so it can be borrowed from 'use_state'
             mem::drop(fiber);
 
-            self.wip_functional_fiber = Some(Rc::clone(&wip_fiber));
-            let child = self.execute_function_component(func, props);
-            self.wip_functional_fiber = None;
+            let (children, async_result) = match async_result {
+                Some(async_result) => (async_result.resolved(), Some(async_result)),
+                None => {
+                    if self.dev_mode.strict_render {
+                        self.render_discarded(&wip_fiber, Rc::clone(&func), Rc::clone(&props));
+                    }
+
+                    self.wip_functional_fiber = Some(Rc::clone(&wip_fiber));
+
+                    // A component that throws renders nothing, and the error
+                    // handler is told. There are no error boundaries.
+                    let output = match self.execute_function_component(func, Rc::clone(&props)) {
+                        Ok(output) => output,
+                        Err(error) => {
+                            self.report_error(&error, &wip_fiber.borrow().component_stack());
+                            JsValue::NULL
+                        },
+                    };
+
+                    // The body of a generator only starts running with its first stage
+                    let rendered = if let Some(stages) = async_component::as_iterator(&output) {
+                        let async_result = self.render_stages(&wip_fiber, stages, props);
+                        (async_result.resolved(), Some(async_result))
+                    } else {
+                        match output.dyn_into::<js_sys::Promise>() {
+                            Ok(promise) => (None, Some(self.await_component(&wip_fiber, promise, props))),
+                            Err(output) => (Self::element_children_of(&output), None),
+                        }
+                    };
+
+                    self.wip_functional_fiber = None;
+                    rendered
+                }
+            };
 
             let mut fiber = wip_fiber.borrow_mut();
+            fiber.set_async_result(async_result);
 
-            if let Some(child) = child {
-                let children_vec = vec![child];
-                fiber.set_element_children(Some(Rc::new(RefCell::new(children_vec))));
+            if let Some(children) = children {
+                fiber.set_element_children(Some(children));
             }
 
             self.reconcile_children(&wip_fiber, &mut fiber);
@@ -97,9 +565,20 @@ impl Context {
                 let dom_node = self.create_dom_node(&fiber);
 
                 fiber.set_dom_node(Rc::new(RefCell::new(dom_node)));
+
+                if self.dev_mode.warnings {
+                    self.check_content_editable(&fiber);
+                }
             }
 
-            self.reconcile_children(&wip_fiber, &mut fiber);
+            // After mount the content of an uncontrolled region is the user's
+            is_frozen = fiber.alternate().is_some() && fiber.props().is_some_and(|props| props.uncontrolled_children());
+
+            if is_frozen {
+                Self::adopt_children(&wip_fiber, &mut fiber);
+            } else {
+                self.reconcile_children(&wip_fiber, &mut fiber);
+            }
         }
 
         let fiber = wip_fiber.borrow_mut();
@@ -110,7 +589,7 @@ impl Context {
         }
 
         // If fiber has a child, make it the next unit of work
-        if let Some(fiber_child) = &fiber.child() {
+        if let Some(fiber_child) = fiber.child().as_ref().filter(|_| !is_frozen) {
             // console_log!("{} has a child", &fiber.element_type());
             return Some(Rc::clone(fiber_child));
 
@@ -133,47 +612,569 @@ impl Context {
             }
         }
 
-        return None;
+        None
+    }
+
+    // Records an update in the lane it's scheduled from. The render for it
+    // starts in a microtask, so every update from the same task, whether
+    // from an event handler, a timeout or a promise, lands in one render.
+    fn schedule_root_update(&mut self) {
+        self.pending_lanes.insert(self.update_lane);
+        self.lane_times.mark(self.update_lane, now());
+
+        if self.is_rendering {
+            self.root_update_deferred = true;
+            return;
+        }
+
+        if self.batch_depth > 0 || mem::replace(&mut self.flush_scheduled, true) {
+            return;
+        }
+
+        let context_ptr = self.self_ptr;
+        let flush: js_sys::Function = Closure::once_into_js(move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.flush_updates();
+            let _ = Box::into_raw(context);
+        }).unchecked_into();
+
+        web_sys::window().unwrap().queue_microtask(&flush);
+    }
+
+    // An update to a component the render in progress already went past
+    // would be lost when it commits, the component's hooks having been read
+    // before it. The render is started over from the committed tree instead.
+    fn schedule_fiber_update(&mut self, fiber_id: u32) {
+        if !self.is_rendering && self.rendered_fibers.contains(&fiber_id) {
+            self.wip_superseded = true;
+        }
+
+        self.schedule_root_update();
+    }
+
+    // Starts the render for the updates batched so far
+    fn flush_updates(&mut self) {
+        self.flush_scheduled = false;
+
+        if self.batch_depth == 0 && !self.is_rendering {
+            self.ensure_render();
+        }
+    }
+
+    // Starts a render for the most urgent pending lane. A render in progress
+    // carries on if it's as urgent, the pending updates waiting for its
+    // commit; a less urgent one is thrown away and started over.
+    fn ensure_render(&mut self) {
+        let lane = match self.pending_lanes.highest() {
+            Some(lane) => lane,
+            None => return,
+        };
+
+        // The first render has no committed tree to start over from, so
+        // updates always wait for it
+        if self.wip_root.is_some() {
+            match self.wip_lane {
+                _ if self.current_root.is_none() => return,
+                Some(wip_lane) if lane < wip_lane => self.discard_render(),
+                _ if mem::take(&mut self.wip_superseded) => self.discard_render(),
+                _ => return,
+            }
+        }
+
+        self.render_lanes = mem::take(&mut self.pending_lanes);
+        self.wip_lane = Some(lane);
+        self.start_render();
+        self.expire_lanes();
+    }
+
+    // Turns the render in progress synchronous once an update it covers
+    // waited past its lane's timeout, so it can't be put off forever by more
+    // urgent updates or a busy main thread. Until then a timer watches for it.
+    fn expire_lanes(&mut self) {
+        if self.wip_lane.is_none_or(Lane::is_blocking) {
+            return;
+        }
+
+        let expiration = match self.lane_times.next_expiration(self.render_lanes) {
+            Some(expiration) => expiration,
+            None => return,
+        };
+
+        if expiration <= now() {
+            self.wip_lane = Some(Lane::Sync);
+            self.schedule_work();
+            return;
+        }
+
+        if let Some(timer) = self.expiration_timer.take() {
+            web_sys::window().unwrap().clear_timeout_with_handle(timer);
+        }
+
+        let context_ptr = self.self_ptr;
+        self.expiration_timer = Some(timers::set_timeout((expiration - now()).ceil() as i32, move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.expiration_timer = None;
+            context.expire_lanes();
+            let _ = Box::into_raw(context);
+        }));
+    }
+
+    // Drops the tree in progress and whatever its render queued. Components
+    // it mounted never commit, so what their hooks subscribed to is released.
+    fn discard_render(&mut self) {
+        let wip_root = match self.wip_root.take() {
+            Some(wip_root) => wip_root,
+            None => return,
+        };
+
+        self.next_unit_of_work = None;
+        self.wip_functional_fiber = None;
+        self.rendered_fibers.clear();
+        self.wip_superseded = false;
+        self.effects.clear();
+        self.passive_effects.clear();
+        self.pending_event_callbacks.clear();
+
+        let mounted: Vec<FiberCell> = wip_root.subtree()
+            .filter(|fiber| {
+                let fiber = fiber.borrow();
+                fiber.alternate().is_none() && fiber.parent().as_ref().is_none_or(|parent| parent.borrow().alternate().is_some())
+            })
+            .collect();
+
+        for fiber in mounted {
+            self.queue_unmount_effects(&fiber);
+        }
+
+        let render_lanes = mem::take(&mut self.render_lanes);
+        self.pending_lanes.merge(render_lanes);
+        self.wip_lane = None;
+    }
+
+    // Starts a new render pass from the current root, reusing its element tree
+    fn start_render(&mut self) {
+        let current_root = self.current_root.as_ref();
+        let mut root = Fiber::new_root();
+
+        if let Some(current_root) = current_root {
+            root.set_alternate(Rc::clone(current_root));
+
+            let current_root = current_root.borrow();
+            root.set_id(current_root.id());
+
+            if let Some(children) = current_root.element_children().as_ref() {
+                root.set_element_children(Some(Rc::clone(children)));
+            }
+
+            // Store the container HTML element
+            if let Some(dom_node) = current_root.dom_node().as_ref() {
+                root.set_dom_node(Rc::clone(dom_node));
+            }
+        }
+
+        // Make it the Work in Progress Root and the Next Unit of Work
+        let root = Rc::new(RefCell::new(Box::new(root)));
+        self.wip_root = Some(Rc::clone(&root));
+        self.next_unit_of_work = Some(Rc::clone(&root));
+        self.schedule_work();
+    }
+
+    // Starts a render pass from the current root with no children, which
+    // deletes the whole tree
+    fn unmount_root(&mut self) {
+        let current_root = match self.current_root.as_ref() {
+            Some(current_root) => Rc::clone(current_root),
+            None => return,
+        };
+
+        // Updates in progress are covered by the unmount
+        self.discard_render();
+
+        let mut root = Fiber::new_root();
+        root.set_alternate(Rc::clone(&current_root));
+        root.set_id(current_root.borrow().id());
+        root.set_element_children(Some(Rc::new(RefCell::new(Vec::new()))));
+
+        if let Some(dom_node) = current_root.borrow().dom_node().as_ref() {
+            root.set_dom_node(Rc::clone(dom_node));
+        }
+
+        let root = Rc::new(RefCell::new(Box::new(root)));
+        self.wip_root = Some(Rc::clone(&root));
+        self.next_unit_of_work = Some(Rc::clone(&root));
+        self.schedule_work();
+    }
+
+    /// Moves a tree built by `prerender` into the container it was built
+    /// for, replacing what the container holds. A render still in progress
+    /// is finished first. The root keeps rendering into the container from
+    /// then on.
+    pub fn activate_prerender(&mut self) -> Result<(), JsValue> {
+        let target = match self.prerender_target.take() {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+
+        self.flush_sync()?;
+
+        let previous_root_node = self.root_node().cloned();
+        let staging = self.container.replace(target.clone());
+        target.set_text_content(None);
+
+        if let Some(staging) = staging {
+            while let Some(child) = staging.first_child() {
+                target.append_child(&child)?;
+            }
+        }
+
+        self.move_delegated_listeners(previous_root_node);
+
+        // Fibers share the root's DOM node, so this moves all of them over
+        if let Some(dom_node) = self.current_root.as_ref().and_then(|root| root.borrow().dom_node().cloned()) {
+            *dom_node.borrow_mut() = Node::Element(target);
+        }
+
+        Ok(())
+    }
+
+    /// Renders each element into its container as a single tree, so the
+    /// widgets share the work loop budget and commit together. Each container
+    /// gets a portal of its own, which receives the delegated listeners, under
+    /// a root on a detached element that claims nothing in the document.
+    pub fn render_many(&mut self, pairs: Vec<(Box<Element>, HTMLElement)>) {
+        self.discard_render();
+
+        let children = pairs.into_iter()
+            .map(|(element, container)| element::portal(vec![element], container))
+            .collect::<Vec<Box<Element>>>();
+
+        let mut root = Fiber::new_root();
+        root.set_element_children(Some(Rc::new(RefCell::new(children))));
+
+        // Later batches keep the detached element of the first
+        let root_dom_node = self.current_root.as_ref()
+            .filter(|_| self.container.is_none())
+            .and_then(|current_root| current_root.borrow().dom_node().cloned())
+            .unwrap_or_else(|| Rc::new(RefCell::new(Node::Element(self.document.create_element("div").unwrap()))));
+
+        let previous_root_node = self.root_node().cloned();
+        self.shadow_root = None;
+        self.container = None;
+        self.move_delegated_listeners(previous_root_node);
+        root.set_dom_node(root_dom_node);
+
+        match self.current_root.as_ref() {
+            Some(current_root) => {
+                root.set_alternate(Rc::clone(current_root));
+                root.set_id(current_root.borrow().id());
+            },
+            None => root.set_id(self.allocate_fiber_id()),
+        }
+
+        let root = Rc::new(RefCell::new(Box::new(root)));
+        self.wip_root = Some(Rc::clone(&root));
+        self.next_unit_of_work = Some(Rc::clone(&root));
+        self.schedule_work();
+    }
+
+    /// Re-renders the subtree of `fiber_id` on the next pass even if its
+    /// props and state are the same: async components are called again and
+    /// every host node gets all of its props written anew. Returns false for
+    /// unknown ids.
+    pub fn invalidate_subtree(&mut self, fiber_id: u32) -> bool {
+        if self.find_fiber(fiber_id).is_none() {
+            return false;
+        }
+
+        self.invalidated.insert(fiber_id);
+        self.schedule_root_update();
+
+        true
+    }
+
+    fn is_invalidated(&self, fiber: &Fiber) -> bool {
+        Self::is_marked(&self.invalidated, fiber)
+    }
+
+    // Whether `fiber` or one of its ancestors is in `marked`
+    fn is_marked(marked: &HashSet<u32>, fiber: &Fiber) -> bool {
+        if marked.is_empty() {
+            return false;
+        }
+
+        marked.contains(&fiber.id()) || fiber.parent().as_ref().is_some_and(|parent| {
+            std::iter::once(Rc::clone(parent))
+                .chain(parent.parents())
+                .any(|ancestor| marked.contains(&ancestor.borrow().id()))
+        })
+    }
+
+    // Updates start rendering in a microtask, so right after a handler's
+    // `set_state` the render is only pending
+    fn is_render_pending(&self) -> bool {
+        self.wip_root.is_some() || self.flush_scheduled || self.root_update_deferred || !self.pending_lanes.is_empty()
+    }
+
+    fn has_pending_work(&self) -> bool {
+        self.wip_root.is_some() || self.root_update_deferred || !self.pending_lanes.is_empty() || !self.passive_queue.is_empty()
+    }
+
+    // Owners are registered on the component as first rendered; a hot
+    // replaced version keeps its owner
+    fn component_owner(&self, func: &Rc<js_sys::Function>) -> Option<Rc<str>> {
+        self.component_owners.iter()
+            .find(|(component, _)| **component == **func || *self.resolve_component_function(component) == **func)
+            .map(|(_, owner)| Rc::clone(owner))
+    }
+
+    fn resolve_component_function(&self, func: &Rc<js_sys::Function>) -> Rc<js_sys::Function> {
+        self.component_replacements.iter()
+            .find(|(old_func, _)| **old_func == **func)
+            .map_or_else(|| Rc::clone(func), |(_, new_func)| Rc::clone(new_func))
     }
 
     fn execute_function_component(
         &self,
         func: Rc<js_sys::Function>,
         props: Rc<JsValue>
-    ) -> Option<Box<Element>> {
+    ) -> Result<JsValue, JsValue> {
         func.call1(&JsValue::null(), &props)
-            .unwrap()
-            .as_f64()
+    }
+
+    // Strict rendering: the component renders once more into a throwaway
+    // fiber first, so side effects in its body show up doubled. The effects
+    // and callbacks that render queued are dropped with its output, apart
+    // from elements it got through its props, which the real call uses
+    // again. Mounts and async components are left out, as hooks subscribe
+    // and promises start on the first call.
+    fn render_discarded(&mut self, wip_fiber: &FiberCell, func: Rc<js_sys::Function>, props: Rc<JsValue>) {
+        let fiber = wip_fiber.borrow();
+
+        let alternate = match fiber.alternate() {
+            Some(alternate) if alternate.borrow().async_result().is_none() => Rc::clone(alternate),
+            _ => return,
+        };
+
+        let mut scratch = Fiber::new(fiber.element_type());
+        scratch.set_id(fiber.id());
+        scratch.set_alternate(alternate);
+        scratch.set_component_function(Some(Rc::clone(&func)));
+        scratch.set_component_function_props(Some(Rc::clone(&props)));
+        scratch.set_hooks(Some(vec![]));
+
+        if let Some(parent) = fiber.parent() {
+            scratch.set_parent(Rc::clone(parent));
+        }
+        mem::drop(fiber);
+
+        let passive_effects = self.passive_effects.len();
+        let pending_event_callbacks = self.pending_event_callbacks.len();
+
+        self.wip_functional_fiber = Some(Rc::new(RefCell::new(Box::new(scratch))));
+        let borrowed = Self::elements_in_props(&props);
+        // The real render reports what this one throws
+        let output = self.execute_function_component(func, props).unwrap_or(JsValue::NULL);
+        self.wip_functional_fiber = None;
+
+        self.passive_effects.truncate(passive_effects);
+        self.pending_event_callbacks.truncate(pending_event_callbacks);
+
+        if let Some(child_ptr) = output.as_f64() {
+            Element::from_ptr(child_ptr as u32 as *mut Element).free_except(&borrowed);
+        }
+    }
+
+    // Elements are numbers, passed as a prop of their own, like a
+    // placeholder, or in an array, like `children`
+    fn elements_in_props(props: &JsValue) -> HashSet<*const Element> {
+        let values = match props.dyn_ref::<js_sys::Object>() {
+            Some(props) => js_sys::Object::values(props),
+            None => return HashSet::new(),
+        };
+
+        values.iter()
+            .flat_map(|value| match value.dyn_into::<js_sys::Array>() {
+                Ok(items) => items.iter().collect(),
+                Err(value) => vec![value],
+            })
+            .filter_map(|value| value.as_f64())
+            .map(|ptr| ptr as u32 as *const Element)
+            .collect()
+    }
+
+    fn element_children_of(output: &JsValue) -> Option<ElementChildren> {
+        output.as_f64()
             .map(|child_ptr| Element::from_ptr(child_ptr as u32 as *mut Element))
+            .map(|child| Rc::new(RefCell::new(vec![child])))
+    }
+
+    // The component renders nothing until the promise settles. Its element
+    // is then kept on the fiber and the tree re-rendered.
+    fn await_component(&self, wip_fiber: &FiberCell, promise: js_sys::Promise, props: Rc<JsValue>) -> AsyncResult {
+        let async_result = AsyncResult::new(props);
+        let component_stack = wip_fiber.borrow().component_stack();
+        let context_ptr = self.self_ptr;
+
+        let on_resolve = {
+            let async_result = async_result.clone();
+
+            Closure::once_into_js(move |output: JsValue| {
+                async_result.resolve(output.as_f64().map(|child_ptr| Element::from_ptr(child_ptr as u32 as *mut Element)));
+
+                let mut context = Context::from_ptr(context_ptr);
+                context.schedule_root_update();
+                let _ = Box::into_raw(context);
+            })
+        };
+
+        let on_reject = Closure::once_into_js(move |error: JsValue| {
+            let context = Context::from_ptr(context_ptr);
+            context.report_error(&error, &component_stack);
+            let _ = Box::into_raw(context);
+        });
+
+        if let Err(error) = promise::then(&promise, &on_resolve, &on_reject) {
+            self.report_error(&error, &wip_fiber.borrow().component_stack());
+        }
+
+        async_result
+    }
+
+    // A generator component renders what it yields, one stage per commit. The
+    // first stage renders right away, each later one once the previous stage
+    // committed, until the generator is done or the component got new props.
+    fn render_stages(&self, wip_fiber: &FiberCell, stages: js_sys::Iterator, props: Rc<JsValue>) -> AsyncResult {
+        let async_result = AsyncResult::new(props);
+        let fiber_id = wip_fiber.borrow().id();
+        let component_stack = wip_fiber.borrow().component_stack();
+
+        if self.advance_stage(&stages, &async_result, &component_stack) {
+            self.schedule_next_stage(fiber_id, stages, async_result.clone(), component_stack);
+        }
+
+        async_result
+    }
+
+    fn schedule_next_stage(&self, fiber_id: u32, stages: js_sys::Iterator, async_result: AsyncResult, component_stack: String) {
+        let context_ptr = self.self_ptr;
+
+        timers::set_timeout(0, move || {
+            let mut context = Context::from_ptr(context_ptr);
+
+            if context.wip_root.is_some() {
+                context.schedule_next_stage(fiber_id, stages, async_result, component_stack);
+            } else {
+                let is_mounted = context.find_fiber(fiber_id).is_some_and(|fiber| {
+                    fiber.borrow().async_result().is_some_and(|current| current.is(&async_result))
+                });
+
+                if is_mounted && context.advance_stage(&stages, &async_result, &component_stack) {
+                    context.schedule_root_update();
+                    context.schedule_next_stage(fiber_id, stages, async_result, component_stack);
+                }
+            }
+
+            let _ = Box::into_raw(context);
+        });
+    }
+
+    // Returns whether the generator yielded another stage
+    fn advance_stage(&self, stages: &js_sys::Iterator, async_result: &AsyncResult, component_stack: &str) -> bool {
+        match stages.next() {
+            Ok(step) if !step.done() => {
+                let stage = step.value().as_f64().map(|child_ptr| Element::from_ptr(child_ptr as u32 as *mut Element));
+                async_result.resolve(stage);
+                true
+            },
+            Ok(_) => false,
+            Err(error) => {
+                self.report_error(&error, component_stack);
+                false
+            },
+        }
     }
 
     fn create_dom_node(&self, fiber: &Fiber) -> Node {
         let props = fiber.props().unwrap();
 
-        if fiber.is_text_fiber() {
-            let node: HTMLText = self.document.create_text_node(&props.node_value().unwrap());
+        if let Some(target) = props.portal_target().filter(|_| fiber.is_portal_fiber()) {
+            self.add_portal_target(target);
+
+            Node::Element(target.clone())
+        } else if fiber.is_text_fiber() {
+            let node: HTMLText = self.document.create_text_node(props.node_value().unwrap());
+            events::tag_node(&node, &self.fiber_id_key, fiber.id());
 
             Node::Text(node)
         } else {
-            let node = self.document.create_element(fiber.element_type()).unwrap();
-            self.update_dom_node(&node, None, &props);
+            let tag_name = if fiber.is_surface_fiber() { "canvas" } else { fiber.element_type() };
+            let is_svg = tag_name == "svg" || Self::in_svg_subtree(fiber);
+
+            // Pooled nodes are all HTML elements
+            let recycled = self.node_pool.as_ref()
+                .filter(|_| !is_svg)
+                .and_then(|pool| pool.borrow_mut().take(fiber.element_type()));
+
+            let node = match recycled {
+                Some(node) => node,
+                None if is_svg => self.document.create_element_ns(Some(SVG_NAMESPACE), tag_name).unwrap(),
+                None => self.document.create_element(tag_name).unwrap(),
+            };
+            events::tag_node(&node, &self.fiber_id_key, fiber.id());
+            self.update_dom_node(fiber, &node, None, props);
 
             Node::Element(node)
         }
     }
 
-    fn update_dom_node(&self, dom_node: &HTMLElement, prev_props: Option<&Box<ElementProps>>, next_props: &Box<ElementProps>) {
+    // Shadow roots can't be attached twice, so later renders into the same
+    // host reuse the one attached first
+    fn attach_shadow_root(&self, host: &HTMLElement, mode: &str) -> web_sys::ShadowRoot {
+        if let Some(shadow_root) = self.shadow_root.as_ref().filter(|shadow_root| shadow_root.host() == *host) {
+            return shadow_root.clone();
+        }
+
+        if let Some(shadow_root) = host.shadow_root() {
+            return shadow_root;
+        }
+
+        let mode = match mode {
+            "closed" => web_sys::ShadowRootMode::Closed,
+            _ => web_sys::ShadowRootMode::Open,
+        };
+
+        host.attach_shadow(&web_sys::ShadowRootInit::new(mode)).unwrap()
+    }
+
+    // Whether the closest host ancestor is an SVG element other than
+    // `foreignObject`, whose children are HTML again
+    fn in_svg_subtree(fiber: &Fiber) -> bool {
+        let parent = match fiber.parent() {
+            Some(parent) => Rc::clone(parent),
+            None => return false,
+        };
+
+        let parent_node = std::iter::once(Rc::clone(&parent))
+            .chain(parent.parents())
+            .find_map(|ancestor| ancestor.borrow().dom_node().map(Rc::clone));
+
+        let is_svg = parent_node.is_some_and(|dom_node| match &*dom_node.borrow() {
+            Node::Element(node) => {
+                node.namespace_uri().as_deref() == Some(SVG_NAMESPACE) && node.local_name() != "foreignObject"
+            },
+            Node::Text(_) | Node::Shadow(_) => false,
+        });
+
+        is_svg
+    }
+
+    fn update_dom_node(&self, fiber: &Fiber, dom_node: &HTMLElement, prev_props: Option<&Box<ElementProps>>, next_props: &Box<ElementProps>) {
+        let owner = fiber.owner();
+        let owner = owner.as_deref();
+
         let prev_class_name = prev_props.and_then(|p| p.class_name());
         let next_class_name = next_props.class_name();
 
-        let prev_on_click = prev_props.and_then(|p| p.on_click());
-        let next_on_click = next_props.on_click();
-        let prev_on_change = prev_props.and_then(|p| p.on_change());
-        let next_on_change = next_props.on_change();
-        let prev_on_blur = prev_props.and_then(|p| p.on_blur());
-        let next_on_blur = next_props.on_blur();
-        let prev_on_keydown = prev_props.and_then(|p| p.on_keydown());
-        let next_on_keydown = next_props.on_keydown();
         let prev_input_type = prev_props.and_then(|p| p.input_type());
         let next_input_type = next_props.input_type();
         let prev_input_value = prev_props.and_then(|p| p.input_value());
@@ -183,44 +1184,59 @@ impl Context {
         let prev_input_checked = prev_props.and_then(|p| p.input_checked());
         let next_input_checked = next_props.input_checked();
 
-        // Class name
+        // Class name. `className` is a read-only SVGAnimatedString on SVG
+        // elements, so it goes through the attribute.
         match (prev_class_name, next_class_name) {
             (Some(prev), Some(next)) => {
                 if *prev != *next {
-                    dom_node.set_class_name(next);
+                    let _ = dom_node.set_attribute("class", next);
                 }
             },
             (None, Some(next)) => {
-                dom_node.set_class_name(next);
+                let _ = dom_node.set_attribute("class", next);
+            },
+            (Some(_), None) => {
+                let _ = dom_node.remove_attribute("class");
             },
             (_, _) => {}
         }
 
         // Input type
         match (prev_input_type, next_input_type) {
-            (None, Some(next)) => {
+            (prev, Some(next)) if prev != Some(next) => {
                 dom_node.unchecked_ref::<web_sys::HtmlInputElement>()
                     .set_type(next);
             },
+            (Some(_), None) => {
+                let _ = dom_node.remove_attribute("type");
+            },
             (_, _) => {}
         }
 
         // Input value
         match (prev_input_value, next_input_value) {
             (_, Some(next)) => {
-                dom_node.unchecked_ref::<web_sys::HtmlInputElement>()
-                    .set_value(next);
+                if !self.is_composing(dom_node) {
+                    forms::set_control_value(dom_node, next);
+                }
             },
-            (_, _) => {}
+            (Some(_), None) => forms::reset_control_value(dom_node),
+            (None, None) => {}
         }
 
         // Input checked
         match (prev_input_checked, next_input_checked) {
-            (_, Some(next)) => {
-                dom_node.unchecked_ref::<web_sys::HtmlInputElement>()
-                    .set_checked(next);
-            },
-            (_, _) => {}
+            (_, Some(next)) => forms::set_control_checked(dom_node, next),
+            (Some(_), None) => forms::reset_control_checked(dom_node),
+            (None, None) => {}
+        }
+
+        // Media source stream or Blob
+        let prev_src_object = prev_props.and_then(|p| p.src_object());
+        let next_src_object = next_props.src_object();
+
+        if prev_src_object != next_src_object {
+            media::set_src_object(dom_node, next_src_object);
         }
 
         // Input placeholder
@@ -234,59 +1250,487 @@ impl Context {
             (None, Some(next)) => {
                 dom_node.unchecked_ref::<web_sys::HtmlInputElement>()
                     .set_placeholder(next);
+            },
+            (Some(_), None) => {
+                let _ = dom_node.remove_attribute("placeholder");
+            },
+            (None, None) => {}
+        }
+
+        // Attributes, or properties where the element only reads those
+        let changes = element::attribute_changes(prev_props.map(|p| p.attributes()), next_props.attributes());
+
+        for (name, value) in changes.set {
+            if self.dev_mode.warnings {
+                self.check_aria_attribute(dom_node, name, owner);
             }
-            (_, _) => {}
+
+            if !self.check_url(dom_node, name, value, owner) {
+                let _ = dom_node.remove_attribute(name);
+                continue;
+            }
+
+            let _ = match (dom_props::resolve(dom_node, name), attribute_namespace(name)) {
+                (PropTarget::Property, _) => js_sys::Reflect::set(dom_node, &name.into(), &value.into()).map(|_| ()),
+                (PropTarget::Attribute, Some(namespace)) => dom_node.set_attribute_ns(Some(namespace), name, value),
+                (PropTarget::Attribute, None) => dom_node.set_attribute(name, value),
+            };
         }
 
-        self.update_listener(
-            "click",
-            &prev_on_click,
-            &next_on_click,
-            &dom_node
-        );
+        for name in changes.removed {
+            let _ = match (dom_props::resolve(dom_node, name), attribute_namespace(name), name.split_once(':')) {
+                (PropTarget::Property, _, _) if dom_props::is_custom_element(&dom_node.local_name()) => {
+                    js_sys::Reflect::set(dom_node, &name.into(), &JsValue::UNDEFINED).map(|_| ())
+                },
+                // Built-in properties such as an input's value go back to their default
+                (PropTarget::Property, _, _) => {
+                    js_sys::Reflect::set(dom_node, &name.into(), &dom_props::default_value(dom_node, name)).map(|_| ())
+                },
+                (_, Some(namespace), Some((_, local_name))) => dom_node.remove_attribute_ns(Some(namespace), local_name),
+                (_, _, _) => dom_node.remove_attribute(name),
+            };
+        }
 
-        self.update_listener(
-            "change",
-            &prev_on_change,
-            &next_on_change,
-            &dom_node
-        );
+        // Object props of custom elements
+        let prev_rich_props = prev_props.map(|p| p.rich_props());
 
-        self.update_listener(
-            "blur",
-            &prev_on_blur,
-            &next_on_blur,
-            &dom_node
-        );
+        for name in prev_rich_props.into_iter().flat_map(|rich_props| rich_props.keys()) {
+            if !next_props.rich_props().contains_key(name) {
+                let _ = js_sys::Reflect::set(dom_node, &name.into(), &JsValue::UNDEFINED);
+            }
+        }
 
-        self.update_listener(
-            "keydown",
-            &prev_on_keydown,
-            &next_on_keydown,
-            &dom_node
-        );
-    }
+        for (name, value) in next_props.rich_props() {
+            if prev_rich_props.and_then(|rich_props| rich_props.get(name)) != Some(value) {
+                let _ = js_sys::Reflect::set(dom_node, &name.into(), value.value());
+            }
+        }
 
-    fn update_listener(
-        &self,
-        event_type: &str,
-        prev_listener: &Option<&js_sys::Function>,
-        next_listener: &Option<&js_sys::Function>,
-        dom_node: &HTMLElement,
-    ) {
-        match (prev_listener, next_listener) {
-            (None, Some(callback)) => {
-                dom_node.add_event_listener_with_callback(event_type, callback).unwrap();
+        // Boolean properties, back to false once the prop is gone
+        let prev_properties = prev_props.map(|p| p.properties());
+
+        for name in prev_properties.into_iter().flat_map(|properties| properties.keys()) {
+            if !next_props.properties().contains_key(name) {
+                let _ = js_sys::Reflect::set(dom_node, &name.into(), &JsValue::FALSE);
+            }
+        }
+
+        for (name, value) in next_props.properties() {
+            let current = js_sys::Reflect::get(dom_node, &name.into()).ok().and_then(|value| value.as_bool());
+
+            if current != Some(*value) {
+                let _ = js_sys::Reflect::set(dom_node, &name.into(), &JsValue::from(*value));
+            }
+        }
+
+        // Style object. A style string is a plain attribute, written above,
+        // which replaces every declaration of a previous object.
+        match (prev_props.and_then(|p| p.style()), next_props.style()) {
+            (prev, Some(next)) => {
+                let direction = Direction::of(fiber, &self.document);
+                let prev_direction = fiber.alternate()
+                    .filter(|_| prev.is_some() && Self::is_marked(&self.redirected, fiber))
+                    .map(|alternate| Direction::of(&alternate.borrow(), &self.document));
+
+                match prev_direction {
+                    // Declarations mirrored for the old direction are taken back first
+                    Some(prev_direction) if prev_direction != direction => {
+                        style::update_style(dom_node, prev, &Default::default(), prev_direction);
+                        style::update_style(dom_node, None, next, direction);
+                    },
+                    _ => style::update_style(dom_node, prev, next, direction),
+                }
             },
-            (Some(callback), None) => {
-                dom_node.remove_event_listener_with_callback(event_type, callback).unwrap();
+            (Some(_), None) => {
+                if !next_props.attributes().contains_key("style") {
+                    let _ = dom_node.remove_attribute("style");
+                }
             },
-            (Some(prev_callback), Some(next_callback)) => {
-                dom_node.remove_event_listener_with_callback(event_type, prev_callback).unwrap();
-                dom_node.add_event_listener_with_callback(event_type, next_callback).unwrap();
+            (None, None) => {}
+        }
+
+        // Handlers are looked up in the committed props on dispatch, so a
+        // removed handler prop is detached as soon as its fiber commits. Only
+        // its `once` bookkeeping has to go, in case it comes back later.
+        if let Some(prev_props) = prev_props {
+            let fiber_id = events::own_fiber_id(dom_node, &self.fiber_id_key);
+            let mut spent_once_listeners = self.spent_once_listeners.borrow_mut();
+
+            for (listener_type, capture) in removed_listeners(prev_props, next_props) {
+                if let Some(fiber_id) = fiber_id {
+                    spent_once_listeners.remove(&(fiber_id, listener_type, capture));
+                }
+            }
+        }
+
+        // Event listeners are attached once per event type and phase to the root container
+        for (listener_type, listener) in next_props.listeners() {
+            for event_type in forms::native_events(listener_type) {
+                self.ensure_delegated_listener(DispatchPhase::for_listener(event_type, listener, false), event_type, listener.passive());
+            }
+        }
+
+        for (listener_type, listener) in next_props.capture_listeners() {
+            for event_type in forms::native_events(listener_type) {
+                self.ensure_delegated_listener(DispatchPhase::for_listener(event_type, listener, true), event_type, listener.passive());
+            }
+        }
+
+        // Browsers only fire `drop` on targets that cancelled the `dragover` before it
+        if next_props.listener("drop").is_some() || next_props.capture_listener("drop").is_some() {
+            self.ensure_delegated_listener(DispatchPhase::Bubble, "dragover", false);
+        }
+
+        // Controlled elements need the root to see user edits even without handlers
+        if next_input_value.is_some() || next_input_checked.is_some() || next_props.select_value().is_some() {
+            for event_type in forms::CONTROL_EVENTS {
+                self.ensure_delegated_listener(DispatchPhase::Bubble, event_type, false);
+            }
+        }
+    }
+
+    // Whether a URL attribute may be written under the URL policy
+    fn check_url(&self, dom_node: &HTMLElement, name: &str, value: &str, owner: Option<&str>) -> bool {
+        if self.url_policy == UrlPolicy::Allow || !url_policy::is_url_attribute(name) || !url_policy::has_javascript_url(name, value) {
+            return true;
+        }
+
+        let blocked = self.url_policy == UrlPolicy::Block;
+        let message = format!(
+            "Reactron: {} a javascript: URL in '{}' of <{}>{}",
+            if blocked { "blocked" } else { "rendering" },
+            name,
+            dom_node.local_name(),
+            owner_note(owner),
+        );
+
+        if blocked {
+            web_sys::console::error_1(&message.into());
+        } else {
+            web_sys::console::warn_1(&message.into());
+        }
+
+        !blocked
+    }
+
+    // Warns once per element type when an editable element's content is
+    // rendered by Reactron, which overwrites the user's edits whenever its
+    // children change
+    fn check_content_editable(&self, fiber: &Fiber) {
+        let has_children = fiber.element_children().as_ref().is_some_and(|children| !children.borrow().is_empty());
+
+        let warns = fiber.props().is_some_and(|props| {
+            props.is_content_editable() && !props.uncontrolled_children() && !props.suppress_content_editable_warning()
+        });
+
+        if has_children && warns && self.content_editable_warnings.borrow_mut().insert(fiber.element_type().clone()) {
+            web_sys::console::warn_1(&format!(
+                "Reactron: <{}>{} is contentEditable and has children. Edits are overwritten when they change; \
+                pass uncontrolledChildren to leave its content to the user, or suppressContentEditableWarning.",
+                fiber.element_type(),
+                owner_note(fiber.owner().as_deref()),
+            ).into());
+        }
+    }
+
+    // Warns once per element type and attribute
+    fn check_aria_attribute(&self, dom_node: &HTMLElement, name: &str, owner: Option<&str>) {
+        let tag_name = dom_node.local_name();
+
+        if let Some(warning) = aria::check_attribute(&tag_name, name) {
+            if self.aria_warnings.borrow_mut().insert((tag_name, String::from(name))) {
+                web_sys::console::warn_1(&format!("{}{}", warning, owner_note(owner)).into());
+            }
+        }
+    }
+
+    // Passive handlers get their own root listener registered with
+    // `{ passive: true }`, so they never block scrolling on the others.
+    fn ensure_delegated_listener(&self, phase: DispatchPhase, event_type: &str, passive: bool) {
+        let mut delegated_events = self.delegated_events.borrow_mut();
+        let key = (String::from(event_type), phase.use_capture(), passive);
+
+        if delegated_events.contains_key(&key) {
+            return;
+        }
+
+        let context_ptr = self.self_ptr;
+
+        let listener: js_sys::Function = Closure::wrap(Box::new(move |event: web_sys::Event| {
+            let mut context = Context::from_ptr(context_ptr);
+
+            if context.is_dispatched_by_root(&event) {
+                let _ = Box::into_raw(context);
+                return;
+            }
+
+            let is_rendering = context.is_rendering;
+
+            if is_rendering {
+                context.queued_dispatches.push((event.clone(), phase, passive));
+            }
+
+            let _ = Box::into_raw(context);
+
+            if !is_rendering {
+                Context::dispatch_event(context_ptr, &event, phase, passive);
+            }
+        }) as Box<dyn FnMut(web_sys::Event)>).into_js_value().unchecked_into();
+
+        // A Context rendering several containers has no container of its own
+        if let Some(root_node) = self.root_node() {
+            add_delegated_listener(root_node, &key, &listener);
+        }
+
+        for portal_target in self.portal_targets.borrow().iter() {
+            add_delegated_listener(portal_target, &key, &listener);
+        }
+
+        delegated_events.insert(key, listener);
+    }
+
+    // Events from inside a shadow tree reach the container retargeted to it,
+    // so they are listened to on the shadow root
+    fn root_node(&self) -> Option<&web_sys::Node> {
+        match self.shadow_root.as_ref() {
+            Some(shadow_root) => Some(shadow_root.as_ref()),
+            None => self.container.as_ref().map(AsRef::as_ref),
+        }
+    }
+
+    // The delegated listeners follow the root to a new container, or shadow
+    // root, once it changed from `previous_root_node`
+    fn move_delegated_listeners(&self, previous_root_node: Option<web_sys::Node>) {
+        let root_node = self.root_node().cloned();
+
+        if root_node == previous_root_node {
+            return;
+        }
+
+        for (key, listener) in self.delegated_events.borrow().iter() {
+            if let Some(previous_root_node) = previous_root_node.as_ref() {
+                let _ = previous_root_node.remove_event_listener_with_callback_and_bool(&key.0, listener, key.1);
+            }
+
+            if let Some(root_node) = root_node.as_ref() {
+                add_delegated_listener(root_node, key, listener);
+            }
+        }
+    }
+
+    fn add_portal_target(&self, target: &HTMLElement) {
+        let mut portal_targets = self.portal_targets.borrow_mut();
+
+        if portal_targets.contains(target) {
+            return;
+        }
+
+        for (key, listener) in self.delegated_events.borrow().iter() {
+            add_delegated_listener(target, key, listener);
+        }
+
+        portal_targets.push(target.clone());
+    }
+
+    // A portal target containing the container, or inside it, also sees the
+    // events of the container's nodes, which the container's own listener
+    // dispatches
+    fn is_dispatched_by_root(&self, event: &web_sys::Event) -> bool {
+        let root_node = match self.root_node() {
+            Some(root_node) => root_node,
+            None => return false,
+        };
+
+        let is_root_listener = event.current_target().as_ref() == Some(root_node.as_ref());
+        let target = event.target().and_then(|target| target.dyn_into::<web_sys::Node>().ok());
+
+        !is_root_listener && root_node.contains(target.as_ref())
+    }
+
+    fn dispatch_event(context_ptr: *mut Context, event: &web_sys::Event, phase: DispatchPhase, passive: bool) {
+        let event_type = event.type_();
+
+        let context = Context::from_ptr(context_ptr);
+        let handlers = context.collect_handlers(event, phase, passive);
+        let target_fiber = event.target().and_then(|target| events::fiber_id_of(&target, &context.fiber_id_key));
+
+        if event_type == "dragover" && !passive && phase.includes_bubble() && context.accepts_drop(event) {
+            event.prevent_default();
+        }
+
+        // Updates scheduled by the handlers are user input
+        let mut context = context;
+        let outer_lane = mem::replace(&mut context.update_lane, Lane::UserInput);
+
+        let _ = Box::into_raw(context);
+
+        let synthetic_event = SyntheticEvent::new(event.clone(), target_fiber);
+        let dispatch_state = synthetic_event.state();
+        let synthetic_event = JsValue::from(synthetic_event);
+
+        // Submit handlers also receive the named field values of the form
+        let extra = match event.target().and_then(|target| target.dyn_into::<web_sys::HtmlFormElement>().ok()) {
+            Some(form) if event_type == "submit" && !handlers.is_empty() => forms::form_values(&form),
+            _ => JsValue::UNDEFINED,
+        };
+
+        for (listener, current_target, fiber_id, component_stack) in handlers {
+            if dispatch_state.is_propagation_stopped() {
+                break;
+            }
+
+            if listener.prevent_default() {
+                event.prevent_default();
+            }
+
+            dispatch_state.set_current_target(current_target, fiber_id);
+            events::invoke_handler(listener.handler(), &synthetic_event, &extra, &component_stack);
+        }
+
+        let mut context = Context::from_ptr(context_ptr);
+        context.update_lane = outer_lane;
+
+        if forms::CONTROL_EVENTS.contains(&event_type.as_str()) {
+            if let Some(fiber_id) = event.target().and_then(|target| events::fiber_id_of(&target, &context.fiber_id_key)) {
+                context.after_control_event(&event_type, fiber_id);
+            }
+        }
+
+        let _ = Box::into_raw(context);
+    }
+
+    // Rewriting the value of an input while an IME composition is in progress
+    // cancels it, so the element is only restored once the composition ends
+    fn after_control_event(&mut self, event_type: &str, fiber_id: u32) {
+        match event_type {
+            "compositionstart" => self.composing_fiber = Some(fiber_id),
+            "compositionend" => {
+                self.composing_fiber = None;
+                self.restore_controlled(fiber_id);
             },
-            (_, _) => {}
+            _ => self.restore_controlled(fiber_id),
+        }
+    }
+
+    fn is_composing(&self, dom_node: &HTMLElement) -> bool {
+        self.composing_fiber.is_some() &&
+            events::own_fiber_id(dom_node, &self.fiber_id_key) == self.composing_fiber
+    }
+
+    // A controlled element is put back in line with its committed props once
+    // the handlers ran. If they scheduled a render, started or not yet, that
+    // waits for the commit so the new state wins instead of the old one.
+    fn restore_controlled(&mut self, fiber_id: u32) {
+        if self.composing_fiber == Some(fiber_id) {
+            return;
+        }
+
+        if self.is_render_pending() {
+            if !self.pending_control_restores.contains(&fiber_id) {
+                self.pending_control_restores.push(fiber_id);
+            }
+            return;
+        }
+
+        if let Some(fiber) = self.find_fiber(fiber_id) {
+            let fiber = fiber.borrow();
+
+            if let (Some(dom_node), Some(props)) = (fiber.dom_node(), fiber.props()) {
+                if let Node::Element(node) = &*dom_node.borrow() {
+                    forms::restore_control(node, props);
+                }
+            }
+        }
+    }
+
+    fn accepts_drop(&self, event: &web_sys::Event) -> bool {
+        let target_fiber = event.target()
+            .and_then(|target| events::fiber_id_of(&target, &self.fiber_id_key))
+            .and_then(|fiber_id| self.find_fiber(fiber_id));
+
+        target_fiber.is_some_and(|target_fiber| {
+            std::iter::once(Rc::clone(&target_fiber)).chain(target_fiber.parents()).any(|fiber| {
+                fiber.borrow().props().is_some_and(|props| {
+                    props.listener("drop").is_some() || props.capture_listener("drop").is_some()
+                })
+            })
+        })
+    }
+
+    // Maps the event target back to its fiber and collects the matching handler
+    // props along the fiber path: capture handlers from the root down, then
+    // bubble handlers from the target up. Each comes with the DOM node it is
+    // attached to.
+    fn collect_handlers(&self, event: &web_sys::Event, phase: DispatchPhase, passive: bool) -> Vec<(Listener, JsValue, u32, String)> {
+        let event_type = event.type_();
+        let mut handlers = Vec::new();
+
+        let target = event.target().map_or(JsValue::NULL, JsValue::from);
+        let listener_types = forms::listener_types(&event_type, &target);
+
+        let target_fiber = event.target()
+            .and_then(|target| events::fiber_id_of(&target, &self.fiber_id_key))
+            .and_then(|fiber_id| self.find_fiber(fiber_id));
+
+        let target_fiber = match target_fiber {
+            Some(target_fiber) => target_fiber,
+            None => return handlers,
+        };
+
+        let path: Vec<FiberCell> = std::iter::once(Rc::clone(&target_fiber))
+            .chain(target_fiber.parents())
+            .filter(|fiber| {
+                let fiber = fiber.borrow();
+                !fiber.is_functional_tree() && !fiber.is_text_fiber()
+            })
+            .collect();
+
+        let mut spent_once_listeners = self.spent_once_listeners.borrow_mut();
+
+        let mut push_handler = |fiber: &Fiber, listener_type: &str, listener: &Listener, capture: bool| {
+            if listener.passive() != passive {
+                return;
+            }
+
+            // A `once` handler stays spent until its prop is removed, however
+            // many new functions later renders pass
+            if listener.once() && !spent_once_listeners.insert((fiber.id(), String::from(listener_type), capture)) {
+                return;
+            }
+
+            let current_target = fiber.dom_node()
+                .map_or(JsValue::NULL, |node| node.borrow().as_js_value().clone());
+
+            handlers.push((listener.clone(), current_target, fiber.id(), fiber.component_stack()));
+        };
+
+        if phase.includes_capture() {
+            for fiber in path.iter().rev() {
+                let fiber = fiber.borrow();
+
+                for listener_type in &listener_types {
+                    if let Some(listener) = fiber.props().and_then(|props| props.capture_listener(listener_type)) {
+                        push_handler(&fiber, listener_type, listener, true);
+                    }
+                }
+            }
+        }
+
+        if phase.includes_bubble() {
+            let bubble_path = if event.bubbles() { &path[..] } else { &path[..path.len().min(1)] };
+
+            for fiber in bubble_path {
+                let fiber = fiber.borrow();
+
+                for listener_type in &listener_types {
+                    if let Some(listener) = fiber.props().and_then(|props| props.listener(listener_type)) {
+                        push_handler(&fiber, listener_type, listener, false);
+                    }
+                }
+            }
         }
+
+        handlers
     }
 
     fn update_dom_text(&self, text_node: &HTMLText, prev_props: Option<&Box<ElementProps>>, next_props: &Box<ElementProps>) {
@@ -302,11 +1746,65 @@ impl Context {
             (None, Some(next)) => {
                 text_node.set_node_value(Some(next));
             },
-            (_, _) => {}
+            (Some(_), None) => {
+                text_node.set_node_value(Some(""));
+            },
+            (None, None) => {}
+        }
+    }
+
+    // Holds the children of `fiber` to the output guardrails before they're
+    // reconciled
+    fn check_guardrails(&mut self, fiber: &Fiber) {
+        if !self.guardrails.is_enabled() {
+            return;
+        }
+
+        if *fiber.element_type() == FIBER_ROOT {
+            self.guardrails.reset();
+        }
+
+        let children = match fiber.element_children() {
+            Some(children) => Rc::clone(children),
+            None => return,
+        };
+        let mut children = children.borrow_mut();
+
+        let (admitted, message) = self.guardrails.admit(&children);
+
+        match self.guardrails.action() {
+            GuardrailAction::Warn => {
+                if let Some(message) = message {
+                    web_sys::console::warn_1(&format!("{}{}", message, fiber.component_stack()).into());
+                }
+            },
+            GuardrailAction::Truncate => {
+                if admitted < children.len() {
+                    children.truncate(admitted);
+                    children.push(guardrails::placeholder());
+                }
+
+                if let Some(message) = message {
+                    web_sys::console::warn_1(&format!("{}, the rest was truncated{}", message, fiber.component_stack()).into());
+                }
+            },
+            GuardrailAction::Error => {
+                if admitted < children.len() {
+                    children.clear();
+                }
+
+                if let Some(message) = message {
+                    self.report_error(&js_sys::Error::new(&message).into(), &fiber.component_stack());
+                }
+            },
         }
     }
 
     fn reconcile_children(&mut self, wip_unit: &FiberCell, fiber: &mut Fiber) {
+        self.check_guardrails(fiber);
+
+        let is_redirected = Self::is_marked(&self.redirected, fiber);
+        let is_invalidated = self.is_invalidated(fiber);
         let children = fiber.element_children().as_ref();
         let children_len = children.map_or(0, |children| children.borrow().len());
 
@@ -314,30 +1812,33 @@ impl Context {
         let mut previous_sibling: Option<FiberCell> = None;
         let mut first_child_fiber: Option<FiberCell> = None;
 
-        let mut old_child_fiber = fiber.alternate().map_or(None, |alternate| {
+        let mut old_child_fiber = fiber.alternate().and_then(|alternate| {
             alternate.borrow()
                 .child()
-                .as_ref()
-                .map_or(None, |child| {
-                    Some(Rc::clone(child))
-                })
+                .as_ref().map(Rc::clone)
         });
 
         while i < children_len || old_child_fiber.as_ref().is_some() {
             let mut children = children.unwrap().borrow_mut();
             let child_element = children.get_mut(i);
 
-            let has_same_type = old_child_fiber.as_ref().map_or(false, |old_child| {
-                child_element.as_ref().map_or(false, |child| {
-                    *old_child.borrow().element_type() == *child.element_type()
+            // Portals moving to another target are mounted anew
+            let has_same_type = old_child_fiber.as_ref().is_some_and(|old_child| {
+                child_element.as_ref().is_some_and(|child| {
+                    let old_child = old_child.borrow();
+                    let old_target = old_child.props().and_then(|props| props.portal_target());
+                    let target = child.props().as_ref().and_then(|props| props.portal_target());
+
+                    *old_child.element_type() == *child.element_type() && old_target == target
                 })
             });
 
-            let child_fiber = child_element.map_or(None, |child_element| {
+            let child_fiber = child_element.and_then(|child_element| {
                 // Generate a new Fiber for the updated node
                 let mut child_fiber = if has_same_type {
                     let alternate_child = old_child_fiber.as_ref().unwrap();
-                    let mut child_fiber = Fiber::new(&alternate_child.borrow().element_type());
+                    let mut child_fiber = Fiber::new(alternate_child.borrow().element_type());
+                    child_fiber.set_id(alternate_child.borrow().id());
 
                     child_fiber.set_props(child_element.props_mut().take());
 
@@ -348,7 +1849,7 @@ impl Context {
                     child_fiber.set_element_children(element_children);
 
                     // relate to alternate
-                    child_fiber.set_alternate(Rc::clone(&alternate_child));
+                    child_fiber.set_alternate(Rc::clone(alternate_child));
 
                     // set existing dom node
                     if let Some(old_child_node) = alternate_child.borrow().dom_node() {
@@ -360,8 +1861,13 @@ impl Context {
 
                     // effect
                     if !child_fiber.is_functional_tree() {
+                        // Styles below a new direction are mirrored the other way
+                        if direction::has_changed(&child_fiber, &alternate_child.borrow()) {
+                            self.redirected.insert(child_fiber.id());
+                        }
+
                         if let Some(old_props) = alternate_child.borrow().props() {
-                            if child_fiber.has_props_changed(old_props) {
+                            if child_fiber.has_props_changed(old_props) || is_invalidated || is_redirected || self.invalidated.contains(&child_fiber.id()) {
                                 child_fiber.set_effect_tag(FiberEffect::Update);
                                 // console_log!("added UPDATE effect for {}", &child_fiber.element_type());
                             }
@@ -370,7 +1876,8 @@ impl Context {
 
                     Some(child_fiber)
                 } else {
-                    let mut child_fiber = Fiber::new(&child_element.element_type());
+                    let mut child_fiber = Fiber::new(child_element.element_type());
+                    child_fiber.set_id(self.allocate_fiber_id());
 
                     child_fiber.set_props(child_element.props_mut().take());
                     let element_children = child_element.children_mut().take().map(|children| {
@@ -390,16 +1897,16 @@ impl Context {
                     Some(child_fiber)
                 };
 
-                child_fiber.as_mut().map(|child_fiber| {
+                if let Some(child_fiber) = child_fiber.as_mut() {
                     if child_fiber.is_functional_tree() {
-                        let func = child_element.component_function().unwrap();
+                        let func = self.resolve_component_function(child_element.component_function().unwrap());
                         let props = child_element.component_function_props().unwrap();
 
-                        child_fiber.set_component_function(Some(Rc::clone(&func)));
-                        child_fiber.set_component_function_props(Some(Rc::clone(&props)));
+                        child_fiber.set_component_function(Some(func));
+                        child_fiber.set_component_function_props(Some(Rc::clone(props)));
                         child_fiber.set_hooks(Some(vec![]));
                     }
-                });
+                }
 
                 child_fiber
             });
@@ -407,7 +1914,7 @@ impl Context {
             if let Some(old_child_fiber) = old_child_fiber.as_ref() {
                 if !has_same_type {
                     old_child_fiber.borrow_mut().set_effect_tag(FiberEffect::Deletion);
-                    self.add_effect(Rc::clone(&old_child_fiber));
+                    self.add_effect(Rc::clone(old_child_fiber));
                     // console_log!("added deletion effect for {}", old_child_fiber.borrow().element_type());
                 }
             }
@@ -448,72 +1955,449 @@ impl Context {
         }
     }
 
+    // Moves the committed children of an uncontrolled region under its new
+    // fiber as they are, so they're neither rendered nor committed again.
+    // They drop their alternates, which belong to older trees.
+    fn adopt_children(wip_unit: &FiberCell, fiber: &mut Fiber) {
+        let first_child = fiber.alternate().and_then(|alternate| alternate.borrow().child().clone());
+        let mut next_child = first_child.clone();
+
+        while let Some(child) = next_child {
+            child.borrow_mut().set_parent(Rc::clone(wip_unit));
+
+            for descendant in child.subtree() {
+                descendant.borrow_mut().clear_alternate();
+            }
+
+            next_child = child.borrow().sibling().clone();
+        }
+
+        if let Some(first_child) = first_child {
+            fiber.set_child(first_child);
+        }
+    }
+
     fn commit_root(&mut self) -> Result<(), JsValue> {
         if self.wip_root.is_some() {
-            let wip_root_fiber = self.wip_root.as_ref().unwrap();
+            let wip_root_fiber = Rc::clone(self.wip_root.as_ref().unwrap());
 
-            for effect in &self.effects {
-                self.commit_work(&effect)?;
+            if cfg!(feature = "invariants") || self.dev_mode.assertions {
+                invariants::assert_commit_invariants(&wip_root_fiber, self.current_root.as_ref(), &self.effects);
             }
 
-            self.effects.clear();
+            // Moving or replacing the focused node blurs it
+            let focus_snapshot = FocusSnapshot::capture(&self.document, self.shadow_root.as_ref(), &self.fiber_id_key);
 
-            self.current_root = Some(Rc::clone(wip_root_fiber));
-            self.wip_root = None;
-        }
+            // Scoped styles are in place before the nodes using them
+            self.styles.flush(self.shadow_root.as_ref())?;
 
-        Ok(())
-    }
+            for effect in &self.effects {
+                self.commit_work(effect)?;
+            }
 
-    fn commit_work(&self, fiber: &FiberCell) -> Result<(), JsValue> {
-        match fiber.borrow().effect_tag() {
-            Some(FiberEffect::Placement) => {
-                let mut parent_dom_node = None;
+            // Surfaces draw, media play, observers observe and scroll
+            // positions apply once their node is in the document
+            let host_effects: Vec<FiberCell> = self.effects.iter()
+                .filter(|effect| !matches!(effect.borrow().effect_tag(), Some(FiberEffect::Deletion)))
+                .map(Rc::clone)
+                .collect();
 
-                for parent in fiber.parents() {
-                    let parent = parent.borrow();
+            let mut selects: Vec<FiberCell> = Vec::new();
 
-                    if let Some(dom_node) = parent.dom_node() {
-                        parent_dom_node = Some(Rc::clone(dom_node));
-                        break;
+            for host_effect in host_effects {
+                if let Some(select) = Self::controlling_select(&host_effect) {
+                    if !selects.iter().any(|other| Rc::ptr_eq(other, &select)) {
+                        selects.push(select);
                     }
                 }
 
-                // console_log!("executing PLACEMENT for {}", fiber.borrow().element_type());
+                let fiber = host_effect.borrow();
 
-                self.commit_node_append(&fiber, parent_dom_node)?;
-            },
-            Some(FiberEffect::Update) => {
-                // console_log!("executing UPDATE for {}", fiber.borrow().element_type());
-                self.commit_node_update(&fiber)?;
-            },
-            Some(FiberEffect::Deletion) => {
-                // console_log!("executing DELETION for {}", fiber.borrow().element_type());
+                if fiber.is_surface_fiber() {
+                    self.commit_surface(&fiber);
+                }
 
-                // This can break if we have a functional component that returns another functional component
-                // without an element in between. An iterator through all children of a fiber to find
-                // the next DOM element would be fine but for my current case simply getting the first child is quicker.
-                if fiber.borrow().is_functional_tree() {
-                    let fiber_borrowed = fiber.borrow();
+                if fiber.props().is_some_and(|props| props.playing().is_some()) {
+                    self.commit_playback(&fiber);
+                }
 
-                    if let Some(child) = fiber_borrowed.child().as_ref() {
-                        self.commit_node_deletion(&child)?;
-                    } else {
-                        self.commit_node_deletion(&fiber)?;
+                self.commit_observers(&fiber);
+                self.commit_scroll(&fiber);
+
+                if self.dev_mode.highlight_updates {
+                    if let Some(Node::Element(node)) = fiber.dom_node().map(|dom_node| dom_node.borrow()).as_deref() {
+                        dev_mode::highlight(node, &self.highlight_keys);
                     }
-                } else {
-                    self.commit_node_deletion(&fiber)?;
                 }
+            }
 
-            },
-            None => {}
-        }
+            // A select picks its options once they are all in place
+            for select in selects {
+                let fiber = select.borrow();
+
+                if let (Some(values), Some(dom_node)) = (fiber.props().and_then(|props| props.select_value()), fiber.dom_node()) {
+                    if let Node::Element(node) = &*dom_node.borrow() {
+                        forms::set_select_values(node, values);
+                    }
+                }
+            }
+
+            let deletions: Vec<FiberCell> = self.effects.iter()
+                .filter(|effect| matches!(effect.borrow().effect_tag(), Some(FiberEffect::Deletion)))
+                .map(Rc::clone)
+                .collect();
+
+            for deletion in deletions {
+                self.queue_unmount_effects(&deletion);
+            }
+
+            self.effects.clear();
+
+            self.fiber_registry = wip_root_fiber.subtree()
+                .map(|fiber| (fiber.borrow().id(), Rc::clone(&fiber)))
+                .collect();
+
+            if let Some(focus_snapshot) = focus_snapshot {
+                let dom_node = self.find_fiber(focus_snapshot.fiber_id())
+                    .and_then(|fiber| fiber.borrow().dom_node().map(Rc::clone));
+
+                if let Some(dom_node) = dom_node {
+                    if let Node::Element(node) = &*dom_node.borrow() {
+                        focus_snapshot.restore(&self.document, self.shadow_root.as_ref(), node);
+                    }
+                }
+            }
+
+            let fiber_registry = &self.fiber_registry;
+            self.spent_once_listeners.borrow_mut().retain(|(fiber_id, _, _)| fiber_registry.contains_key(fiber_id));
+
+            // Layout phase: stable callbacks start forwarding to this render's closures
+            for (event_callback, callback) in self.pending_event_callbacks.drain(..) {
+                event_callback.latest().replace(Some(callback));
+            }
+
+            self.current_root = Some(wip_root_fiber);
+            self.wip_root = None;
+            self.wip_lane = None;
+            self.lane_times.committed(mem::take(&mut self.render_lanes), self.pending_lanes);
+            self.rendered_fibers.clear();
+            self.wip_superseded = false;
+
+            // Invalidated during this render: kept for the deferred update
+            if !self.root_update_deferred {
+                self.invalidated.clear();
+            }
+            self.redirected.clear();
+
+            for fiber_id in mem::take(&mut self.pending_control_restores) {
+                self.restore_controlled(fiber_id);
+            }
+
+            if let Some(navigation_focus) = self.pending_navigation_focus.take() {
+                navigation_focus.apply(&self.document, self.container.as_ref(), &self.announcer);
+            }
+        }
+
+        Ok(())
+    }
+
+    // The select whose selection a committed select, option or optgroup
+    // affects
+    fn controlling_select(fiber: &FiberCell) -> Option<FiberCell> {
+        match fiber.borrow().element_type().as_str() {
+            "select" => return Some(Rc::clone(fiber)),
+            "option" | "optgroup" => {},
+            _ => return None,
+        }
+
+        fiber.parents()
+            .find(|parent| parent.borrow().element_type() == "select")
+    }
+
+    // Written on mount and whenever the prop changes, so the user scrolls
+    // freely in between
+    fn commit_scroll(&self, fiber: &Fiber) {
+        let props = match fiber.props() {
+            Some(props) if props.scroll_top().is_some() || props.scroll_left().is_some() => props,
+            _ => return,
+        };
+
+        let prev_props = fiber.alternate()
+            .filter(|_| matches!(fiber.effect_tag(), Some(FiberEffect::Update)) && !self.is_invalidated(fiber))
+            .map(|alternate| alternate.borrow());
+        let prev_props = prev_props.as_ref().and_then(|alternate| alternate.props());
+
+        if let Some(Node::Element(node)) = fiber.dom_node().map(|dom_node| dom_node.borrow()).as_deref() {
+            if let Some(top) = props.scroll_top().filter(|top| prev_props.and_then(|p| p.scroll_top()) != Some(*top)) {
+                node.set_scroll_top(top);
+            }
+
+            if let Some(left) = props.scroll_left().filter(|left| prev_props.and_then(|p| p.scroll_left()) != Some(*left)) {
+                node.set_scroll_left(left);
+            }
+        }
+    }
+
+    fn commit_observers(&mut self, fiber: &Fiber) {
+        let (on_resize, on_intersect) = match fiber.props() {
+            Some(props) => (props.on_resize(), props.on_intersect()),
+            None => return,
+        };
+
+        if on_resize.is_none() && on_intersect.is_none() && !self.observers.contains_key(&fiber.id()) {
+            return;
+        }
+
+        let node = match fiber.dom_node().map(|dom_node| dom_node.borrow()) {
+            Some(dom_node) => match &*dom_node {
+                Node::Element(node) => node.clone(),
+                Node::Text(_) | Node::Shadow(_) => return,
+            },
+            None => return,
+        };
+
+        let observers = self.observers.entry(fiber.id())
+            .or_insert_with(|| ElementObservers::new(node));
+
+        let result = observers.set_on_resize(on_resize)
+            .and_then(|_| observers.set_on_intersect(on_intersect));
+
+        if observers.is_empty() {
+            self.observers.remove(&fiber.id());
+        }
+
+        if let Err(error) = result {
+            self.report_error(&error, &fiber.component_stack());
+        }
+    }
+
+    fn commit_playback(&self, fiber: &Fiber) {
+        if let (Some(playing), Some(dom_node)) = (fiber.props().and_then(|props| props.playing()), fiber.dom_node()) {
+            if let Node::Element(node) = &*dom_node.borrow() {
+                media::sync_playback(node, playing);
+            }
+        }
+    }
+
+    fn commit_surface(&mut self, fiber: &Fiber) {
+        let (props, dom_node) = match (fiber.props(), fiber.dom_node()) {
+            (Some(props), Some(dom_node)) => (props, dom_node),
+            (_, _) => return,
+        };
+
+        let draw = match props.draw() {
+            Some(draw) => draw.clone(),
+            None => return,
+        };
+
+        if let Some(surface) = self.surfaces.get(&fiber.id()) {
+            surface.set_draw(draw);
+            surface.draw();
+            return;
+        }
+
+        let canvas = match &*dom_node.borrow() {
+            Node::Element(node) => node.clone().unchecked_into::<web_sys::HtmlCanvasElement>(),
+            Node::Text(_) | Node::Shadow(_) => return,
+        };
+        let context_type = props.context_type().map_or("2d", |context_type| context_type.as_str());
+
+        match Surface::mount(canvas, context_type, draw) {
+            Ok(surface) => {
+                surface.draw();
+                self.surfaces.insert(fiber.id(), surface);
+            },
+            Err(error) => self.report_error(&error, &fiber.component_stack()),
+        }
+    }
+
+    // Queues the cleanup of every effect mounted inside a deleted subtree
+    fn queue_unmount_effects(&mut self, fiber: &FiberCell) {
+        // Instances are disposed of after every cleanup of the subtree, which
+        // may still use them
+        let mut disposals = Vec::new();
+
+        for fiber in fiber.subtree() {
+            let fiber = fiber.borrow();
+
+            if let Some(surface) = self.surfaces.remove(&fiber.id()) {
+                surface.unmount();
+            }
+
+            if let Some(observers) = self.observers.remove(&fiber.id()) {
+                observers.disconnect();
+            }
+
+            // A stream handed over through `srcObject` is owned by the element
+            if let (Some(props), Some(dom_node)) = (fiber.props(), fiber.dom_node()) {
+                if let (Some(_), Node::Element(node)) = (props.src_object(), &*dom_node.borrow()) {
+                    media::release_src_object(node);
+                }
+            }
+
+            for hook in fiber.hooks().into_iter().flatten() {
+                if hook.borrow().announcer().is_some() {
+                    self.announcer.borrow_mut().release();
+                }
+
+                if let Some(job) = hook.borrow().worker_job() {
+                    job.cancel();
+                }
+
+                if let Some(binding) = hook.borrow().hotkey_binding() {
+                    self.hotkeys.borrow_mut().unregister(binding.id());
+                }
+
+                if let Some(binding) = hook.borrow().display_mode() {
+                    binding.unmount();
+                }
+
+                if hook.borrow().is_wake_lock() {
+                    self.wake_lock.borrow_mut().release();
+                }
+
+                if let Some(id) = hook.borrow().idle_watcher() {
+                    self.idle_tracker.borrow_mut().unwatch(id);
+                }
+
+                if let Some(binding) = hook.borrow().broadcast_channel() {
+                    binding.close();
+                }
+
+                if let Some(query) = hook.borrow().container_query() {
+                    query.disconnect();
+                }
+
+                if let Some(id) = hook.borrow().blocker() {
+                    self.blockers.unregister(id);
+                }
+
+                for id in hook.borrow().undoable_state().map_or(&[][..], |state| state.hotkey_ids()) {
+                    self.hotkeys.borrow_mut().unregister(*id);
+                }
+
+                let cleanup = hook.borrow().effect().and_then(|effect| effect.cleanup().cloned());
+
+                if cleanup.is_some() {
+                    self.passive_effects.push(PassiveEffect::new(
+                        Rc::clone(hook),
+                        None,
+                        cleanup,
+                        fiber.component_stack()
+                    ));
+                }
+
+                let dispose = hook.borrow().instance().and_then(|instance| instance.destroy());
+
+                if dispose.is_some() {
+                    disposals.push(PassiveEffect::new(Rc::clone(hook), None, dispose, fiber.component_stack()));
+                }
+            }
+        }
+
+        self.passive_effects.extend(disposals);
+    }
+
+    // Runs queued cleanups and effects in order until the queue is empty or,
+    // given a deadline, until it passed. At least one step runs per call. A
+    // throwing effect doesn't stop the flush: each error is reported once the
+    // batch is done.
+    fn flush_passive_effects(&mut self, deadline: Option<f64>) {
+        let performance = web_sys::window().unwrap().performance().unwrap();
+        let mut errors = Vec::new();
+
+        while let Some(step) = self.passive_queue.pop_front() {
+            match step {
+                PassiveStep::Destroy(destroy, component_stack) => {
+                    if let Err(error) = destroy.call0(&JsValue::null()) {
+                        errors.push((error, component_stack));
+                    }
+                },
+                PassiveStep::Create(hook, create, component_stack) => {
+                    match create.call0(&JsValue::null()) {
+                        Ok(cleanup) => {
+                            if let Some(effect_hook) = hook.borrow_mut().effect_mut() {
+                                effect_hook.set_cleanup(cleanup.dyn_into::<js_sys::Function>().ok());
+                            }
+                        },
+                        Err(error) => {
+                            errors.push((error, component_stack));
+                        }
+                    }
+                },
+            }
+
+            if deadline.is_some_and(|deadline| performance.now() >= deadline) {
+                break;
+            }
+        }
+
+        for (error, component_stack) in errors {
+            self.report_error(&error, &component_stack);
+        }
+    }
+
+    // Hands an error to the host's error handler, or logs it if there is none
+    fn report_error(&self, error: &JsValue, component_stack: &str) {
+        let reported = self.error_handler.as_ref().is_some_and(|handler| {
+            handler.call2(&JsValue::null(), error, &JsValue::from_str(component_stack)).is_ok()
+        });
+
+        if !reported {
+            let message = format!("Reactron: uncaught error{}", component_stack);
+            web_sys::console::error_2(&JsValue::from_str(&message), error);
+        }
+    }
+
+    fn commit_work(&self, fiber: &FiberCell) -> Result<(), JsValue> {
+        match fiber.borrow().effect_tag() {
+            Some(FiberEffect::Placement) => {
+                let mut parent_dom_node = None;
+
+                for parent in fiber.parents() {
+                    let parent = parent.borrow();
+
+                    if let Some(dom_node) = parent.dom_node() {
+                        parent_dom_node = Some(Rc::clone(dom_node));
+                        break;
+                    }
+                }
+
+                // console_log!("executing PLACEMENT for {}", fiber.borrow().element_type());
+
+                self.commit_node_append(fiber, parent_dom_node)?;
+            },
+            Some(FiberEffect::Update) => {
+                // console_log!("executing UPDATE for {}", fiber.borrow().element_type());
+                self.commit_node_update(fiber)?;
+            },
+            Some(FiberEffect::Deletion) => {
+                // console_log!("executing DELETION for {}", fiber.borrow().element_type());
+
+                // This can break if we have a functional component that returns another functional component
+                // without an element in between. An iterator through all children of a fiber to find
+                // the next DOM element would be fine but for my current case simply getting the first child is quicker.
+                if fiber.borrow().is_functional_tree() {
+                    let fiber_borrowed = fiber.borrow();
+
+                    if let Some(child) = fiber_borrowed.child().as_ref() {
+                        self.commit_node_deletion(child)?;
+                    } else {
+                        self.commit_node_deletion(fiber)?;
+                    }
+                } else {
+                    self.commit_node_deletion(fiber)?;
+                }
+
+            },
+            None => {}
+        }
 
         Ok(())
     }
 
     fn commit_node_append(&self, fiber: &FiberCell, parent_dom_node: Option<Rc<RefCell<Node>>>) -> Result<(), JsValue> {
-        let has_dom_node = fiber.borrow().dom_node().is_some();
+        let has_dom_node = fiber.borrow().dom_node().is_some() && !fiber.borrow().is_portal_fiber();
         let has_parent_node = parent_dom_node.is_some();
 
         if has_dom_node && has_parent_node {
@@ -527,12 +2411,20 @@ impl Context {
             match (parent_node, dom_node) {
                 // Append HTML element
                 (Node::Element(parent), Node::Element(child)) => {
-                    parent.append_child(&child)?;
+                    parent.append_child(child)?;
                 },
 
                 // Append text node
                 (Node::Element(parent), Node::Text(text)) => {
-                    parent.append_child(&text)?;
+                    parent.append_child(text)?;
+                }
+
+                // Append to a shadow root container
+                (Node::Shadow(parent), Node::Element(child)) => {
+                    parent.append_child(child)?;
+                },
+                (Node::Shadow(parent), Node::Text(text)) => {
+                    parent.append_child(text)?;
                 }
                 _ => {}
             }
@@ -544,17 +2436,23 @@ impl Context {
     fn commit_node_update(&self, fiber: &FiberCell) -> Result<(), JsValue> {
         let fiber = fiber.borrow();
 
+        if fiber.is_portal_fiber() {
+            return Ok(());
+        }
+
         if let Some(dom_node) = fiber.dom_node() {
             if let Some(alternate) = fiber.alternate() {
                 let alternate = alternate.borrow();
-                let prev_props = alternate.props();
+                // Refreshed nodes are written as if they were new
+                let prev_props = alternate.props().filter(|_| !self.is_invalidated(&fiber));
                 let next_props = fiber.props().unwrap();
                 let node= &*dom_node.borrow();
 
                 match node {
                     Node::Element(node) => {
                         self.update_dom_node(
-                            &node,
+                            &fiber,
+                            node,
                             prev_props,
                             next_props
                         );
@@ -565,7 +2463,8 @@ impl Context {
                             prev_props,
                             next_props
                         );
-                    }
+                    },
+                    Node::Shadow(_) => {}
                 }
             }
         }
@@ -574,126 +2473,1469 @@ impl Context {
     }
 
     fn commit_node_deletion(&self, fiber: &FiberCell) -> Result<(), JsValue> {
-        if let Some(dom_node) = fiber.borrow().dom_node() {
+        // Integrations dispose of their state while the nodes are attached
+        let pending = before_delete::notify(fiber);
+        let is_deferred = !pending.is_empty();
+
+        // The children of portals aren't under the removed node
+        for portal in fiber.subtree().filter(|fiber| fiber.borrow().is_portal_fiber()) {
+            Self::remove_portal_children(&portal);
+        }
+
+        if let Some(dom_node) = fiber.borrow().dom_node().filter(|_| !fiber.borrow().is_portal_fiber()) {
             match &*dom_node.borrow() {
+                Node::Element(node) if is_deferred => {
+                    before_delete::remove_after(node.clone(), pending);
+                },
                 Node::Element(node) => {
                     node.remove();
                 },
                 Node::Text(text) => {
                     text.remove();
-                }
+                },
+                Node::Shadow(_) => {}
             }
         }
 
+        // Nodes still in the document can't be handed out again
+        if self.node_pool.is_some() && !is_deferred {
+            self.recycle_subtree(fiber);
+        }
+
         Ok(())
     }
-}
 
-#[wasm_bindgen]
-pub fn get_context() -> *mut Context {
-    let context = Box::new(Context::new());
-    Box::into_raw(context)
-}
+    // Removes the DOM nodes a portal put into its target, leaving the target
+    // and nested portals' targets in place
+    fn remove_portal_children(portal: &FiberCell) {
+        for fiber in portal.subtree().skip(1) {
+            let fiber_ref = fiber.borrow();
 
-#[wasm_bindgen]
-pub fn render(context_ptr: *mut Context, element_ptr: *mut Element, container: HTMLElement) -> *mut Context {
-    let mut context = Context::from_ptr(context_ptr);
-    let element = Element::from_ptr(element_ptr);
+            let dom_node = match fiber_ref.dom_node() {
+                Some(dom_node) if !fiber_ref.is_portal_fiber() => Rc::clone(dom_node),
+                _ => continue,
+            };
+            mem::drop(fiber_ref);
 
-    // Create the Root fiber
-    let mut root = Fiber::new_root();
-    
-    // The root element will be the Root fiber's only child
-    let mut children = Vec::with_capacity(1);
-    children.push(element);
-    root.set_element_children(Some(Rc::new(RefCell::new(children))));
+            let host_parent = fiber.parents().find(|parent| parent.borrow().dom_node().is_some());
 
-    // Store the container HTML element
-    root.set_dom_node(Rc::new(RefCell::new(Node::Element(container))));
+            if !host_parent.is_some_and(|host_parent| Rc::ptr_eq(&host_parent, portal)) {
+                continue;
+            }
 
-    // Set the current root as the alternate root
-    if let Some(current_root) = context.current_root.as_ref() {
-        root.set_alternate(Rc::clone(current_root));
+            match &*dom_node.borrow() {
+                Node::Element(node) => node.remove(),
+                Node::Text(text) => text.remove(),
+                Node::Shadow(_) => {}
+            };
+        }
     }
 
-    // Make it the Work in Progress Root and the Next Unit of Work
-    let root = Rc::new(RefCell::new(Box::new(root)));
-    context.wip_root = Some(Rc::clone(&root));
-    context.next_unit_of_work = Some(Rc::clone(&root));
+    // Parks every host element of a deleted subtree in the node pool
+    fn recycle_subtree(&self, fiber: &FiberCell) {
+        let mut pool = self.node_pool.as_ref().unwrap().borrow_mut();
 
-    Box::into_raw(context)
+        for fiber in fiber.subtree() {
+            let fiber = fiber.borrow();
+
+            // A canvas keeps the context type it handed out first
+            if fiber.is_functional_tree() || fiber.is_text_fiber() || fiber.is_surface_fiber() || fiber.is_portal_fiber() {
+                continue;
+            }
+
+            if let Some(dom_node) = fiber.dom_node() {
+                match &*dom_node.borrow() {
+                    // Media elements would carry on loading or playing while
+                    // parked, and the pool only hands out HTML elements
+                    Node::Element(node) if !media::is_media_element(node) && node.namespace_uri().as_deref() != Some(SVG_NAMESPACE) => {
+                        pool.park(fiber.element_type(), node.clone());
+                    },
+                    _ => {}
+                }
+            }
+        }
+    }
 }
 
+fn add_delegated_listener(target: &web_sys::EventTarget, key: &(String, bool, bool), listener: &js_sys::Function) {
+    let (event_type, capture, passive) = key;
 
-#[wasm_bindgen]
-pub fn work_loop(context_ptr: *mut Context, did_timeout: bool) -> *mut Context {
-    let mut context = Context::from_ptr(context_ptr);
+    let options = web_sys::AddEventListenerOptions::new();
+    options.set_capture(*capture);
+    options.set_passive(*passive);
 
-    context.work_loop(did_timeout).unwrap();
+    target.add_event_listener_with_callback_and_add_event_listener_options(event_type, listener, &options).unwrap();
+}
 
-    Box::into_raw(context)
+// Namespace of a prefixed attribute such as `xlink:href`
+fn attribute_namespace(name: &str) -> Option<&'static str> {
+    match name.split_once(':') {
+        Some(("xlink", _)) => Some(XLINK_NAMESPACE),
+        Some(("xml", _)) => Some(XML_NAMESPACE),
+        _ => None,
+    }
 }
 
-#[wasm_bindgen]
-pub fn use_state(context_ptr: *mut Context, initial_value: JsValue) -> Box<[JsValue]> {
-    let context = Context::from_ptr(context_ptr.clone());
-    let wip_fiber = context.wip_functional_fiber.as_ref().unwrap();
-    let mut fiber = wip_fiber.borrow_mut();
+// Handler props of the previous render that the next one dropped, as
+// `(event type, capture)` pairs
+fn removed_listeners(prev_props: &ElementProps, next_props: &ElementProps) -> Vec<(String, bool)> {
+    let removed_bubble = prev_props.listeners().keys()
+        .filter(|listener_type| next_props.listener(listener_type).is_none())
+        .map(|listener_type| (listener_type.clone(), false));
 
-    let old_state = fiber.alternate().map_or(None, |alternate| {
-        let alternate = alternate.borrow();
-        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+    let removed_capture = prev_props.capture_listeners().keys()
+        .filter(|listener_type| next_props.capture_listener(listener_type).is_none())
+        .map(|listener_type| (listener_type.clone(), true));
 
-        hook.map_or(None, |hook| {
-            Some(hook.borrow().clone())
-        })
-    });
+    removed_bubble.chain(removed_capture).collect()
+}
 
-    let current_state = if old_state.is_some() {
-        old_state.unwrap()
-    } else {
-        initial_value
-    };
+// Milliseconds on the performance timeline, which workers have as well as
+// windows, or on the clock for hosts without one
+fn now() -> f64 {
+    js_sys::Reflect::get(&js_sys::global(), &"performance".into()).ok()
+        .and_then(|performance| performance.dyn_into::<web_sys::Performance>().ok())
+        .map_or_else(js_sys::Date::now, |performance| performance.now())
+}
 
-    let new_hook = Rc::new(RefCell::new(current_state.clone()));
+/// Creates a Context. `scheduler_options` picks how its scheduler gets
+/// passes once started, see `SchedulerOptions::from_js`.
+#[wasm_bindgen]
+pub fn get_context(scheduler_options: JsValue) -> Result<*mut Context, JsValue> {
+    let context = Box::new(Context::new(SchedulerOptions::from_js(&scheduler_options)?));
+    let context_ptr = Box::into_raw(context);
 
-    fiber.add_hook(Rc::clone(&new_hook));
+    // Delegated event listeners need to find their way back to the Context
+    let mut context = Context::from_ptr(context_ptr);
+    context.self_ptr = context_ptr;
 
-    let set_state = Closure::wrap(Box::new(move |new_state: JsValue| {
-        *new_hook.borrow_mut() = new_state;
-        let mut context = Context::from_ptr(context_ptr);
+    Ok(Box::into_raw(context))
+}
 
-        let current_root = context.current_root.as_ref();
-        let mut root = Fiber::new_root();
-        
-        current_root.map(|current_root| {
-            root.set_alternate(Rc::clone(current_root));
+/// Version of the loaded module, for loaders and devtools
+#[wasm_bindgen]
+pub fn fusion_version() -> String {
+    String::from(env!("CARGO_PKG_VERSION"))
+}
 
-            let current_root = current_root.borrow();
+/// What this build of the module supports, so loaders, devtools and plugins
+/// can adapt instead of failing on a missing export. `scheduler` is the
+/// backend the Context's scheduler was created with.
+#[wasm_bindgen]
+pub fn capabilities(context_ptr: *mut Context) -> JsValue {
+    let context = Context::from_ptr(context_ptr);
+    let scheduler = context.scheduler.backend().name();
+    let _ = Box::into_raw(context);
+
+    let capabilities = js_sys::Object::new();
+    let set = |key: &str, value: JsValue| {
+        js_sys::Reflect::set(&capabilities, &key.into(), &value).unwrap();
+    };
 
-            if let Some(children) = current_root.element_children().as_ref() {
-                root.set_element_children(Some(Rc::clone(&children)));
-            }
+    set("version", fusion_version().into());
+    set("scheduler", scheduler.into());
+    set("hydration", false.into());
+    set("invariants", cfg!(feature = "invariants").into());
+    set("weeAlloc", cfg!(feature = "wee_alloc").into());
+    set("nodeRecycling", true.into());
+    set("eventDelegation", true.into());
 
-            // Store the container HTML element
-            if let Some(dom_node) = current_root.dom_node().as_ref() {
-                root.set_dom_node(Rc::clone(dom_node));
-            }
-        });
+    capabilities.into()
+}
 
-        // Make it the Work in Progress Root and the Next Unit of Work
-        let root = Rc::new(RefCell::new(Box::new(root)));
-        context.wip_root = Some(Rc::clone(&root));
-        context.next_unit_of_work = Some(Rc::clone(&root));
+/// Opts into reusing the DOM nodes of deleted elements for later placements
+/// of the same element type. Disabling it drops every parked node.
+#[wasm_bindgen]
+pub fn set_node_recycling(context_ptr: *mut Context, enabled: bool) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
 
-        Box::into_raw(context);
-    }) as Box<dyn FnMut(JsValue)>).into_js_value();
+    match (enabled, context.node_pool.as_ref()) {
+        (true, None) => {
+            context.node_pool = Some(RefCell::new(NodePool::new()));
+        },
+        (false, Some(pool)) => {
+            pool.borrow_mut().clear();
+            context.node_pool = None;
+        },
+        (_, _) => {}
+    }
 
-    fiber.incr_hook_idx();
-    mem::drop(fiber);
-    mem::drop(wip_fiber);
+    Box::into_raw(context)
+}
 
-    Box::into_raw(context);
+/// Sets what happens to `href`, `src` and other URL props starting with
+/// `javascript:`: "allow", "warn" (the default in debug builds) or "block",
+/// which leaves the attribute out. Other values are ignored.
+#[wasm_bindgen]
+pub fn set_url_policy(context_ptr: *mut Context, policy: String) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    if let Some(policy) = UrlPolicy::from_name(&policy) {
+        context.url_policy = policy;
+    }
+
+    Box::into_raw(context)
+}
+
+/// Switches the diagnostics of this Context, meant to be called once right
+/// after loading: `true` or `false` for all of them, or an object picking
+/// among `warnings`, `strictRender`, `highlightUpdates` and `assertions`.
+/// A URL policy that doesn't block follows `warnings`.
+#[wasm_bindgen]
+pub fn set_dev_mode(context_ptr: *mut Context, options: JsValue) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+    let dev_mode = DevMode::from_options(&options);
+
+    context.dev_mode = dev_mode;
+    context.hotkeys.borrow_mut().set_warnings(dev_mode.warnings);
+
+    if context.url_policy != UrlPolicy::Block {
+        context.url_policy = if dev_mode.warnings { UrlPolicy::Warn } else { UrlPolicy::Allow };
+    }
+
+    Box::into_raw(context)
+}
+
+/// Makes `component` available to UI schemas as a node `type` of `name`
+#[wasm_bindgen]
+pub fn register_schema_component(context_ptr: *mut Context, name: String, component: js_sys::Function) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.schema_components.register(name, component);
+
+    Box::into_raw(context)
+}
+
+/// Builds an element from a JSON UI schema, such as one sent by a server,
+/// resolving its `bind` paths against `data`. Schemas using unknown types,
+/// script-capable elements, event handler props or `javascript:` URLs are
+/// rejected with an error naming the offending node.
+#[wasm_bindgen]
+pub fn render_schema(context_ptr: *mut Context, schema: JsValue, data: JsValue) -> Result<*mut Element, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+
+    let element = context.schema_components.build(&schema, &data);
+
+    let _ = Box::into_raw(context);
+    element
+}
+
+/// Holds finished renders back until the next animation frame before
+/// committing them, except for renders in a blocking lane and `flush_sync`.
+#[wasm_bindgen]
+pub fn set_commit_on_frame(context_ptr: *mut Context, enabled: bool) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.commit_on_frame = enabled;
+
+    Box::into_raw(context)
+}
+
+/// Limits the output of renders. See `Guardrails::configure`.
+#[wasm_bindgen]
+pub fn set_guardrails(context_ptr: *mut Context, options: JsValue) -> Result<(), JsValue> {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let result = context.guardrails.configure(&options);
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Labels `component` and everything it renders with `owner`, a team or
+/// person, in warnings, error component stacks and fiber descriptions.
+/// Registering the component again replaces its owner.
+#[wasm_bindgen]
+pub fn set_component_owner(context_ptr: *mut Context, component: js_sys::Function, owner: String) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.component_owners.retain(|(registered, _)| **registered != component);
+    context.component_owners.push((Rc::new(component), Rc::from(owner)));
+
+    Box::into_raw(context)
+}
+
+/// Swaps a component function for a new implementation (e.g. after a hot
+/// module replacement) and re-renders. Fibers keep their hooks, so state
+/// survives the edit.
+#[wasm_bindgen]
+pub fn replace_component(context_ptr: *mut Context, old_func: js_sys::Function, new_func: js_sys::Function) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+    let new_func = Rc::new(new_func);
+
+    // Earlier replacements of the same component now point at the newest version
+    for (_, replacement) in context.component_replacements.iter_mut() {
+        if **replacement == old_func {
+            *replacement = Rc::clone(&new_func);
+        }
+    }
+    context.component_replacements.push((Rc::new(old_func), Rc::clone(&new_func)));
+
+    if let Some(current_root) = context.current_root.as_ref() {
+        for fiber in current_root.subtree() {
+            let mut fiber = fiber.borrow_mut();
+
+            if let Some(func) = fiber.component_function() {
+                let func = context.resolve_component_function(func);
+                fiber.set_component_function(Some(func));
+            }
+        }
+    }
+
+    context.schedule_root_update();
+
+    Box::into_raw(context)
+}
+
+/// Renders into `container`, an element or a `ShadowRoot`. With a `shadow`
+/// mode ("open" or "closed") the element gets a shadow root of its own to
+/// render into, keeping the tree's styles isolated.
+#[wasm_bindgen]
+pub fn render(context_ptr: *mut Context, element_ptr: *mut Element, container: web_sys::Node, shadow: Option<String>) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+    let element = Element::from_ptr(element_ptr);
+    let previous_root_node = context.root_node().cloned();
+
+    // A render in progress would commit the previous element tree
+    context.discard_render();
+
+    // Create the Root fiber
+    let mut root = Fiber::new_root();
+    
+    // The root element will be the Root fiber's only child
+    let children = vec![element];
+    root.set_element_children(Some(Rc::new(RefCell::new(children))));
+
+    let shadow_root = match container.dyn_into::<web_sys::ShadowRoot>() {
+        Ok(shadow_root) => Some(shadow_root),
+        Err(container) => {
+            let container: HTMLElement = container.unchecked_into();
+            let shadow_root = shadow.map(|mode| context.attach_shadow_root(&container, &mode));
+            context.shadow_root = None;
+
+            // Store the container HTML element
+            context.container = Some(container.clone());
+            root.set_dom_node(Rc::new(RefCell::new(Node::Element(container))));
+
+            shadow_root
+        },
+    };
+
+    if let Some(shadow_root) = shadow_root {
+        context.container = Some(shadow_root.host());
+        context.shadow_root = Some(shadow_root.clone());
+        root.set_dom_node(Rc::new(RefCell::new(Node::Shadow(shadow_root))));
+    }
+
+    context.move_delegated_listeners(previous_root_node);
+
+    // Set the current root as the alternate root
+    if let Some(current_root) = context.current_root.as_ref() {
+        root.set_alternate(Rc::clone(current_root));
+        root.set_id(current_root.borrow().id());
+    } else {
+        root.set_id(context.allocate_fiber_id());
+    }
+
+    // Make it the Work in Progress Root and the Next Unit of Work
+    let root = Rc::new(RefCell::new(Box::new(root)));
+    context.wip_root = Some(Rc::clone(&root));
+    context.next_unit_of_work = Some(Rc::clone(&root));
+    context.schedule_work();
+
+    Box::into_raw(context)
+}
+
+/// Renders `elements[i]` into `containers[i]` in one batch. See
+/// `Context::render_many`. Lists of different lengths render nothing: the
+/// elements are freed and an error thrown.
+#[wasm_bindgen]
+pub fn render_many(context_ptr: *mut Context, element_ptrs: &[u32], containers: js_sys::Array) -> Result<*mut Context, JsValue> {
+    if element_ptrs.len() != containers.length() as usize {
+        for element_ptr in element_ptrs {
+            mem::drop(Element::from_ptr(*element_ptr as *mut Element));
+        }
+
+        return Err(js_sys::Error::new(&format!(
+            "Reactron: renderMany got {} elements for {} containers",
+            element_ptrs.len(),
+            containers.length(),
+        )).into());
+    }
+
+    let mut context = Context::from_ptr(context_ptr);
+
+    let pairs = element_ptrs.iter()
+        .zip(containers.iter())
+        .map(|(element_ptr, container)| (Element::from_ptr(*element_ptr as *mut Element), container.unchecked_into()))
+        .collect();
+
+    context.render_many(pairs);
+
+    Ok(Box::into_raw(context))
+}
+
+/// Builds `element` for `container` without attaching it: the tree renders
+/// in the idle lane into a detached element with the container's tag, and
+/// `activate` swaps it in. Meant for a Context of its own. Effects run when
+/// the detached tree commits, so layout effects measure nothing until it's
+/// activated.
+#[wasm_bindgen]
+pub fn prerender(context_ptr: *mut Context, element_ptr: *mut Element, container: HTMLElement) -> Result<*mut Context, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+
+    let staging = context.document.create_element(&container.tag_name());
+
+    let _ = Box::into_raw(context);
+    let staging: HTMLElement = staging?.unchecked_into();
+
+    let mut context = Context::from_ptr(render(context_ptr, element_ptr, staging.into(), None));
+    context.prerender_target = Some(container);
+    context.wip_lane = Some(Lane::Idle);
+
+    Ok(Box::into_raw(context))
+}
+
+/// Moves a prerendered tree into its container. See
+/// `Context::activate_prerender`.
+#[wasm_bindgen]
+pub fn activate(context_ptr: *mut Context) -> Result<(), JsValue> {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let result = context.activate_prerender();
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Removes the tree rendered by `render` from its container. Components are
+/// unmounted and their effects cleaned up by the following work loop passes.
+#[wasm_bindgen]
+pub fn unmount(context_ptr: *mut Context) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.unmount_root();
+
+    Box::into_raw(context)
+}
+
+/// Whether a render, commit or passive effects are still waiting for the
+/// work loop
+#[wasm_bindgen]
+pub fn has_pending_work(context_ptr: *mut Context) -> bool {
+    let context = Context::from_ptr(context_ptr);
+
+    let pending = context.has_pending_work();
+
+    let _ = Box::into_raw(context);
+
+    pending
+}
+
+/// One pass of the work loop, for hosts driving it themselves.
+/// `time_remaining` is what the idle deadline has left, the frame budget
+/// when left out; renders that don't block yield there.
+#[wasm_bindgen]
+pub fn work_loop(context_ptr: *mut Context, time_remaining: Option<f64>) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.work_loop(time_remaining).unwrap();
+
+    Box::into_raw(context)
+}
+
+/// Rolling work loop measurements, see `FrameStats::to_js`
+#[wasm_bindgen]
+pub fn frame_stats(context_ptr: *mut Context) -> JsValue {
+    let context = Context::from_ptr(context_ptr);
+
+    let stats = context.frame_stats.to_js();
+
+    let _ = Box::into_raw(context);
+    stats
+}
+
+/// Lets the module schedule its own work loop passes, each one at most
+/// `frame_budget` milliseconds long (FRAME_BUDGET_MS by default). See
+/// `Context::start`.
+#[wasm_bindgen]
+pub fn start(context_ptr: *mut Context, frame_budget: Option<f64>) -> Result<(), JsValue> {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let result = context.start(frame_budget);
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Runs `callback` with the updates it schedules in `lane`: "sync",
+/// "user-input", "default", "transition" or "idle".
+#[wasm_bindgen]
+pub fn run_with_lane(context_ptr: *mut Context, lane: String, callback: js_sys::Function) -> Result<JsValue, JsValue> {
+    let lane = Lane::from_name(&lane)
+        .ok_or_else(|| js_sys::Error::new(&format!("Reactron: unknown update lane '{}'", lane)))?;
+
+    run_in_lane(context_ptr, lane, callback)
+}
+
+/// Runs `callback` as a batch of its own: its updates start one render as
+/// soon as it returns, instead of joining the rest of the task's updates.
+/// Batches may nest; the outermost one starts the render.
+#[wasm_bindgen]
+pub fn batched_updates(context_ptr: *mut Context, callback: js_sys::Function) -> Result<JsValue, JsValue> {
+    let mut context = Context::from_ptr(context_ptr);
+    context.batch_depth += 1;
+
+    // The callback's updates reach the Context through its pointer
+    let _ = Box::into_raw(context);
+    let result = callback.call0(&JsValue::NULL);
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.batch_depth -= 1;
+    if context.batch_depth == 0 {
+        context.flush_updates();
+    }
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Runs `callback` with its updates marked as a transition: their render
+/// yields to the browser and is thrown away for more urgent updates, such
+/// as the ones from user input, so it never holds up typing.
+#[wasm_bindgen]
+pub fn start_transition(context_ptr: *mut Context, callback: js_sys::Function) -> Result<(), JsValue> {
+    run_in_lane(context_ptr, Lane::Transition, callback).map(|_| ())
+}
+
+fn run_in_lane(context_ptr: *mut Context, lane: Lane, callback: js_sys::Function) -> Result<JsValue, JsValue> {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let outer_lane = mem::replace(&mut context.update_lane, lane);
+
+    // The callback's updates reach the Context through its pointer
+    let _ = Box::into_raw(context);
+    let result = callback.call0(&JsValue::NULL);
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.update_lane = outer_lane;
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Runs `callback`, if any, with its updates in the sync lane, then renders
+/// and commits every pending update right away. See `Context::flush_sync`.
+#[wasm_bindgen]
+pub fn flush_sync(context_ptr: *mut Context, callback: Option<js_sys::Function>) -> Result<JsValue, JsValue> {
+    let result = match callback {
+        Some(callback) => run_in_lane(context_ptr, Lane::Sync, callback),
+        None => Ok(JsValue::UNDEFINED),
+    };
+    let mut context = Context::from_ptr(context_ptr);
+
+    let flushed = context.flush_sync();
+
+    let _ = Box::into_raw(context);
+    flushed.and(result)
+}
+
+#[wasm_bindgen]
+pub fn use_state(context_ptr: *mut Context, initial_value: JsValue) -> Box<[JsValue]> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = context.wip_functional_fiber.as_ref().unwrap();
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_state = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().state().cloned())
+    });
+
+    let current_state = old_state.unwrap_or(initial_value);
+
+    let new_hook = Rc::new(RefCell::new(Hook::State(current_state.clone())));
+
+    fiber.add_hook(Rc::clone(&new_hook));
+
+    let set_state = Closure::wrap(Box::new(move |new_state: JsValue| {
+        *new_hook.borrow_mut() = Hook::State(new_state);
+        let mut context = Context::from_ptr(context_ptr);
+
+        context.schedule_fiber_update(fiber_id);
+
+        let _ = Box::into_raw(context);
+    }) as Box<dyn FnMut(JsValue)>).into_js_value();
+
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
 
     vec![current_state, set_state].into_boxed_slice()
 }
+
+/// State with a bounded undo history. Returns `[value, set, { undo, redo,
+/// reset, canUndo, canRedo }]`, the functions keeping their identity across
+/// renders. `options.limit` caps the number of undoable steps and
+/// `options.hotkeys` binds Ctrl/Cmd+Z and Ctrl/Cmd+Shift+Z while mounted.
+#[wasm_bindgen]
+pub fn use_undoable_state(context_ptr: *mut Context, initial_value: JsValue, options: JsValue) -> Box<[JsValue]> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let read_option = |name: &str| js_sys::Reflect::get(&options, &name.into()).unwrap_or(JsValue::UNDEFINED);
+    let limit = read_option("limit").as_f64().map_or(UNDO_HISTORY_LIMIT, |limit| limit.max(0.0) as usize);
+
+    let old_state = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().undoable_state().cloned())
+    });
+
+    let state = match old_state {
+        Some(state) => {
+            state.history().borrow_mut().set_limit(limit);
+            state
+        },
+        None => {
+            let mut state = UndoableState::new(initial_value, limit, move || {
+                let mut context = Context::from_ptr(context_ptr);
+                context.schedule_fiber_update(fiber_id);
+                let _ = Box::into_raw(context);
+            });
+
+            if read_option("hotkeys").is_truthy() {
+                let undo_bindings = UNDO_HOTKEYS.iter().map(|combo| (combo, state.undo().clone()));
+                let redo_bindings = REDO_HOTKEYS.iter().map(|combo| (combo, state.redo().clone()));
+
+                let hotkey_ids = undo_bindings.chain(redo_bindings)
+                    .filter_map(|(combo, handler)| Some(HotkeyRegistry::register(&context.hotkeys, Hotkey::parse(combo)?, handler)))
+                    .collect();
+
+                state.set_hotkey_ids(hotkey_ids);
+            }
+
+            state
+        },
+    };
+    let result = state.to_js();
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::Undoable(state))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    result
+}
+
+/// Returns a function that keeps the same identity across renders but always
+/// calls the callback from the latest committed render. Only the first
+/// argument is forwarded.
+#[wasm_bindgen]
+pub fn use_event_callback(context_ptr: *mut Context, callback: js_sys::Function) -> js_sys::Function {
+    let mut context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_event_callback = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().event_callback().cloned())
+    });
+
+    let event_callback = old_event_callback.unwrap_or_else(EventCallback::new);
+    let stable = event_callback.stable().clone();
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::EventCallback(event_callback.clone()))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    context.pending_event_callbacks.push((event_callback, callback));
+
+    let _ = Box::into_raw(context);
+
+    stable
+}
+
+/// Returns a function `announce(message, politeness)` posting to the screen
+/// reader live region of the Context. `politeness` is "polite" (default) or
+/// "assertive". The function keeps its identity across renders.
+#[wasm_bindgen]
+pub fn use_announcer(context_ptr: *mut Context) -> js_sys::Function {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = context.wip_functional_fiber.as_ref().unwrap();
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_announce = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().announcer().cloned())
+    });
+
+    let announce = old_announce.unwrap_or_else(|| {
+        let announcer = Rc::clone(&context.announcer);
+        announcer.borrow_mut().retain();
+
+        Closure::wrap(Box::new(move |message: JsValue, politeness: JsValue| {
+            if let Some(message) = message.as_string() {
+                Announcer::announce(&announcer, message, Politeness::from_value(&politeness));
+            }
+        }) as Box<dyn FnMut(JsValue, JsValue)>).into_js_value().unchecked_into()
+    });
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::Announcer(announce.clone()))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    announce
+}
+
+/// Guards the page against leaving while `when` is true, e.g. with a dirty
+/// form mounted. Reloads and hard navigations get the browser's own
+/// `beforeunload` prompt; routers call `confirm_navigation` before soft ones.
+#[wasm_bindgen]
+pub fn use_blocker(context_ptr: *mut Context, when: bool, message: Option<String>) {
+    let mut context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_id = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().blocker())
+    });
+
+    let id = match old_id {
+        Some(id) => {
+            context.blockers.update(id, when, message);
+            id
+        },
+        None => context.blockers.register(when, message),
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::Blocker(id))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+}
+
+/// Called by routers before they navigate. Returns whether the navigation
+/// may go on: right away when no mounted `use_blocker` is active, otherwise
+/// once the user confirmed the message of the innermost one.
+#[wasm_bindgen]
+pub fn confirm_navigation(context_ptr: *mut Context) -> bool {
+    let context = Context::from_ptr(context_ptr);
+
+    let confirmed = match context.blockers.active_message() {
+        Some(message) => web_sys::window().unwrap().confirm_with_message(message).unwrap_or(false),
+        None => true,
+    };
+
+    let _ = Box::into_raw(context);
+
+    confirmed
+}
+
+/// Called by routers when they navigate. Once the render of the new page
+/// commits, focus moves to `target` (an element or a selector, the render
+/// container by default), the window scrolls to `scroll_y` (top by default)
+/// and `announcement` (the document title by default) goes to the live region.
+#[wasm_bindgen]
+pub fn focus_after_navigation(context_ptr: *mut Context, target: JsValue, scroll_y: Option<f64>, announcement: Option<String>) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+    let navigation_focus = NavigationFocus::new(target, scroll_y, announcement);
+
+    if context.is_render_pending() {
+        context.pending_navigation_focus = Some(navigation_focus);
+    } else {
+        navigation_focus.apply(&context.document, context.container.as_ref(), &context.announcer);
+    }
+
+    Box::into_raw(context)
+}
+
+/// Text direction the component renders in, "ltr" or "rtl": the closest
+/// `dir` attribute above it, such as the one of a `Direction` provider, then
+/// the document's.
+#[wasm_bindgen]
+pub fn use_direction(context_ptr: *mut Context) -> String {
+    let context = Context::from_ptr(context_ptr);
+
+    let direction = Direction::of(&context.wip_functional_fiber.as_ref().unwrap().borrow(), &context.document);
+
+    let _ = Box::into_raw(context);
+
+    String::from(direction.as_str())
+}
+
+/// Queues `url` for an idle-time prefetch, so it's in the HTTP cache by the
+/// time it's needed. `destination` is the `as` of the prefetch ("image",
+/// "font", "style"...). Each URL is fetched once; nothing is fetched when the
+/// user asked to save data.
+#[wasm_bindgen]
+pub fn use_prefetch(context_ptr: *mut Context, url: String, destination: Option<String>) {
+    let context = Context::from_ptr(context_ptr);
+
+    PrefetchQueue::enqueue(&context.prefetch_queue, url, destination);
+
+    let _ = Box::into_raw(context);
+}
+
+/// Registers a stylesheet for the component called `name`, once, and returns
+/// an object mapping each of its class names to a name scoped to the
+/// component, e.g. `{ root: "root-Button-3fa2c1" }`. The stylesheet is added
+/// to the document, or to the shadow root rendered into, at the next commit.
+#[wasm_bindgen]
+pub fn scoped_css(context_ptr: *mut Context, name: String, source: String) -> js_sys::Object {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let class_map = context.styles.register(&name, &source);
+
+    let _ = Box::into_raw(context);
+
+    class_map
+}
+
+/// Runs a computation in a pooled module worker loaded from `url`, which gets
+/// `input` as its message and answers with the result. Returns `[value,
+/// pending]`: the latest result (undefined until the first one) and whether a
+/// job is running. A new job only starts when `deps` change, cancelling the
+/// one still running.
+#[wasm_bindgen]
+pub fn use_worker(context_ptr: *mut Context, url: String, input: JsValue, deps: Option<js_sys::Array>) -> Box<[JsValue]> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_job = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().worker_job().cloned())
+    });
+
+    let job = match old_job {
+        Some(job) if job.url() == url && (deps.is_none() || !hooks::deps_changed(job.deps(), deps.as_ref())) => job,
+        old_job => {
+            let value = old_job.as_ref().map_or(JsValue::UNDEFINED, |job| {
+                job.cancel();
+                job.state().borrow().value.clone()
+            });
+
+            let job = WorkerJob::new(url, deps, value);
+            let component_stack = fiber.component_stack();
+
+            let started = job.start(&context.worker_pool, &input, move |result| {
+                let mut context = Context::from_ptr(context_ptr);
+
+                if let Err(error) = result {
+                    context.report_error(&error, &component_stack);
+                }
+
+                context.schedule_fiber_update(fiber_id);
+                let _ = Box::into_raw(context);
+            });
+
+            if let Err(error) = started {
+                job.state().borrow_mut().pending = false;
+                context.report_error(&error, &fiber.component_stack());
+            }
+
+            job
+        }
+    };
+
+    let result = {
+        let state = job.state().borrow();
+        vec![state.value.clone(), JsValue::from(state.pending)]
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::Worker(job))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    result.into_boxed_slice()
+}
+
+/// Calls `handler` with the `keydown` event whenever the key combination
+/// (`"ctrl+k"`, `"shift+alt+ArrowUp"`) is pressed anywhere in the document,
+/// for as long as the component is mounted. A malformed combination binds
+/// nothing and is warned about when it first shows up.
+#[wasm_bindgen]
+pub fn use_hotkey(context_ptr: *mut Context, combo: String, handler: js_sys::Function) {
+    let mut context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_hook = fiber.alternate().and_then(|alternate| {
+        alternate.borrow().get_hook_at(fiber.hook_idx() as usize)
+    });
+    let old_binding = old_hook.as_ref().and_then(|hook| hook.borrow().hotkey_binding().cloned());
+
+    let hook = match Hotkey::parse(&combo) {
+        Some(hotkey) => {
+            let binding = match old_binding {
+                Some(binding) => {
+                    if *binding.hotkey() != hotkey {
+                        context.hotkeys.borrow_mut().rebind(binding.id(), hotkey.clone());
+                    }
+
+                    HotkeyBinding::new(binding.id(), hotkey, binding.callback().clone())
+                },
+                None => {
+                    let callback = EventCallback::new();
+                    let id = HotkeyRegistry::register(&context.hotkeys, hotkey.clone(), callback.stable().clone());
+
+                    HotkeyBinding::new(id, hotkey, callback)
+                },
+            };
+
+            context.pending_event_callbacks.push((binding.callback().clone(), handler));
+
+            Hook::Hotkey(binding)
+        },
+        None => {
+            if !old_hook.is_some_and(|hook| matches!(*hook.borrow(), Hook::Failed)) {
+                web_sys::console::warn_1(&format!("Reactron: invalid hotkey '{}'{}", combo, fiber.component_stack()).into());
+            }
+
+            if let Some(binding) = old_binding {
+                context.hotkeys.borrow_mut().unregister(binding.id());
+            }
+
+            Hook::Failed
+        },
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(hook)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+}
+
+/// Tracks whether `target` is the fullscreen element. Returns `[active,
+/// enter, exit]`, where `enter` and `exit` keep their identity across
+/// renders and return the browser's promise. Fullscreen is left on unmount.
+#[wasm_bindgen]
+pub fn use_fullscreen(context_ptr: *mut Context, target: Option<web_sys::Element>) -> Box<[JsValue]> {
+    use_display_mode(context_ptr, DisplayMode::Fullscreen, target.map_or(JsValue::NULL, JsValue::from))
+}
+
+/// Like `use_fullscreen`, for a video element floating in Picture-in-Picture
+#[wasm_bindgen]
+pub fn use_picture_in_picture(context_ptr: *mut Context, video: Option<web_sys::HtmlVideoElement>) -> Box<[JsValue]> {
+    use_display_mode(context_ptr, DisplayMode::PictureInPicture, video.map_or(JsValue::NULL, JsValue::from))
+}
+
+fn use_display_mode(context_ptr: *mut Context, mode: DisplayMode, target: JsValue) -> Box<[JsValue]> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_binding = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().display_mode().cloned())
+    });
+
+    let binding = match old_binding {
+        Some(binding) if binding.mode() == mode => binding,
+        _ => DisplayModeBinding::mount(mode, context.document.clone(), move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.schedule_fiber_update(fiber_id);
+            let _ = Box::into_raw(context);
+        }),
+    };
+    binding.set_target(target);
+
+    let result = vec![
+        JsValue::from(binding.is_active()),
+        binding.enter().clone().into(),
+        binding.exit().clone().into(),
+    ];
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::DisplayMode(binding))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    result.into_boxed_slice()
+}
+
+/// Keeps the screen awake for as long as the component is mounted, where the
+/// browser supports it. The lock is shared by every mounted user.
+#[wasm_bindgen]
+pub fn use_wake_lock(context_ptr: *mut Context) {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let is_mounted = fiber.alternate().is_some_and(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.is_some_and(|hook| hook.borrow().is_wake_lock())
+    });
+
+    if !is_mounted {
+        ScreenWakeLock::retain(&context.wake_lock);
+    }
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::WakeLock)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+}
+
+/// Object retained for the lifetime of the component, e.g. a map or chart
+/// handle. `create` runs on mount only and the same value is returned on
+/// every render, so effects can reach it; `dispose` gets it once the
+/// component unmounts, after the cleanups of its effects.
+#[wasm_bindgen]
+pub fn use_instance(context_ptr: *mut Context, create: js_sys::Function, dispose: Option<js_sys::Function>) -> Result<JsValue, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_value = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().instance().map(|instance| instance.value().clone()))
+    });
+
+    let value = match old_value {
+        Some(value) => Ok(value),
+        None => create.call0(&JsValue::NULL),
+    };
+
+    let hook = match &value {
+        Ok(value) => Hook::Instance(Instance::new(value.clone(), dispose)),
+        Err(_) => Hook::Failed,
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(hook)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    value
+}
+
+/// Whether the user hasn't touched the mouse, keyboard or screen for
+/// `timeout` milliseconds. Components re-render when it changes.
+#[wasm_bindgen]
+pub fn use_idle(context_ptr: *mut Context, timeout: f64) -> bool {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_id = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().idle_watcher())
+    });
+
+    let id = match old_id {
+        Some(id) => {
+            IdleTracker::set_timeout(&context.idle_tracker, id, timeout);
+            id
+        },
+        None => IdleTracker::watch(&context.idle_tracker, timeout, move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.schedule_fiber_update(fiber_id);
+            let _ = Box::into_raw(context);
+        }),
+    };
+    let is_idle = context.idle_tracker.borrow().is_idle(id);
+
+    fiber.add_hook(Rc::new(RefCell::new(Hook::Idle(id))));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    is_idle
+}
+
+/// Name of the breakpoint `element` currently falls into, out of
+/// `{ name: minWidth }` breakpoints: the one with the largest minimum width
+/// not above the element's content width. Null until the element has been
+/// measured. Components re-render when the breakpoint changes.
+#[wasm_bindgen]
+pub fn use_container_query(context_ptr: *mut Context, element: Option<HTMLElement>, breakpoints: JsValue) -> Result<Option<String>, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_query = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().container_query().cloned())
+    });
+
+    let query = match old_query {
+        Some(query) => Ok(query),
+        None => ContainerQuery::mount(move || {
+            let mut context = Context::from_ptr(context_ptr);
+            context.schedule_fiber_update(fiber_id);
+            let _ = Box::into_raw(context);
+        }),
+    };
+
+    let (hook, active) = match query {
+        Ok(query) => {
+            query.set_breakpoints(&breakpoints);
+            query.set_target(element);
+            let active = query.active();
+
+            (Hook::ContainerQuery(query), Ok(active))
+        },
+        Err(error) => (Hook::Failed, Err(error)),
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(hook)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    active
+}
+
+/// Joins the `BroadcastChannel` called `name`, shared with the other tabs and
+/// windows of the same origin. Returns `[message, post]`: the latest message
+/// another context posted (undefined until one arrives) and a stable function
+/// posting to them. The channel is closed on unmount or when `name` changes.
+#[wasm_bindgen]
+pub fn use_broadcast_channel(context_ptr: *mut Context, name: String) -> Result<Box<[JsValue]>, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+    let fiber_id = fiber.id();
+
+    let old_binding = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| hook.borrow().broadcast_channel().cloned())
+    });
+
+    let binding = match old_binding {
+        Some(binding) if binding.name() == name => Ok(binding),
+        old_binding => {
+            if let Some(old_binding) = old_binding {
+                old_binding.close();
+            }
+
+            ChannelBinding::open(name, move || {
+                let mut context = Context::from_ptr(context_ptr);
+                context.schedule_fiber_update(fiber_id);
+                let _ = Box::into_raw(context);
+            })
+        }
+    };
+
+    let (hook, result) = match binding {
+        Ok(binding) => {
+            let result = vec![binding.latest(), binding.post().clone().into()];
+
+            (Hook::BroadcastChannel(binding), Ok(result.into_boxed_slice()))
+        },
+        Err(error) => (Hook::Failed, Err(error)),
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(hook)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    result
+}
+
+/// Describes every hook of a committed fiber as `{ type, value, deps }`
+/// objects, for devtools and tests.
+#[wasm_bindgen]
+pub fn inspect_hooks(context_ptr: *mut Context, fiber_id: u32) -> js_sys::Array {
+    let context = Context::from_ptr(context_ptr);
+    let descriptions = js_sys::Array::new();
+
+    if let Some(fiber) = context.find_fiber(fiber_id) {
+        let fiber = fiber.borrow();
+
+        for hook in fiber.hooks().into_iter().flatten() {
+            descriptions.push(&hook.borrow().describe());
+        }
+    }
+
+    let _ = Box::into_raw(context);
+
+    descriptions
+}
+
+/// Schedules `callback` to run after the commit when `deps` changed since the
+/// last render (or on every render without deps). A function returned by the
+/// callback is called before the next run and on unmount.
+#[wasm_bindgen]
+pub fn use_effect(context_ptr: *mut Context, callback: js_sys::Function, deps: Option<js_sys::Array>) {
+    let mut context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_effect = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| {
+            hook.borrow().effect().map(|effect| (effect.deps().cloned(), effect.cleanup().cloned()))
+        })
+    });
+
+    let has_changed = old_effect.as_ref().is_none_or(|(old_deps, _)| {
+        hooks::deps_changed(old_deps.as_ref(), deps.as_ref())
+    });
+    let old_cleanup = old_effect.and_then(|(_, cleanup)| cleanup);
+
+    let new_hook = if has_changed {
+        let new_hook = Rc::new(RefCell::new(Hook::Effect(Effect::new(deps, None))));
+
+        context.passive_effects.push(PassiveEffect::new(
+            Rc::clone(&new_hook),
+            Some(callback),
+            old_cleanup,
+            fiber.component_stack()
+        ));
+
+        new_hook
+    } else {
+        Rc::new(RefCell::new(Hook::Effect(Effect::new(deps, old_cleanup))))
+    };
+
+    fiber.add_hook(new_hook);
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+}
+
+/// Exposes the object returned by `create` to the host page through
+/// `send_command`. `create` runs again when `deps` change, or on every
+/// render without them, so the methods see the latest state.
+#[wasm_bindgen]
+pub fn use_imperative_handle(context_ptr: *mut Context, create: js_sys::Function, deps: Option<js_sys::Array>) -> Result<(), JsValue> {
+    let context = Context::from_ptr(context_ptr);
+    let wip_fiber = Rc::clone(context.wip_functional_fiber.as_ref().unwrap());
+    let mut fiber = wip_fiber.borrow_mut();
+
+    let old_handle = fiber.alternate().and_then(|alternate| {
+        let alternate = alternate.borrow();
+        let hook = alternate.get_hook_at(fiber.hook_idx() as usize);
+
+        hook.and_then(|hook| {
+            hook.borrow().imperative_handle().map(|handle| (handle.handle().clone(), handle.deps().cloned()))
+        })
+    });
+
+    let handle = match old_handle {
+        Some((handle, old_deps)) if !hooks::deps_changed(old_deps.as_ref(), deps.as_ref()) => Ok(handle),
+        _ => create.call0(&JsValue::NULL),
+    };
+
+    let (hook, result) = match handle {
+        Ok(handle) => (Hook::ImperativeHandle(ImperativeHandle::new(handle, deps)), Ok(())),
+        Err(error) => (Hook::Failed, Err(error)),
+    };
+
+    fiber.add_hook(Rc::new(RefCell::new(hook)));
+    fiber.incr_hook_idx();
+    mem::drop(fiber);
+
+    let _ = Box::into_raw(context);
+
+    result
+}
+
+/// Runs the `name` command of a mounted component's imperative handle. See
+/// `Context::send_command`.
+#[wasm_bindgen]
+pub fn send_command(context_ptr: *mut Context, target: JsValue, name: String, payload: JsValue) -> Result<JsValue, JsValue> {
+    let context = Context::from_ptr(context_ptr);
+
+    let result = context.send_command(&target, &name, &payload);
+
+    let _ = Box::into_raw(context);
+    result
+}
+
+/// Sets the callback receiving `(error, componentStack)` for errors fusion
+/// catches, such as throwing components and effects.
+#[wasm_bindgen]
+pub fn set_error_handler(context_ptr: *mut Context, handler: Option<js_sys::Function>) -> *mut Context {
+    let mut context = Context::from_ptr(context_ptr);
+
+    context.error_handler = handler;
+
+    Box::into_raw(context)
+}
+
+/// Snapshot of the committed fiber tree with the stable id of every fiber,
+/// for devtools and external tooling.
+#[wasm_bindgen]
+pub fn debug_tree(context_ptr: *mut Context) -> JsValue {
+    let context = Context::from_ptr(context_ptr);
+
+    let tree = context.current_root.as_ref()
+        .map_or(JsValue::NULL, fiber::describe_tree);
+
+    let _ = Box::into_raw(context);
+
+    tree
+}
+
+/// Stable id of the fiber that rendered `node` (or its closest rendered
+/// ancestor), so DOM nodes can be correlated with `debug_tree` entries.
+#[wasm_bindgen]
+pub fn fiber_id_of_node(context_ptr: *mut Context, node: &web_sys::Node) -> Option<u32> {
+    let context = Context::from_ptr(context_ptr);
+
+    let fiber_id = events::fiber_id_of(node, &context.fiber_id_key);
+
+    let _ = Box::into_raw(context);
+
+    fiber_id
+}
+
+/// Plain JS description of a single fiber: `{ id, type, name, parentId }`.
+/// Returns null for unknown ids.
+#[wasm_bindgen]
+pub fn describe_fiber(context_ptr: *mut Context, fiber_id: u32) -> JsValue {
+    let context = Context::from_ptr(context_ptr);
+
+    let description = context.find_fiber(fiber_id)
+        .map_or(JsValue::NULL, |fiber| fiber::describe_fiber(&fiber));
+
+    let _ = Box::into_raw(context);
+
+    description
+}
+
+/// Whether `fiber_id` is `ancestor_id` or one of its descendants, e.g. to
+/// tell which child of a container an event's `targetFiberId` belongs to.
+#[wasm_bindgen]
+pub fn is_fiber_within(context_ptr: *mut Context, fiber_id: u32, ancestor_id: u32) -> bool {
+    let context = Context::from_ptr(context_ptr);
+
+    let within = context.find_fiber(fiber_id).is_some_and(|fiber| {
+        std::iter::once(Rc::clone(&fiber))
+            .chain(fiber.parents())
+            .any(|fiber| fiber.borrow().id() == ancestor_id)
+    });
+
+    let _ = Box::into_raw(context);
+
+    within
+}
+
+/// Checks an element tree from `createElement` without rendering it, e.g. a
+/// dashboard built from user configuration. `options` may set `maxDepth`
+/// and `maxNodes`. Returns `{ valid, errors: [{ path, message }], nodes,
+/// depth }`. The element stays usable for `render`.
+#[wasm_bindgen]
+pub fn validate_element(element_ptr: *mut Element, options: JsValue) -> JsValue {
+    let element = Element::from_ptr(element_ptr);
+    let document = web_sys::window().unwrap().document().unwrap();
+
+    let limit = |name: &str, default: usize| {
+        js_sys::Reflect::get(&options, &name.into()).ok()
+            .and_then(|limit| limit.as_f64())
+            .map_or(default, |limit| limit as usize)
+    };
+
+    let limits = validate::Limits {
+        max_depth: limit("maxDepth", VALIDATION_MAX_DEPTH),
+        max_nodes: limit("maxNodes", VALIDATION_MAX_NODES),
+    };
+
+    let report = validate::validate(&document, &element, &limits).to_js();
+
+    let _ = Box::into_raw(element);
+
+    report
+}
+
+/// Forces the subtree of `fiber_id` to re-render and rewrite its DOM nodes,
+/// for integrations changing things fusion can't observe. Returns false for
+/// unknown ids.
+#[wasm_bindgen]
+pub fn invalidate_subtree(context_ptr: *mut Context, fiber_id: u32) -> bool {
+    let mut context = Context::from_ptr(context_ptr);
+
+    let found = context.invalidate_subtree(fiber_id);
+
+    let _ = Box::into_raw(context);
+
+    found
+}
+
+/// Committed DOM node of a fiber. Function components resolve to the first
+/// DOM node they rendered. Returns null for unknown ids.
+#[wasm_bindgen]
+pub fn get_dom_node(context_ptr: *mut Context, fiber_id: u32) -> JsValue {
+    let context = Context::from_ptr(context_ptr);
+
+    let dom_node = context.find_fiber(fiber_id).and_then(|fiber| {
+        fiber.subtree()
+            .find_map(|fiber| fiber.borrow().dom_node().map(|node| node.borrow().as_js_value().clone()))
+    });
+
+    let _ = Box::into_raw(context);
+
+    dom_node.unwrap_or(JsValue::NULL)
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod js_tests {
+    use super::*;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    // Strict rendering frees what the discarded render returned, except
+    // elements it was handed through props
+    #[wasm_bindgen_test]
+    fn elements_in_props_finds_forwarded_elements() {
+        let props = js_sys::Object::new();
+        js_sys::Reflect::set(&props, &"children".into(), &js_sys::Array::of2(&8.into(), &"text".into())).unwrap();
+        js_sys::Reflect::set(&props, &"fallback".into(), &16.into()).unwrap();
+        js_sys::Reflect::set(&props, &"label".into(), &"name".into()).unwrap();
+
+        let expected: HashSet<*const Element> = [8usize, 16].iter().map(|&ptr| ptr as *const Element).collect();
+        assert_eq!(Context::elements_in_props(&props.into()), expected);
+        assert!(Context::elements_in_props(&JsValue::NULL).is_empty());
+    }
+}
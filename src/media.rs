@@ -0,0 +1,110 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Blob, Element as HTMLElement, HtmlMediaElement, MediaStream, MediaStreamTrack, Url};
+use super::promise;
+
+// Node property holding the object URL created for a Blob source
+static OBJECT_URL_KEY: &str = "__fusionObjectUrl";
+
+/// What a `srcObject` prop can hold
+#[derive(Clone, PartialEq, Eq)]
+pub enum MediaSource {
+    Stream(MediaStream),
+    Blob(Blob),
+}
+
+impl MediaSource {
+    pub fn from_value(value: JsValue) -> Option<Self> {
+        match value.dyn_into::<MediaStream>() {
+            Ok(stream) => Some(MediaSource::Stream(stream)),
+            Err(value) => value.dyn_into::<Blob>().ok().map(MediaSource::Blob),
+        }
+    }
+}
+
+pub fn is_media_element(node: &HTMLElement) -> bool {
+    node.is_instance_of::<HtmlMediaElement>()
+}
+
+/// Writes the `srcObject` IDL property. There is no attribute to reflect a
+/// MediaStream, so it can't go through `src`. Browsers refusing a Blob as
+/// `srcObject` get an object URL in `src` instead, revoked once replaced.
+pub fn set_src_object(node: &HTMLElement, source: Option<&MediaSource>) {
+    let media = match node.dyn_ref::<HtmlMediaElement>() {
+        Some(media) => media,
+        None => return,
+    };
+
+    revoke_object_url(media);
+
+    match source {
+        Some(MediaSource::Stream(stream)) => media.set_src_object(Some(stream)),
+        Some(MediaSource::Blob(blob)) => {
+            if js_sys::Reflect::set(media, &"srcObject".into(), blob).is_err() {
+                if let Ok(url) = Url::create_object_url_with_blob(blob) {
+                    media.set_src(&url);
+                    let _ = js_sys::Reflect::set(media, &OBJECT_URL_KEY.into(), &url.into());
+                }
+            }
+        },
+        None => media.set_src_object(None),
+    }
+}
+
+fn revoke_object_url(media: &HtmlMediaElement) {
+    let url = js_sys::Reflect::get(media, &OBJECT_URL_KEY.into()).ok().and_then(|url| url.as_string());
+
+    if let Some(url) = url {
+        let _ = Url::revoke_object_url(&url);
+        let _ = js_sys::Reflect::delete_property(media, &OBJECT_URL_KEY.into());
+        media.remove_attribute("src").ok();
+    }
+}
+
+/// Starts or pauses playback to match the `playing` prop. A rejected `play()`,
+/// e.g. blocked by the autoplay policy, is logged instead of left unhandled.
+pub fn sync_playback(node: &HTMLElement, playing: bool) {
+    let media = match node.dyn_ref::<HtmlMediaElement>() {
+        Some(media) => media,
+        None => return,
+    };
+
+    if !playing {
+        if !media.paused() {
+            let _ = media.pause();
+        }
+        return;
+    }
+
+    if !media.paused() {
+        return;
+    }
+
+    if let Ok(promise) = media.play() {
+        let on_reject = Closure::once_into_js(|error: JsValue| {
+            web_sys::console::warn_2(&JsValue::from_str("Reactron: media playback was refused"), &error);
+        });
+
+        let _ = promise::then(&promise, &JsValue::UNDEFINED, &on_reject);
+    }
+}
+
+/// Stops every track of the stream a media element was given through
+/// `srcObject` and detaches it, releasing cameras and microphones. A Blob's
+/// object URL is revoked.
+pub fn release_src_object(node: &HTMLElement) {
+    let media = match node.dyn_ref::<HtmlMediaElement>() {
+        Some(media) => media,
+        None => return,
+    };
+
+    revoke_object_url(media);
+
+    if let Some(stream) = media.src_object() {
+        for track in stream.get_tracks().iter() {
+            track.unchecked_into::<MediaStreamTrack>().stop();
+        }
+
+        media.set_src_object(None);
+    }
+}
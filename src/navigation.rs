@@ -0,0 +1,47 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as HTMLElement, Document};
+use super::announcer::{Announcer, Politeness};
+
+/// Focus move requested by a router for the navigation being rendered. It is
+/// applied after the commit so the new page content is in the document.
+pub struct NavigationFocus {
+    target: JsValue,
+    scroll_y: f64,
+    announcement: Option<String>,
+}
+
+impl NavigationFocus {
+    pub fn new(target: JsValue, scroll_y: Option<f64>, announcement: Option<String>) -> Self {
+        NavigationFocus {
+            target,
+            scroll_y: scroll_y.unwrap_or(0.0),
+            announcement,
+        }
+    }
+
+    pub fn apply(self, document: &Document, container: Option<&HTMLElement>, announcer: &Rc<RefCell<Announcer>>) {
+        let target = match self.target.as_string() {
+            Some(selector) => document.query_selector(&selector).ok().flatten(),
+            None => self.target.dyn_into::<HTMLElement>().ok(),
+        };
+
+        if let Some(target) = target.as_ref().or(container).and_then(|target| target.dyn_ref::<web_sys::HtmlElement>()) {
+            // Landmarks like <main> aren't focusable on their own
+            if target.tab_index() < 0 && !target.has_attribute("tabindex") {
+                target.set_tab_index(-1);
+            }
+
+            // A target that can't take focus, like a detached node, leaves it
+            // where it is
+            let _ = target.focus();
+        }
+
+        web_sys::window().unwrap().scroll_to_with_x_and_y(0.0, self.scroll_y);
+
+        let announcement = self.announcement.unwrap_or_else(|| document.title());
+        Announcer::announce_once(announcer, announcement, Politeness::Polite);
+    }
+}
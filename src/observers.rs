@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{Element as HTMLElement, IntersectionObserver, ResizeObserver};
+
+type HandlerSlot = Rc<RefCell<js_sys::Function>>;
+
+/// The `onResize` and `onIntersect` observers of a mounted element. Each one
+/// is created the first time its prop shows up and forwards to the handler
+/// of the latest commit, so a new handler on every render doesn't reconnect.
+pub struct ElementObservers {
+    node: HTMLElement,
+    resize: Option<(ResizeObserver, HandlerSlot)>,
+    intersect: Option<(IntersectionObserver, HandlerSlot)>,
+}
+
+impl ElementObservers {
+    pub fn new(node: HTMLElement) -> Self {
+        ElementObservers { node, resize: None, intersect: None }
+    }
+
+    pub fn set_on_resize(&mut self, handler: Option<&js_sys::Function>) -> Result<(), JsValue> {
+        match (&self.resize, handler) {
+            (Some((_, slot)), Some(handler)) => {
+                slot.replace(handler.clone());
+            },
+            (None, Some(handler)) => {
+                let slot = Rc::new(RefCell::new(handler.clone()));
+                let observer = ResizeObserver::new(&observer_callback(Rc::clone(&slot)))?;
+                observer.observe(&self.node);
+
+                self.resize = Some((observer, slot));
+            },
+            (Some(_), None) => {
+                if let Some((observer, _)) = self.resize.take() {
+                    observer.disconnect();
+                }
+            },
+            (None, None) => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn set_on_intersect(&mut self, handler: Option<&js_sys::Function>) -> Result<(), JsValue> {
+        match (&self.intersect, handler) {
+            (Some((_, slot)), Some(handler)) => {
+                slot.replace(handler.clone());
+            },
+            (None, Some(handler)) => {
+                let slot = Rc::new(RefCell::new(handler.clone()));
+                let observer = IntersectionObserver::new(&observer_callback(Rc::clone(&slot)))?;
+                observer.observe(&self.node);
+
+                self.intersect = Some((observer, slot));
+            },
+            (Some(_), None) => {
+                if let Some((observer, _)) = self.intersect.take() {
+                    observer.disconnect();
+                }
+            },
+            (None, None) => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.resize.is_none() && self.intersect.is_none()
+    }
+
+    pub fn disconnect(self) {
+        if let Some((observer, _)) = self.resize {
+            observer.disconnect();
+        }
+
+        if let Some((observer, _)) = self.intersect {
+            observer.disconnect();
+        }
+    }
+}
+
+// Both observers watch a single element, so only its latest entry matters
+fn observer_callback(slot: HandlerSlot) -> js_sys::Function {
+    Closure::wrap(Box::new(move |entries: js_sys::Array| {
+        let entry = entries.get(entries.length().saturating_sub(1));
+        let handler = slot.borrow().clone();
+
+        if let Err(error) = handler.call1(&JsValue::null(), &entry) {
+            web_sys::console::error_2(&JsValue::from_str("Reactron: an observer handler threw an error"), &error);
+        }
+    }) as Box<dyn FnMut(js_sys::Array)>).into_js_value().unchecked_into()
+}
@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+use wasm_bindgen::JsCast;
+use web_sys::Element as HTMLElement;
+use super::{RECYCLE_POOL_LIMIT, UNRECYCLABLE_ELEMENTS};
+
+/// Detached DOM elements kept around after a deletion so later placements of
+/// the same element type can reuse them instead of calling `createElement`.
+pub struct NodePool {
+    nodes: HashMap<String, Vec<HTMLElement>>,
+}
+
+impl NodePool {
+    pub fn new() -> Self {
+        NodePool {
+            nodes: HashMap::new(),
+        }
+    }
+
+    pub fn park(&mut self, element_type: &str, node: HTMLElement) {
+        if UNRECYCLABLE_ELEMENTS.contains(&element_type.to_ascii_lowercase().as_str()) {
+            return;
+        }
+
+        let nodes = self.nodes
+            .entry(String::from(element_type))
+            .or_default();
+
+        if nodes.len() < RECYCLE_POOL_LIMIT {
+            nodes.push(node);
+        }
+    }
+
+    /// Takes a pooled node of the given type, stripped of every attribute,
+    /// child, scroll offset and focus it had in its previous life.
+    pub fn take(&mut self, element_type: &str) -> Option<HTMLElement> {
+        let node = self.nodes.get_mut(element_type)?.pop()?;
+
+        let attribute_names = node.get_attribute_names();
+        for name in attribute_names.iter() {
+            if let Some(name) = name.as_string() {
+                node.remove_attribute(&name).unwrap();
+            }
+        }
+
+        node.set_text_content(None);
+        node.set_scroll_top(0);
+        node.set_scroll_left(0);
+
+        if let Some(node) = node.dyn_ref::<web_sys::HtmlElement>() {
+            node.blur().unwrap();
+        }
+
+        Some(node)
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+    }
+}
@@ -0,0 +1,118 @@
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+use super::PREFETCH_CONCURRENCY;
+
+/// Resources components asked to prefetch, fetched at idle time through
+/// `<link rel="prefetch">` a few at a time. Each URL is only fetched once per
+/// Context, and nothing is fetched when the user enabled Save-Data.
+pub struct PrefetchQueue {
+    document: Document,
+    requested: HashSet<String>,
+    queue: VecDeque<(String, Option<String>)>,
+    in_flight: usize,
+    idle_callback_scheduled: bool,
+}
+
+impl PrefetchQueue {
+    pub fn new(document: Document) -> Self {
+        PrefetchQueue {
+            document,
+            requested: HashSet::new(),
+            queue: VecDeque::new(),
+            in_flight: 0,
+            idle_callback_scheduled: false,
+        }
+    }
+
+    pub fn enqueue(this: &Rc<RefCell<Self>>, url: String, destination: Option<String>) {
+        let mut prefetch_queue = this.borrow_mut();
+
+        if prefetch_queue.requested.contains(&url) || save_data() {
+            return;
+        }
+
+        prefetch_queue.requested.insert(url.clone());
+        prefetch_queue.queue.push_back((url, destination));
+        prefetch_queue.schedule(this);
+    }
+
+    fn schedule(&mut self, this: &Rc<RefCell<Self>>) {
+        if self.idle_callback_scheduled || self.queue.is_empty() || self.in_flight >= PREFETCH_CONCURRENCY {
+            return;
+        }
+
+        let weak_queue = Rc::downgrade(this);
+        let callback: js_sys::Function = Closure::once_into_js(move || {
+            PrefetchQueue::start_next(&weak_queue);
+        }).unchecked_into();
+
+        self.idle_callback_scheduled = web_sys::window().unwrap().request_idle_callback(&callback).is_ok();
+    }
+
+    fn start_next(this: &Weak<RefCell<Self>>) {
+        let this = match this.upgrade() {
+            Some(this) => this,
+            None => return,
+        };
+        let mut prefetch_queue = this.borrow_mut();
+        prefetch_queue.idle_callback_scheduled = false;
+
+        while prefetch_queue.in_flight < PREFETCH_CONCURRENCY {
+            let (url, destination) = match prefetch_queue.queue.pop_front() {
+                Some(entry) => entry,
+                None => break,
+            };
+
+            if prefetch_queue.append_link(&this, &url, destination.as_deref()).is_ok() {
+                prefetch_queue.in_flight += 1;
+            }
+        }
+    }
+
+    fn append_link(&self, this: &Rc<RefCell<Self>>, url: &str, destination: Option<&str>) -> Result<(), JsValue> {
+        let link = self.document.create_element("link")?;
+        link.set_attribute("rel", "prefetch")?;
+        link.set_attribute("href", url)?;
+
+        if let Some(destination) = destination {
+            link.set_attribute("as", destination)?;
+
+            // Fonts are always fetched in CORS mode
+            if destination == "font" {
+                link.set_attribute("crossorigin", "")?;
+            }
+        }
+
+        let weak_queue = Rc::downgrade(this);
+        let on_settled = Closure::once_into_js(move || {
+            if let Some(this) = weak_queue.upgrade() {
+                let mut prefetch_queue = this.borrow_mut();
+                prefetch_queue.in_flight = prefetch_queue.in_flight.saturating_sub(1);
+                prefetch_queue.schedule(&this);
+            }
+        });
+
+        // Only one of the two fires, and `once_into_js` frees the closure then
+        let on_settled: &js_sys::Function = on_settled.unchecked_ref();
+        link.add_event_listener_with_callback("load", on_settled)?;
+        link.add_event_listener_with_callback("error", on_settled)?;
+
+        match self.document.head() {
+            Some(head) => head.append_child(&link).map(|_| ()),
+            None => Err(JsValue::from_str("Reactron: no document head to prefetch into")),
+        }
+    }
+}
+
+fn save_data() -> bool {
+    let navigator = web_sys::window().unwrap().navigator();
+
+    js_sys::Reflect::get(&navigator, &"connection".into()).ok()
+        .filter(|connection| connection.is_object())
+        .and_then(|connection| js_sys::Reflect::get(&connection, &"saveData".into()).ok())
+        .is_some_and(|save_data| save_data.is_truthy())
+}
@@ -0,0 +1,12 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Calls `promise.then(on_resolve, on_reject)` through the `then` the promise
+/// itself has rather than `js_sys::Promise::then`, so callbacks made with
+/// `Closure::once_into_js` free themselves once called. Pass `undefined` for
+/// a side to leave it out. Fails on values without a callable `then`.
+pub fn then(promise: &JsValue, on_resolve: &JsValue, on_reject: &JsValue) -> Result<(), JsValue> {
+    let then: js_sys::Function = js_sys::Reflect::get(promise, &"then".into())?.dyn_into()?;
+
+    then.call2(promise, on_resolve, on_reject).map(|_| ())
+}
@@ -0,0 +1,425 @@
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use super::{timers, FRAME_BUDGET_MS, FRAME_STATS_SMOOTHING};
+
+// Runs one pass with the time its deadline has left, if it has one, and
+// whether it came from an idle callback
+type Pass = Rc<dyn Fn(Option<f64>, bool)>;
+
+/// How the scheduler gets the next pass
+#[derive(Clone)]
+pub enum SchedulerBackend {
+    // Idle periods through `requestIdleCallback`, with urgent passes and
+    // browsers without idle callbacks falling back to a `MessageChannel`
+    Idle,
+    // Right after the current task, through a `MessageChannel`
+    MessageChannel,
+    // A zero delay `setTimeout`, for hosts without either
+    Timeout,
+    // A host function called with a continuation and whether the pass is
+    // urgent. The pass runs when the host calls the continuation, with the
+    // milliseconds it may take or nothing for the frame budget, so tests
+    // and embedders decide exactly when work happens.
+    Callback(js_sys::Function),
+}
+
+/// Where a requested pass comes from
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum PassSource {
+    IdleCallback,
+    Message,
+    Timeout,
+    Callback,
+}
+
+/// Scheduling APIs the host has, looked up when the scheduler starts
+#[derive(Clone, Copy, Default)]
+struct HostApis {
+    idle_callback: bool,
+    message_channel: bool,
+}
+
+/// Options a Context is created with
+#[derive(Clone)]
+pub struct SchedulerOptions {
+    pub backend: SchedulerBackend,
+    pub frame_budget: f64,
+}
+
+impl Default for SchedulerOptions {
+    fn default() -> Self {
+        SchedulerOptions::new(SchedulerBackend::Idle, None)
+    }
+}
+
+impl SchedulerBackend {
+    /// Backend called `name`: "idle" (the default), "message-channel" or
+    /// "timeout"
+    pub fn from_name(name: Option<&str>) -> Result<Self, String> {
+        match name {
+            None | Some("idle") => Ok(SchedulerBackend::Idle),
+            Some("message-channel") => Ok(SchedulerBackend::MessageChannel),
+            Some("timeout") => Ok(SchedulerBackend::Timeout),
+            Some(name) => Err(format!("Reactron: unknown scheduler backend '{}'", name)),
+        }
+    }
+
+    // Idle periods unless the pass is urgent or the host has no idle
+    // callbacks, then a message unless it has no `MessageChannel`, then a
+    // timeout, which every host has
+    fn pass_source(&self, urgent: bool, host: HostApis) -> PassSource {
+        match self {
+            SchedulerBackend::Callback(_) => PassSource::Callback,
+            SchedulerBackend::Idle if !urgent && host.idle_callback => PassSource::IdleCallback,
+            SchedulerBackend::Idle | SchedulerBackend::MessageChannel if host.message_channel => PassSource::Message,
+            SchedulerBackend::Idle | SchedulerBackend::MessageChannel | SchedulerBackend::Timeout => PassSource::Timeout,
+        }
+    }
+
+    /// The name `from_name` takes, "callback" for a host function
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchedulerBackend::Idle => "idle",
+            SchedulerBackend::MessageChannel => "message-channel",
+            SchedulerBackend::Timeout => "timeout",
+            SchedulerBackend::Callback(_) => "callback",
+        }
+    }
+}
+
+impl SchedulerOptions {
+    pub fn new(backend: SchedulerBackend, frame_budget: Option<f64>) -> Self {
+        SchedulerOptions {
+            backend,
+            frame_budget: frame_budget.unwrap_or(FRAME_BUDGET_MS),
+        }
+    }
+
+    /// `{ backend, frameBudget }`, `backend` being the name of a backend or
+    /// a function to call for each pass. Null or undefined keeps the
+    /// defaults.
+    pub fn from_js(options: &JsValue) -> Result<Self, JsValue> {
+        if !options.is_object() {
+            return Ok(SchedulerOptions::default());
+        }
+
+        let backend = match js_sys::Reflect::get(options, &"backend".into())?.dyn_into::<js_sys::Function>() {
+            Ok(callback) => SchedulerBackend::Callback(callback),
+            Err(backend) => SchedulerBackend::from_name(backend.as_string().as_deref())
+                .map_err(|message| js_sys::Error::new(&message))?,
+        };
+        let frame_budget = js_sys::Reflect::get(options, &"frameBudget".into())?.as_f64();
+
+        Ok(SchedulerOptions::new(backend, frame_budget))
+    }
+}
+
+/// Drives the work loop from inside the module once started, so the host
+/// doesn't need an idle callback loop of its own. Passes come from the
+/// backend picked when the Context was created, and each one stops after
+/// the frame budget.
+pub struct Scheduler {
+    pass: Option<Pass>,
+    backend: SchedulerBackend,
+    host: HostApis,
+    channel: Option<web_sys::MessageChannel>,
+    frame_budget: f64,
+    idle_requested: bool,
+    message_requested: bool,
+}
+
+impl Scheduler {
+    pub fn new(options: SchedulerOptions) -> Self {
+        Scheduler {
+            pass: None,
+            backend: options.backend,
+            host: HostApis::default(),
+            channel: None,
+            frame_budget: options.frame_budget,
+            idle_requested: false,
+            message_requested: false,
+        }
+    }
+
+    pub fn start(&mut self, pass: impl Fn(Option<f64>, bool) + 'static) -> Result<(), JsValue> {
+        if self.pass.is_some() {
+            return Ok(());
+        }
+
+        let pass: Pass = Rc::new(pass);
+        let global = js_sys::global();
+
+        self.host.idle_callback = js_sys::Reflect::has(&global, &"requestIdleCallback".into()).unwrap_or(false);
+
+        if matches!(self.backend, SchedulerBackend::Idle | SchedulerBackend::MessageChannel) {
+            if let Ok(channel) = web_sys::MessageChannel::new() {
+                let on_message: js_sys::Function = {
+                    let pass = Rc::clone(&pass);
+                    Closure::wrap(Box::new(move || pass(None, false)) as Box<dyn FnMut()>).into_js_value().unchecked_into()
+                };
+                channel.port1().set_onmessage(Some(&on_message));
+
+                self.channel = Some(channel);
+            }
+        }
+
+        self.host.message_channel = self.channel.is_some();
+        self.pass = Some(pass);
+
+        Ok(())
+    }
+
+    pub fn backend(&self) -> &SchedulerBackend {
+        &self.backend
+    }
+
+    pub fn is_started(&self) -> bool {
+        self.pass.is_some()
+    }
+
+    pub fn frame_budget(&self) -> f64 {
+        self.frame_budget
+    }
+
+    pub fn set_frame_budget(&mut self, frame_budget: f64) {
+        self.frame_budget = frame_budget;
+    }
+
+    /// Milliseconds a pass may take: what its idle deadline has left, if it
+    /// has one, and never more than the frame budget
+    pub fn pass_budget(&self, time_remaining: Option<f64>) -> f64 {
+        time_remaining.map_or(self.frame_budget, |time_remaining| time_remaining.min(self.frame_budget))
+    }
+
+    /// Called at the start of a pass, so updates it schedules request the
+    /// next one
+    pub fn pass_started(&mut self, from_idle_callback: bool) {
+        if from_idle_callback {
+            self.idle_requested = false;
+        } else {
+            self.message_requested = false;
+        }
+    }
+
+    /// Requests a pass unless one is on its way. An urgent pass doesn't wait
+    /// for an idle period requested earlier.
+    pub fn request(&mut self, urgent: bool) {
+        let pass = match self.pass.as_ref() {
+            Some(pass) => Rc::clone(pass),
+            None => return,
+        };
+
+        if self.message_requested || (self.idle_requested && !urgent) {
+            return;
+        }
+
+        match self.backend.pass_source(urgent, self.host) {
+            PassSource::IdleCallback => {
+                let callback: js_sys::Function = Closure::once_into_js(move |deadline: web_sys::IdleDeadline| {
+                    pass(Some(deadline.time_remaining()), true);
+                }).unchecked_into();
+
+                self.idle_requested = web_sys::window().unwrap().request_idle_callback(&callback).is_ok();
+                if !self.idle_requested {
+                    self.post_message();
+                }
+            },
+            PassSource::Message => self.post_message(),
+            PassSource::Timeout => {
+                timers::set_timeout(0, move || pass(None, false));
+                self.message_requested = true;
+            },
+            PassSource::Callback => {
+                let callback = match &self.backend {
+                    SchedulerBackend::Callback(callback) => callback,
+                    _ => return,
+                };
+                let continuation = Closure::once_into_js(move |time_remaining: Option<f64>| pass(time_remaining, false));
+
+                self.message_requested = true;
+                if let Err(error) = callback.call2(&JsValue::NULL, &continuation, &urgent.into()) {
+                    self.message_requested = false;
+                    web_sys::console::error_2(&"Reactron: scheduler backend failed".into(), &error);
+                }
+            },
+        }
+    }
+
+    fn post_message(&mut self) {
+        if let Some(channel) = self.channel.as_ref() {
+            self.message_requested = channel.port2().post_message(&JsValue::NULL).is_ok();
+        }
+    }
+}
+
+/// Rolling measurements of the work loop. The cost of a unit of work lets a
+/// pass stop before a slow component would run it past its deadline, and
+/// the share of their budget passes use shows how responsive the app is.
+#[derive(Default)]
+pub struct FrameStats {
+    unit_estimate: Option<f64>,
+    utilization: f64,
+    passes: u32,
+    overruns: u32,
+    pass_units: u32,
+}
+
+impl FrameStats {
+    pub fn pass_started(&mut self) {
+        self.pass_units = 0;
+    }
+
+    pub fn record_unit(&mut self, duration: f64) {
+        self.unit_estimate = Some(self.unit_estimate.map_or(duration, |estimate| smooth(estimate, duration)));
+        self.pass_units += 1;
+    }
+
+    /// Whether another unit of work, taking as long as the estimate, still
+    /// finishes by `deadline`
+    pub fn fits(&self, now: f64, deadline: f64) -> bool {
+        now + self.unit_estimate.unwrap_or(0.0) < deadline
+    }
+
+    /// Counts a pass that got `budget` milliseconds and took `used`. Passes
+    /// without a budget, or that found no work, don't say anything about
+    /// responsiveness and are left out.
+    pub fn pass_finished(&mut self, budget: f64, used: f64) {
+        if !budget.is_finite() || budget <= 0.0 || self.pass_units == 0 {
+            return;
+        }
+
+        let utilization = used / budget;
+        self.utilization = match self.passes {
+            0 => utilization,
+            _ => smooth(self.utilization, utilization),
+        };
+        self.passes += 1;
+        self.overruns += (used > budget) as u32;
+    }
+
+    /// `{ unitEstimate, utilization, passes, overruns }`, utilization being
+    /// the share of its budget a pass uses, above 1 when it overran
+    pub fn to_js(&self) -> JsValue {
+        let stats = js_sys::Object::new();
+
+        let _ = js_sys::Reflect::set(&stats, &"unitEstimate".into(), &self.unit_estimate.unwrap_or(0.0).into());
+        let _ = js_sys::Reflect::set(&stats, &"utilization".into(), &self.utilization.into());
+        let _ = js_sys::Reflect::set(&stats, &"passes".into(), &self.passes.into());
+        let _ = js_sys::Reflect::set(&stats, &"overruns".into(), &self.overruns.into());
+
+        stats.into()
+    }
+}
+
+fn smooth(estimate: f64, measurement: f64) -> f64 {
+    estimate + (measurement - estimate) * FRAME_STATS_SMOOTHING
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backends_by_name() {
+        assert!(matches!(SchedulerBackend::from_name(Some("idle")), Ok(SchedulerBackend::Idle)));
+        assert!(matches!(SchedulerBackend::from_name(Some("message-channel")), Ok(SchedulerBackend::MessageChannel)));
+        assert!(matches!(SchedulerBackend::from_name(Some("timeout")), Ok(SchedulerBackend::Timeout)));
+        assert_eq!(SchedulerBackend::from_name(Some("raf")).err().as_deref(), Some("Reactron: unknown scheduler backend 'raf'"));
+
+        for name in ["idle", "message-channel", "timeout"] {
+            assert_eq!(SchedulerBackend::from_name(Some(name)).map(|backend| backend.name()), Ok(name));
+        }
+    }
+
+    #[test]
+    fn defaults_to_idle_backend_and_frame_budget() {
+        assert!(matches!(SchedulerBackend::from_name(None), Ok(SchedulerBackend::Idle)));
+
+        let options = SchedulerOptions::default();
+        assert!(matches!(options.backend, SchedulerBackend::Idle));
+        assert_eq!(options.frame_budget, FRAME_BUDGET_MS);
+    }
+
+    #[test]
+    fn scheduler_takes_its_options() {
+        let scheduler = Scheduler::new(SchedulerOptions::new(SchedulerBackend::Timeout, Some(12.0)));
+
+        assert!(matches!(scheduler.backend, SchedulerBackend::Timeout));
+        assert_eq!(scheduler.frame_budget(), 12.0);
+        assert!(!scheduler.is_started());
+    }
+
+    const BROWSER: HostApis = HostApis { idle_callback: true, message_channel: true };
+    const NO_IDLE_CALLBACK: HostApis = HostApis { idle_callback: false, message_channel: true };
+    const NEITHER: HostApis = HostApis { idle_callback: false, message_channel: false };
+
+    #[test]
+    fn idle_backend_waits_for_idle_periods_unless_urgent() {
+        assert_eq!(SchedulerBackend::Idle.pass_source(false, BROWSER), PassSource::IdleCallback);
+        assert_eq!(SchedulerBackend::Idle.pass_source(true, BROWSER), PassSource::Message);
+    }
+
+    #[test]
+    fn idle_backend_falls_back_to_messages_then_timeouts() {
+        assert_eq!(SchedulerBackend::Idle.pass_source(false, NO_IDLE_CALLBACK), PassSource::Message);
+        assert_eq!(SchedulerBackend::Idle.pass_source(false, NEITHER), PassSource::Timeout);
+        assert_eq!(SchedulerBackend::Idle.pass_source(true, NEITHER), PassSource::Timeout);
+    }
+
+    #[test]
+    fn message_channel_backend_falls_back_to_timeouts() {
+        for urgent in [false, true] {
+            assert_eq!(SchedulerBackend::MessageChannel.pass_source(urgent, BROWSER), PassSource::Message);
+            assert_eq!(SchedulerBackend::MessageChannel.pass_source(urgent, NEITHER), PassSource::Timeout);
+        }
+    }
+
+    #[test]
+    fn timeout_backend_ignores_the_host() {
+        for host in [BROWSER, NO_IDLE_CALLBACK, NEITHER] {
+            assert_eq!(SchedulerBackend::Timeout.pass_source(false, host), PassSource::Timeout);
+            assert_eq!(SchedulerBackend::Timeout.pass_source(true, host), PassSource::Timeout);
+        }
+    }
+
+    #[test]
+    fn pass_budget_is_capped_by_frame_budget() {
+        let scheduler = Scheduler::new(SchedulerOptions::new(SchedulerBackend::Idle, Some(5.0)));
+
+        assert_eq!(scheduler.pass_budget(None), 5.0);
+        assert_eq!(scheduler.pass_budget(Some(50.0)), 5.0);
+        assert_eq!(scheduler.pass_budget(Some(2.0)), 2.0);
+    }
+
+    // Runs 1.5ms units the way the work loop does, until the next one
+    // wouldn't fit, and returns how long the pass took
+    fn run_pass(stats: &mut FrameStats, budget: f64) -> f64 {
+        let deadline = budget;
+        let mut now = 0.0;
+
+        stats.pass_started();
+        while stats.fits(now, deadline) {
+            now += 1.5;
+            stats.record_unit(1.5);
+        }
+        stats.pass_finished(budget, now);
+
+        now
+    }
+
+    #[test]
+    fn passes_stay_within_their_budget() {
+        let scheduler = Scheduler::new(SchedulerOptions::new(SchedulerBackend::Idle, Some(5.0)));
+        let mut stats = FrameStats::default();
+
+        // The first unit has no estimate and may overrun; later passes don't
+        run_pass(&mut stats, scheduler.pass_budget(Some(50.0)));
+        for time_remaining in [Some(50.0), None, Some(3.0)] {
+            let budget = scheduler.pass_budget(time_remaining);
+            assert!(run_pass(&mut stats, budget) <= budget);
+        }
+        assert_eq!(stats.passes, 4);
+        assert_eq!(stats.overruns, 0);
+    }
+}
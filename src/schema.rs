@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use super::{Element, element, url_policy, SCHEMA_BLOCKED_ELEMENTS, SCHEMA_BLOCKED_PROPS};
+
+/// Components a UI schema may refer to by name
+pub struct ComponentRegistry {
+    components: HashMap<String, js_sys::Function>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        ComponentRegistry { components: HashMap::new() }
+    }
+
+    pub fn register(&mut self, name: String, component: js_sys::Function) {
+        self.components.insert(name, component);
+    }
+
+    /// Builds the element tree described by `schema`. A node is a string or
+    /// number for text, or `{ type, props, children, bind }` where `type` is
+    /// a registered component or an HTML tag and `bind` maps prop names to
+    /// dot separated paths into `data`. Event handler props, script-capable
+    /// elements and `javascript:` URLs are refused, since the schema may come
+    /// from a server.
+    pub fn build(&self, schema: &JsValue, data: &JsValue) -> Result<*mut Element, JsValue> {
+        self.build_node(schema, data, "root")
+    }
+
+    fn build_node(&self, node: &JsValue, data: &JsValue, path: &str) -> Result<*mut Element, JsValue> {
+        if let Some(text) = node.as_string() {
+            return Ok(element::create_text_element(text));
+        }
+
+        if let Some(number) = node.as_f64() {
+            return Ok(element::create_text_element(number.to_string()));
+        }
+
+        if !node.is_object() {
+            return Err(schema_error(path, "expected a string, a number or an object"));
+        }
+
+        let element_type = js_sys::Reflect::get(node, &"type".into())?.as_string()
+            .ok_or_else(|| schema_error(path, "missing 'type'"))?;
+        let path = format!("{} > {}", path, element_type);
+
+        let props = self.build_props(node, data, &path)?;
+        let children = self.build_children(node, data, &path)?;
+
+        if let Some(component) = self.components.get(&element_type) {
+            let children_array: js_sys::Array = children.iter().map(|ptr| JsValue::from(*ptr)).collect();
+            js_sys::Reflect::set(&props, &"children".into(), &children_array)?;
+
+            return Ok(element::create_functional_component(component.clone(), props.into()));
+        }
+
+        let is_tag_name = !element_type.is_empty() &&
+            element_type.starts_with(|c: char| c.is_ascii_lowercase()) &&
+            element_type.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+        if !is_tag_name || SCHEMA_BLOCKED_ELEMENTS.contains(&element_type.as_str()) {
+            free_elements(&children);
+            return Err(schema_error(&path, "unknown or disallowed element type"));
+        }
+
+        let props_ptr = element::create_props_from_object(&props, Some(element_type.clone()));
+
+        Ok(element::create_element(element_type, props_ptr, &children))
+    }
+
+    fn build_props(&self, node: &JsValue, data: &JsValue, path: &str) -> Result<js_sys::Object, JsValue> {
+        let props = js_sys::Object::new();
+        let raw_props = js_sys::Reflect::get(node, &"props".into())?;
+        let bindings = js_sys::Reflect::get(node, &"bind".into())?;
+
+        let mut entries: Vec<(String, JsValue)> = Vec::new();
+
+        if raw_props.is_object() {
+            entries.extend(object_entries(&raw_props));
+        }
+
+        if bindings.is_object() {
+            for (name, data_path) in object_entries(&bindings) {
+                let data_path = data_path.as_string()
+                    .ok_or_else(|| schema_error(path, &format!("binding of '{}' isn't a path", name)))?;
+
+                entries.push((name, lookup(data, &data_path)));
+            }
+        }
+
+        for (name, value) in entries {
+            if name.starts_with("on") || SCHEMA_BLOCKED_PROPS.contains(&name.as_str()) {
+                return Err(schema_error(path, &format!("disallowed prop '{}'", name)));
+            }
+
+            let is_script_url = url_policy::is_url_attribute(&name) &&
+                value.as_string().is_some_and(|url| url_policy::has_javascript_url(&name, &url));
+
+            if is_script_url {
+                return Err(schema_error(path, &format!("javascript: URL in '{}'", name)));
+            }
+
+            js_sys::Reflect::set(&props, &name.into(), &value)?;
+        }
+
+        Ok(props)
+    }
+
+    fn build_children(&self, node: &JsValue, data: &JsValue, path: &str) -> Result<Vec<u32>, JsValue> {
+        let children = js_sys::Reflect::get(node, &"children".into())?;
+
+        let children: Vec<JsValue> = match children.dyn_into::<js_sys::Array>() {
+            Ok(children) => children.iter().collect(),
+            Err(child) if child.is_undefined() || child.is_null() => Vec::new(),
+            Err(child) => vec![child],
+        };
+
+        let mut built = Vec::with_capacity(children.len());
+
+        for (index, child) in children.iter().enumerate() {
+            match self.build_node(child, data, &format!("{}[{}]", path, index)) {
+                Ok(ptr) => built.push(ptr as u32),
+                Err(error) => {
+                    free_elements(&built);
+                    return Err(error);
+                },
+            }
+        }
+
+        Ok(built)
+    }
+}
+
+fn object_entries(value: &JsValue) -> Vec<(String, JsValue)> {
+    js_sys::Object::entries(value.unchecked_ref()).iter()
+        .filter_map(|entry| {
+            let entry: js_sys::Array = entry.unchecked_into();
+            Some((entry.get(0).as_string()?, entry.get(1)))
+        })
+        .collect()
+}
+
+// `user.address.city`; undefined as soon as a step is missing
+fn lookup(data: &JsValue, path: &str) -> JsValue {
+    path.split('.').try_fold(data.clone(), |value, key| {
+        if value.is_object() {
+            js_sys::Reflect::get(&value, &key.into()).ok()
+        } else {
+            None
+        }
+    }).unwrap_or(JsValue::UNDEFINED)
+}
+
+fn free_elements(ptrs: &[u32]) {
+    for ptr in ptrs {
+        drop(Element::from_ptr(*ptr as *mut Element));
+    }
+}
+
+fn schema_error(path: &str, message: &str) -> JsValue {
+    js_sys::Error::new(&format!("Reactron: invalid UI schema at {}: {}", path, message)).into()
+}
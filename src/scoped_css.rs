@@ -0,0 +1,190 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use wasm_bindgen::prelude::*;
+use web_sys::{Document, Element as HTMLElement};
+
+/// Stylesheets registered through `css`, keyed by scope. Each one is parsed
+/// once; its rewritten text waits for the next commit to be written into the
+/// Context's `<style>` element.
+pub struct StyleRegistry {
+    document: Document,
+    sheets: HashMap<String, js_sys::Object>,
+    pending: Vec<String>,
+    style_element: Option<HTMLElement>,
+}
+
+impl StyleRegistry {
+    pub fn new(document: Document) -> Self {
+        StyleRegistry {
+            document,
+            sheets: HashMap::new(),
+            pending: Vec::new(),
+            style_element: None,
+        }
+    }
+
+    /// Registers `source` for the component called `name` and returns the
+    /// map from its class names to their scoped names. Registering the same
+    /// stylesheet again hands back the first map.
+    pub fn register(&mut self, name: &str, source: &str) -> js_sys::Object {
+        let scope = scope_of(name, source);
+
+        if let Some(class_map) = self.sheets.get(&scope) {
+            return class_map.clone();
+        }
+
+        let (scoped_source, class_names) = scope_class_names(source, &scope);
+
+        let class_map = js_sys::Object::new();
+        for (class_name, scoped_name) in class_names {
+            js_sys::Reflect::set(&class_map, &class_name.into(), &scoped_name.into()).unwrap();
+        }
+
+        self.pending.push(scoped_source);
+        self.sheets.insert(scope, class_map.clone());
+
+        class_map
+    }
+
+    /// Writes the stylesheets registered since the last commit. `root` is
+    /// the shadow root the tree renders into, if any, so its styles apply
+    /// inside it.
+    pub fn flush(&mut self, root: Option<&web_sys::ShadowRoot>) -> Result<(), JsValue> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+
+        let style_element = match self.style_element.as_ref() {
+            Some(style_element) => style_element.clone(),
+            None => {
+                let style_element = self.document.create_element("style")?;
+                style_element.set_attribute("data-fusion-scoped", "")?;
+
+                match (root, self.document.head()) {
+                    (Some(root), _) => root.append_child(&style_element)?,
+                    (None, Some(head)) => head.append_child(&style_element)?,
+                    (None, None) => return Err(JsValue::from_str("Reactron: no document head to add styles to")),
+                };
+
+                self.style_element = Some(style_element.clone());
+                style_element
+            },
+        };
+
+        let mut text = style_element.text_content().unwrap_or_default();
+        for source in self.pending.drain(..) {
+            text.push_str(&source);
+            text.push('\n');
+        }
+        style_element.set_text_content(Some(&text));
+
+        Ok(())
+    }
+}
+
+// Component name followed by a hash of the stylesheet, `Button-3fa2c1`
+fn scope_of(name: &str, source: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    name.hash(&mut hasher);
+    source.hash(&mut hasher);
+
+    let name: String = name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect();
+
+    format!("{}-{:06x}", if name.is_empty() { "c" } else { &name }, hasher.finish() & 0xff_ffff)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_' || !c.is_ascii()
+}
+
+/// Appends `-scope` to every class selector of `source`. Declaration blocks,
+/// comments and strings are copied as they are, while the blocks of
+/// at-rules like `@media` hold rules of their own.
+fn scope_class_names(source: &str, scope: &str) -> (String, BTreeMap<String, String>) {
+    let chars: Vec<char> = source.chars().collect();
+    let mut output = String::with_capacity(source.len());
+    let mut class_names = BTreeMap::new();
+
+    // Whether each open block holds rules rather than declarations
+    let mut blocks: Vec<bool> = Vec::new();
+    let mut prelude_is_at_rule = false;
+    let mut at_prelude_start = true;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let in_rules = blocks.last().copied().unwrap_or(true);
+
+        match c {
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                let end = (i + 2..chars.len().saturating_sub(1))
+                    .find(|&j| chars[j] == '*' && chars[j + 1] == '/')
+                    .map_or(chars.len(), |j| j + 2);
+
+                output.extend(&chars[i..end]);
+                i = end;
+                continue;
+            },
+            '"' | '\'' => {
+                let mut end = i + 1;
+                while end < chars.len() && chars[end] != c {
+                    end += if chars[end] == '\\' { 2 } else { 1 };
+                }
+                let end = (end + 1).min(chars.len());
+
+                output.extend(&chars[i..end]);
+                i = end;
+                continue;
+            },
+            '{' => {
+                blocks.push(in_rules && prelude_is_at_rule);
+                at_prelude_start = true;
+                prelude_is_at_rule = false;
+            },
+            '}' => {
+                blocks.pop();
+                at_prelude_start = true;
+                prelude_is_at_rule = false;
+            },
+            ';' if in_rules => {
+                // End of a statement at-rule such as `@import`
+                at_prelude_start = true;
+                prelude_is_at_rule = false;
+            },
+            '@' if in_rules && at_prelude_start => {
+                prelude_is_at_rule = true;
+                at_prelude_start = false;
+            },
+            '.' if in_rules && !prelude_is_at_rule => {
+                let start = i + 1;
+                let starts_name = chars.get(start).is_some_and(|c| !c.is_ascii_digit() && is_name_char(*c));
+
+                if starts_name {
+                    let end = (start..chars.len()).find(|&j| !is_name_char(chars[j])).unwrap_or(chars.len());
+                    let class_name: String = chars[start..end].iter().collect();
+                    let scoped_name = format!("{}-{}", class_name, scope);
+
+                    output.push('.');
+                    output.push_str(&scoped_name);
+                    class_names.insert(class_name, scoped_name);
+
+                    at_prelude_start = false;
+                    i = end;
+                    continue;
+                }
+            },
+            c if !c.is_whitespace() && in_rules => {
+                at_prelude_start = false;
+            },
+            _ => {}
+        }
+
+        output.push(c);
+        i += 1;
+    }
+
+    (output, class_names)
+}
@@ -0,0 +1,85 @@
+use std::collections::BTreeMap;
+use wasm_bindgen::JsCast;
+use web_sys::Element as HTMLElement;
+use super::{direction, Direction, UNITLESS_STYLE_PROPERTIES};
+
+/// CSS declarations of a `style={{...}}` prop, keyed by kebab-case property
+/// name. Numbers get a `px` suffix unless the property is unitless; null,
+/// undefined, false and empty values are left out.
+pub fn parse_style(style: &js_sys::Object) -> BTreeMap<String, String> {
+    let mut declarations = BTreeMap::new();
+
+    for entry in js_sys::Object::entries(style).iter() {
+        let entry: js_sys::Array = entry.unchecked_into();
+        let name = match entry.get(0).as_string() {
+            Some(name) => css_property_name(&name),
+            None => continue,
+        };
+        let value = entry.get(1);
+
+        let value = match value.as_f64() {
+            Some(number) if number != 0.0 && !UNITLESS_STYLE_PROPERTIES.contains(&name.as_str()) => format!("{}px", number),
+            Some(number) => number.to_string(),
+            None => match value.as_string() {
+                Some(value) => value,
+                None => continue,
+            },
+        };
+
+        if !value.is_empty() {
+            declarations.insert(name, value);
+        }
+    }
+
+    declarations
+}
+
+// `marginTop` -> `margin-top`, `WebkitLineClamp` -> `-webkit-line-clamp`,
+// `msTransform` -> `-ms-transform`. Custom properties are kept as they are.
+fn css_property_name(name: &str) -> String {
+    if name.starts_with("--") {
+        return String::from(name);
+    }
+
+    let mut property = String::with_capacity(name.len() + 4);
+
+    if name.starts_with("ms") && name[2..].starts_with(|c: char| c.is_ascii_uppercase()) {
+        property.push('-');
+    }
+
+    for c in name.chars() {
+        if c.is_ascii_uppercase() {
+            property.push('-');
+            property.push(c.to_ascii_lowercase());
+        } else {
+            property.push(c);
+        }
+    }
+
+    property
+}
+
+/// Applies the difference between two style props with `setProperty` and
+/// `removeProperty`, leaving declarations that didn't change alone. Logical
+/// properties are written as their physical counterparts in `direction`.
+pub fn update_style(node: &HTMLElement, prev: Option<&BTreeMap<String, String>>, next: &BTreeMap<String, String>, direction: Direction) {
+    let style = match node.dyn_ref::<web_sys::HtmlElement>() {
+        Some(element) => element.style(),
+        None => match node.dyn_ref::<web_sys::SvgElement>() {
+            Some(element) => element.style(),
+            None => return,
+        },
+    };
+
+    for name in prev.into_iter().flat_map(|prev| prev.keys()) {
+        if !next.contains_key(name) {
+            let _ = style.remove_property(direction::physical_property(name, direction));
+        }
+    }
+
+    for (name, value) in next {
+        if prev.and_then(|prev| prev.get(name)) != Some(value) {
+            let _ = style.set_property(direction::physical_property(name, direction), value);
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::HtmlCanvasElement;
+
+type ContextSlot = Rc<RefCell<Option<JsValue>>>;
+type DrawSlot = Rc<RefCell<js_sys::Function>>;
+
+/// The canvas of a mounted `Surface` fiber with its rendering context. The
+/// draw callback receives `(context, canvas)` after every commit of the
+/// fiber, after every resize of the canvas and when a lost WebGL context is
+/// restored.
+pub struct Surface {
+    canvas: HtmlCanvasElement,
+    context_type: String,
+    context: ContextSlot,
+    draw: DrawSlot,
+    observer: web_sys::ResizeObserver,
+    listeners: Vec<(&'static str, js_sys::Function)>,
+}
+
+impl Surface {
+    // The context type is fixed once the canvas handed out a context
+    pub fn mount(canvas: HtmlCanvasElement, context_type: &str, draw: js_sys::Function) -> Result<Self, JsValue> {
+        let context: ContextSlot = Rc::new(RefCell::new(canvas.get_context(context_type)?.map(JsValue::from)));
+        let draw: DrawSlot = Rc::new(RefCell::new(draw));
+
+        let observer = {
+            let canvas = canvas.clone();
+            let context = Rc::clone(&context);
+            let draw = Rc::clone(&draw);
+
+            let on_resize: js_sys::Function = Closure::wrap(Box::new(move || {
+                if fit_to_layout(&canvas) {
+                    call_draw(&draw, &context, &canvas);
+                }
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into();
+
+            web_sys::ResizeObserver::new(&on_resize)?
+        };
+        observer.observe(&canvas);
+
+        let on_context_lost: js_sys::Function = {
+            let context = Rc::clone(&context);
+
+            // Cancelling the event asks the browser to restore the context later
+            Closure::wrap(Box::new(move |event: web_sys::Event| {
+                event.prevent_default();
+                context.replace(None);
+            }) as Box<dyn FnMut(web_sys::Event)>).into_js_value().unchecked_into()
+        };
+
+        let on_context_restored: js_sys::Function = {
+            let canvas = canvas.clone();
+            let context_type = String::from(context_type);
+            let context = Rc::clone(&context);
+            let draw = Rc::clone(&draw);
+
+            Closure::wrap(Box::new(move || {
+                context.replace(canvas.get_context(&context_type).ok().flatten().map(JsValue::from));
+                call_draw(&draw, &context, &canvas);
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into()
+        };
+
+        let listeners = vec![
+            ("webglcontextlost", on_context_lost),
+            ("webglcontextrestored", on_context_restored),
+        ];
+
+        for (event_type, listener) in &listeners {
+            canvas.add_event_listener_with_callback(event_type, listener)?;
+        }
+
+        fit_to_layout(&canvas);
+
+        Ok(Surface {
+            canvas,
+            context_type: String::from(context_type),
+            context,
+            draw,
+            observer,
+            listeners,
+        })
+    }
+
+    pub fn set_draw(&self, draw: js_sys::Function) {
+        self.draw.replace(draw);
+    }
+
+    pub fn draw(&self) {
+        call_draw(&self.draw, &self.context, &self.canvas);
+    }
+
+    /// Stops observing the canvas and releases a WebGL context right away
+    /// instead of waiting for the canvas to be garbage collected.
+    pub fn unmount(self) {
+        self.observer.disconnect();
+
+        for (event_type, listener) in &self.listeners {
+            let _ = self.canvas.remove_event_listener_with_callback(event_type, listener);
+        }
+
+        if let Some(context) = self.context.borrow_mut().take() {
+            if self.context_type.starts_with("webgl") {
+                let lose_context = js_sys::Reflect::get(&context, &"getExtension".into()).ok()
+                    .and_then(|get_extension| get_extension.dyn_into::<js_sys::Function>().ok())
+                    .and_then(|get_extension| get_extension.call1(&context, &"WEBGL_lose_context".into()).ok())
+                    .filter(|extension| extension.is_object());
+
+                if let Some(extension) = lose_context {
+                    let _ = js_sys::Reflect::get(&extension, &"loseContext".into()).ok()
+                        .and_then(|lose| lose.dyn_into::<js_sys::Function>().ok())
+                        .map(|lose| lose.call0(&extension));
+                }
+            }
+        }
+    }
+}
+
+// Sizes the drawing buffer to the laid out size of the canvas in device
+// pixels. Returns whether it changed.
+fn fit_to_layout(canvas: &HtmlCanvasElement) -> bool {
+    let pixel_ratio = web_sys::window().unwrap().device_pixel_ratio();
+    let rect = canvas.get_bounding_client_rect();
+
+    let width = (rect.width() * pixel_ratio).round() as u32;
+    let height = (rect.height() * pixel_ratio).round() as u32;
+
+    if width == canvas.width() && height == canvas.height() {
+        return false;
+    }
+
+    canvas.set_width(width);
+    canvas.set_height(height);
+    true
+}
+
+fn call_draw(draw: &DrawSlot, context: &ContextSlot, canvas: &HtmlCanvasElement) {
+    if let Some(context) = context.borrow().as_ref() {
+        if let Err(error) = draw.borrow().call2(&JsValue::null(), context, canvas) {
+            web_sys::console::error_2(&"Reactron: a surface draw callback threw an error".into(), &error);
+        }
+    }
+}
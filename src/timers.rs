@@ -0,0 +1,12 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Runs `callback` once after `delay` milliseconds. Returns the timeout
+/// handle for `clearTimeout`.
+pub fn set_timeout(delay: i32, callback: impl FnOnce() + 'static) -> i32 {
+    let callback: js_sys::Function = Closure::once_into_js(callback).unchecked_into();
+
+    web_sys::window().unwrap()
+        .set_timeout_with_callback_and_timeout_and_arguments_0(&callback, delay)
+        .unwrap()
+}
@@ -0,0 +1,183 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// Past and future values of a `use_undoable_state` hook around the present
+/// one. Setting a value drops the future; the past keeps at most `limit`
+/// values, the oldest going first.
+pub struct UndoHistory {
+    initial: JsValue,
+    past: VecDeque<JsValue>,
+    present: JsValue,
+    future: Vec<JsValue>,
+    limit: usize,
+}
+
+impl UndoHistory {
+    pub fn new(initial: JsValue, limit: usize) -> Self {
+        UndoHistory {
+            initial: initial.clone(),
+            past: VecDeque::new(),
+            present: initial,
+            future: Vec::new(),
+            limit,
+        }
+    }
+
+    pub fn present(&self) -> &JsValue {
+        &self.present
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.past.drain(..self.past.len().saturating_sub(limit));
+    }
+
+    fn set(&mut self, value: JsValue) {
+        let previous = std::mem::replace(&mut self.present, value);
+
+        self.past.push_back(previous);
+        if self.past.len() > self.limit {
+            self.past.pop_front();
+        }
+
+        self.future.clear();
+    }
+
+    fn undo(&mut self) -> bool {
+        match self.past.pop_back() {
+            Some(value) => {
+                let next = std::mem::replace(&mut self.present, value);
+                self.future.push(next);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn redo(&mut self) -> bool {
+        match self.future.pop() {
+            Some(value) => {
+                let previous = std::mem::replace(&mut self.present, value);
+                self.past.push_back(previous);
+                true
+            },
+            None => false,
+        }
+    }
+
+    fn reset(&mut self, value: JsValue) {
+        self.present = if value.is_undefined() { self.initial.clone() } else { value };
+        self.past.clear();
+        self.future.clear();
+    }
+}
+
+/// State of a `use_undoable_state` hook: the history and the `set`, `undo`,
+/// `redo` and `reset` functions handed to JS, which keep their identity for
+/// the whole lifetime of the component.
+#[derive(Clone)]
+pub struct UndoableState {
+    history: Rc<RefCell<UndoHistory>>,
+    set: js_sys::Function,
+    undo: js_sys::Function,
+    redo: js_sys::Function,
+    reset: js_sys::Function,
+    hotkey_ids: Vec<u32>,
+}
+
+impl UndoableState {
+    pub fn new(initial: JsValue, limit: usize, on_change: impl Fn() + 'static) -> Self {
+        let history = Rc::new(RefCell::new(UndoHistory::new(initial, limit)));
+        let on_change: Rc<dyn Fn()> = Rc::new(on_change);
+
+        let set = {
+            let history = Rc::clone(&history);
+            let on_change = Rc::clone(&on_change);
+
+            Closure::wrap(Box::new(move |value: JsValue| {
+                history.borrow_mut().set(value);
+                on_change();
+            }) as Box<dyn FnMut(JsValue)>).into_js_value().unchecked_into()
+        };
+
+        let undo = {
+            let history = Rc::clone(&history);
+            let on_change = Rc::clone(&on_change);
+
+            Closure::wrap(Box::new(move || {
+                if history.borrow_mut().undo() {
+                    on_change();
+                }
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into()
+        };
+
+        let redo = {
+            let history = Rc::clone(&history);
+            let on_change = Rc::clone(&on_change);
+
+            Closure::wrap(Box::new(move || {
+                if history.borrow_mut().redo() {
+                    on_change();
+                }
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into()
+        };
+
+        // Back to the initial value, or to the given one, with an empty history
+        let reset = {
+            let history = Rc::clone(&history);
+
+            Closure::wrap(Box::new(move |value: JsValue| {
+                history.borrow_mut().reset(value);
+                on_change();
+            }) as Box<dyn FnMut(JsValue)>).into_js_value().unchecked_into()
+        };
+
+        UndoableState { history, set, undo, redo, reset, hotkey_ids: Vec::new() }
+    }
+
+    pub fn history(&self) -> &Rc<RefCell<UndoHistory>> {
+        &self.history
+    }
+
+    pub fn undo(&self) -> &js_sys::Function {
+        &self.undo
+    }
+
+    pub fn redo(&self) -> &js_sys::Function {
+        &self.redo
+    }
+
+    /// Ids in the Context's hotkey registry of the undo/redo key bindings
+    pub fn hotkey_ids(&self) -> &[u32] {
+        &self.hotkey_ids
+    }
+
+    pub fn set_hotkey_ids(&mut self, hotkey_ids: Vec<u32>) {
+        self.hotkey_ids = hotkey_ids;
+    }
+
+    /// `[value, set, { undo, redo, reset, canUndo, canRedo }]`
+    pub fn to_js(&self) -> Box<[JsValue]> {
+        let history = self.history.borrow();
+        let controls = js_sys::Object::new();
+
+        js_sys::Reflect::set(&controls, &"undo".into(), &self.undo).unwrap();
+        js_sys::Reflect::set(&controls, &"redo".into(), &self.redo).unwrap();
+        js_sys::Reflect::set(&controls, &"reset".into(), &self.reset).unwrap();
+        js_sys::Reflect::set(&controls, &"canUndo".into(), &history.can_undo().into()).unwrap();
+        js_sys::Reflect::set(&controls, &"canRedo".into(), &history.can_redo().into()).unwrap();
+
+        vec![history.present().clone(), self.set.clone().into(), controls.into()].into_boxed_slice()
+    }
+}
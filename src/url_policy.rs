@@ -0,0 +1,93 @@
+/// What happens to a `javascript:` URL written to a URL attribute
+#[derive(Clone, Copy, PartialEq)]
+pub enum UrlPolicy {
+    Allow,
+    Warn,
+    Block,
+}
+
+impl UrlPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "allow" => Some(UrlPolicy::Allow),
+            "warn" => Some(UrlPolicy::Warn),
+            "block" => Some(UrlPolicy::Block),
+            _ => None,
+        }
+    }
+
+    /// Debug builds warn, release builds leave URLs alone until told otherwise
+    pub fn default_for_build() -> Self {
+        if cfg!(debug_assertions) { UrlPolicy::Warn } else { UrlPolicy::Allow }
+    }
+}
+
+pub fn is_url_attribute(name: &str) -> bool {
+    URL_ATTRIBUTES.contains(&name.to_ascii_lowercase().as_str())
+}
+
+static URL_ATTRIBUTES: &[&str] = &[
+    "href", "src", "srcset", "action", "formaction", "poster", "xlink:href", "data", "ping", "cite", "background",
+];
+
+/// Whether any URL in the value of the URL attribute `name` would run as
+/// script. `srcset` holds comma-separated candidates, each a URL followed by
+/// an optional descriptor, and `ping` holds space-separated URLs.
+pub fn has_javascript_url(name: &str, value: &str) -> bool {
+    match name.to_ascii_lowercase().as_str() {
+        "srcset" => value
+            .split(',')
+            .filter_map(|candidate| candidate.split_ascii_whitespace().next())
+            .any(is_javascript_url),
+        "ping" => value.split_ascii_whitespace().any(is_javascript_url),
+        _ => is_javascript_url(value),
+    }
+}
+
+/// Whether the browser would run `value` as script. URL parsing drops leading
+/// and trailing spaces and control characters and every tab and newline, so
+/// `" java\tscript:..."` counts too.
+pub fn is_javascript_url(value: &str) -> bool {
+    let scheme: String = value
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .take("javascript:".len())
+        .collect();
+
+    scheme.eq_ignore_ascii_case("javascript:")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn url_attributes_ignore_case() {
+        for name in ["href", "srcSet", "ping", "cite", "background", "poster", "xlink:href"] {
+            assert!(is_url_attribute(name), "{}", name);
+        }
+        assert!(!is_url_attribute("title"));
+    }
+
+    #[test]
+    fn javascript_urls_through_whitespace() {
+        assert!(is_javascript_url("javascript:alert(1)"));
+        assert!(is_javascript_url(" \u{1}JavaScript:alert(1)"));
+        assert!(is_javascript_url("java\tscript:alert(1)"));
+        assert!(!is_javascript_url("https://example.com/javascript:"));
+    }
+
+    #[test]
+    fn srcset_checks_every_candidate() {
+        assert!(!has_javascript_url("srcset", "a.png 1x, b.png 2x"));
+        assert!(has_javascript_url("srcset", "a.png 1x, javascript:alert(1) 2x"));
+        assert!(has_javascript_url("srcset", "javascript:alert(1)"));
+    }
+
+    #[test]
+    fn ping_checks_every_url() {
+        assert!(!has_javascript_url("ping", "/track /audit"));
+        assert!(has_javascript_url("ping", "/track javascript:alert(1)"));
+    }
+}
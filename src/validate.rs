@@ -0,0 +1,137 @@
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+use super::{Element, aria, TEXT_ELEMENT, FIBER_FUNCTIONAL, SURFACE_ELEMENT, PORTAL_ELEMENT};
+
+// Elements taking a `value` or `checked` prop
+static VALUE_ELEMENTS: &[&str] = &["input", "textarea", "select"];
+
+/// Limits a tree is checked against
+pub struct Limits {
+    pub max_depth: usize,
+    pub max_nodes: usize,
+}
+
+/// Problems found in an element tree, each with the path of the element it
+/// was found on, such as `div > ul[1] > li[3]`.
+pub struct Report {
+    errors: Vec<(String, String)>,
+    nodes: usize,
+    depth: usize,
+}
+
+impl Report {
+    /// Plain JS form: `{ valid, errors: [{ path, message }], nodes, depth }`
+    pub fn to_js(&self) -> JsValue {
+        let errors = js_sys::Array::new();
+
+        for (path, message) in &self.errors {
+            let error = js_sys::Object::new();
+            js_sys::Reflect::set(&error, &"path".into(), &path.into()).unwrap();
+            js_sys::Reflect::set(&error, &"message".into(), &message.into()).unwrap();
+            errors.push(&error);
+        }
+
+        let report = js_sys::Object::new();
+        js_sys::Reflect::set(&report, &"valid".into(), &self.errors.is_empty().into()).unwrap();
+        js_sys::Reflect::set(&report, &"errors".into(), &errors).unwrap();
+        js_sys::Reflect::set(&report, &"nodes".into(), &(self.nodes as u32).into()).unwrap();
+        js_sys::Reflect::set(&report, &"depth".into(), &(self.depth as u32).into()).unwrap();
+
+        report.into()
+    }
+}
+
+/// Checks an element tree without rendering it: unknown tags, props the
+/// element type doesn't take, invalid ARIA attributes, and the depth and
+/// size limits. Function components count as one node; what they render
+/// isn't known until they are called.
+pub fn validate(document: &Document, element: &Element, limits: &Limits) -> Report {
+    let mut report = Report { errors: Vec::new(), nodes: 0, depth: 0 };
+    let mut stack = vec![(element, element.element_type().clone(), 1, false)];
+
+    while let Some((element, path, depth, in_svg)) = stack.pop() {
+        report.nodes += 1;
+        report.depth = report.depth.max(depth);
+
+        if report.nodes > limits.max_nodes {
+            report.errors.push((path, format!("Reactron: the tree has more than {} elements", limits.max_nodes)));
+            break;
+        }
+
+        if depth > limits.max_depth {
+            report.errors.push((path, format!("Reactron: the tree is more than {} elements deep", limits.max_depth)));
+            continue;
+        }
+
+        let element_type = element.element_type().as_str();
+        let in_svg = in_svg || element_type == "svg";
+
+        for message in check_element(document, element, in_svg) {
+            report.errors.push((path.clone(), message));
+        }
+
+        // Children are taken from the stack in order
+        let children = element.children().as_deref().unwrap_or_default().iter().enumerate().rev();
+
+        for (index, child) in children {
+            let child_path = format!("{} > {}[{}]", path, child.element_type(), index);
+            let child_in_svg = in_svg && element_type != "foreignObject";
+
+            stack.push((child, child_path, depth + 1, child_in_svg));
+        }
+    }
+
+    report
+}
+
+fn check_element(document: &Document, element: &Element, in_svg: bool) -> Vec<String> {
+    let element_type = element.element_type().as_str();
+    let mut messages = Vec::new();
+
+    if element_type == FIBER_FUNCTIONAL || element_type == TEXT_ELEMENT {
+        return messages;
+    }
+
+    let is_builtin = element_type == SURFACE_ELEMENT || element_type == PORTAL_ELEMENT;
+
+    if !is_builtin && !in_svg && !element_type.contains('-') && !is_known_tag(document, element_type) {
+        messages.push(format!("Reactron: unknown element type '{}'", element_type));
+    }
+
+    let props = match element.props() {
+        Some(props) => props,
+        None => return messages,
+    };
+
+    let mut unsupported = Vec::new();
+
+    if !VALUE_ELEMENTS.contains(&element_type) && props.input_value().is_some() {
+        unsupported.push("value");
+    }
+    if element_type != "input" && props.input_checked().is_some() {
+        unsupported.push("checked");
+    }
+    if element_type != SURFACE_ELEMENT && (props.draw().is_some() || props.context_type().is_some()) {
+        unsupported.push("draw");
+    }
+    if element_type != "audio" && element_type != "video" && (props.src_object().is_some() || props.playing().is_some()) {
+        unsupported.push("srcObject");
+    }
+
+    for name in unsupported {
+        messages.push(format!("Reactron: '{}' has no effect on <{}>", name, element_type));
+    }
+
+    for name in props.attributes().keys() {
+        messages.extend(aria::check_attribute(element_type, name));
+    }
+
+    messages
+}
+
+// The parser turns unknown tag names into HTMLUnknownElement
+fn is_known_tag(document: &Document, tag_name: &str) -> bool {
+    document.create_element(tag_name)
+        .is_ok_and(|node| !node.is_instance_of::<web_sys::HtmlUnknownElement>())
+}
@@ -0,0 +1,151 @@
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Document;
+use super::promise;
+
+/// The screen wake lock shared by every `use_wake_lock` of a Context. It is
+/// requested while at least one of them is mounted and requested again each
+/// time the page becomes visible, since browsers drop it when it's hidden.
+pub struct ScreenWakeLock {
+    document: Document,
+    sentinel: Option<JsValue>,
+    requesting: bool,
+    on_visibility_change: Option<js_sys::Function>,
+    users: usize,
+}
+
+impl ScreenWakeLock {
+    pub fn new(document: Document) -> Self {
+        ScreenWakeLock {
+            document,
+            sentinel: None,
+            requesting: false,
+            on_visibility_change: None,
+            users: 0,
+        }
+    }
+
+    pub fn retain(this: &Rc<RefCell<Self>>) {
+        {
+            let mut wake_lock = this.borrow_mut();
+            wake_lock.users += 1;
+
+            if wake_lock.users > 1 {
+                return;
+            }
+
+            let weak_wake_lock = Rc::downgrade(this);
+            let on_visibility_change: js_sys::Function = Closure::wrap(Box::new(move || {
+                if let Some(wake_lock) = weak_wake_lock.upgrade() {
+                    ScreenWakeLock::request(&wake_lock);
+                }
+            }) as Box<dyn FnMut()>).into_js_value().unchecked_into();
+
+            wake_lock.document.add_event_listener_with_callback("visibilitychange", &on_visibility_change).unwrap();
+            wake_lock.on_visibility_change = Some(on_visibility_change);
+        }
+
+        ScreenWakeLock::request(this);
+    }
+
+    pub fn release(&mut self) {
+        self.users = self.users.saturating_sub(1);
+
+        if self.users > 0 {
+            return;
+        }
+
+        if let Some(on_visibility_change) = self.on_visibility_change.take() {
+            let _ = self.document.remove_event_listener_with_callback("visibilitychange", &on_visibility_change);
+        }
+
+        if let Some(sentinel) = self.sentinel.take() {
+            release_sentinel(&sentinel);
+        }
+    }
+
+    // Browsers without the Screen Wake Lock API simply keep their default
+    // behaviour. A refused request (low battery, hidden page) is retried on
+    // the next visibility change.
+    fn request(this: &Rc<RefCell<Self>>) {
+        let mut wake_lock = this.borrow_mut();
+
+        if wake_lock.users == 0 || wake_lock.sentinel.is_some() || wake_lock.requesting
+            || wake_lock.document.visibility_state() != web_sys::VisibilityState::Visible {
+            return;
+        }
+
+        let navigator = web_sys::window().unwrap().navigator();
+        let api = js_sys::Reflect::get(&navigator, &"wakeLock".into()).unwrap_or(JsValue::UNDEFINED);
+        let request = match js_sys::Reflect::get(&api, &"request".into()).ok().and_then(|f| f.dyn_into::<js_sys::Function>().ok()) {
+            Some(request) => request,
+            None => return,
+        };
+
+        let promise = match request.call1(&api, &"screen".into()) {
+            Ok(promise) => promise,
+            Err(_) => return,
+        };
+        wake_lock.requesting = true;
+
+        let on_resolve = {
+            let weak_wake_lock = Rc::downgrade(this);
+
+            Closure::once_into_js(move |sentinel: JsValue| {
+                ScreenWakeLock::acquired(&weak_wake_lock, sentinel);
+            })
+        };
+
+        let on_reject = {
+            let weak_wake_lock = Rc::downgrade(this);
+
+            Closure::once_into_js(move |_error: JsValue| {
+                if let Some(wake_lock) = weak_wake_lock.upgrade() {
+                    wake_lock.borrow_mut().requesting = false;
+                }
+            })
+        };
+
+        if promise::then(&promise, &on_resolve, &on_reject).is_err() {
+            wake_lock.requesting = false;
+        }
+    }
+
+    fn acquired(this: &Weak<RefCell<Self>>, sentinel: JsValue) {
+        let wake_lock = match this.upgrade() {
+            Some(wake_lock) => wake_lock,
+            None => return release_sentinel(&sentinel),
+        };
+        let mut wake_lock = wake_lock.borrow_mut();
+        wake_lock.requesting = false;
+
+        // Every user unmounted while the request was pending
+        if wake_lock.users == 0 {
+            return release_sentinel(&sentinel);
+        }
+
+        let weak_wake_lock = this.clone();
+        let released_sentinel = sentinel.clone();
+        let on_release = Closure::once_into_js(move || {
+            if let Some(wake_lock) = weak_wake_lock.upgrade() {
+                let mut wake_lock = wake_lock.borrow_mut();
+
+                if wake_lock.sentinel.as_ref() == Some(&released_sentinel) {
+                    wake_lock.sentinel = None;
+                }
+            }
+        });
+
+        let _ = sentinel.unchecked_ref::<web_sys::EventTarget>()
+            .add_event_listener_with_callback("release", on_release.unchecked_ref());
+        wake_lock.sentinel = Some(sentinel);
+    }
+}
+
+fn release_sentinel(sentinel: &JsValue) {
+    if let Some(release) = js_sys::Reflect::get(sentinel, &"release".into()).ok().and_then(|f| f.dyn_into::<js_sys::Function>().ok()) {
+        let _ = release.call0(sentinel);
+    }
+}
@@ -0,0 +1,159 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::Worker;
+use super::WORKER_POOL_LIMIT;
+
+/// Idle module workers kept per script URL between `use_worker` jobs
+pub struct WorkerPool {
+    workers: HashMap<String, Vec<Worker>>,
+}
+
+impl WorkerPool {
+    pub fn new() -> Self {
+        WorkerPool {
+            workers: HashMap::new(),
+        }
+    }
+
+    pub fn take(&mut self, url: &str) -> Result<Worker, JsValue> {
+        if let Some(worker) = self.workers.get_mut(url).and_then(|workers| workers.pop()) {
+            return Ok(worker);
+        }
+
+        let options = web_sys::WorkerOptions::new();
+        options.set_type(web_sys::WorkerType::Module);
+
+        Worker::new_with_options(url, &options)
+    }
+
+    pub fn release(&mut self, url: &str, worker: Worker) {
+        let workers = self.workers.entry(String::from(url)).or_default();
+
+        if workers.len() < WORKER_POOL_LIMIT {
+            worker.set_onmessage(None);
+            worker.set_onerror(None);
+            workers.push(worker);
+        } else {
+            worker.terminate();
+        }
+    }
+}
+
+/// Result of the latest job of a `use_worker` hook. `value` keeps the last
+/// result while a newer job runs.
+pub struct JobState {
+    pub value: JsValue,
+    pub pending: bool,
+    worker: Option<Worker>,
+}
+
+/// A computation posted to a pooled worker. The worker script receives the
+/// input as its message and answers with a single message holding the result.
+#[derive(Clone)]
+pub struct WorkerJob {
+    url: String,
+    deps: Option<js_sys::Array>,
+    state: Rc<RefCell<JobState>>,
+}
+
+impl WorkerJob {
+    pub fn new(url: String, deps: Option<js_sys::Array>, value: JsValue) -> Self {
+        let state = JobState {
+            value,
+            pending: true,
+            worker: None,
+        };
+
+        WorkerJob {
+            url,
+            deps,
+            state: Rc::new(RefCell::new(state)),
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    pub fn deps(&self) -> Option<&js_sys::Array> {
+        self.deps.as_ref()
+    }
+
+    pub fn state(&self) -> &Rc<RefCell<JobState>> {
+        &self.state
+    }
+
+    /// Posts `input` to a worker from the pool. `on_settled` runs once the
+    /// worker answered or failed, unless the job was cancelled before.
+    pub fn start(
+        &self,
+        pool: &Rc<RefCell<WorkerPool>>,
+        input: &JsValue,
+        on_settled: impl Fn(Result<(), JsValue>) + 'static,
+    ) -> Result<(), JsValue> {
+        let worker = pool.borrow_mut().take(&self.url)?;
+        let on_settled = Rc::new(on_settled);
+
+        let on_message = {
+            let state = Rc::clone(&self.state);
+            let pool = Rc::clone(pool);
+            let url = self.url.clone();
+            let on_settled = Rc::clone(&on_settled);
+
+            Closure::wrap(Box::new(move |message: web_sys::MessageEvent| {
+                let mut job_state = state.borrow_mut();
+
+                let worker = match job_state.worker.take() {
+                    Some(worker) => worker,
+                    None => return,
+                };
+
+                job_state.value = message.data();
+                job_state.pending = false;
+                drop(job_state);
+
+                pool.borrow_mut().release(&url, worker);
+                on_settled(Ok(()));
+            }) as Box<dyn FnMut(web_sys::MessageEvent)>).into_js_value()
+        };
+
+        let on_error = {
+            let state = Rc::clone(&self.state);
+
+            Closure::wrap(Box::new(move |error: JsValue| {
+                let mut job_state = state.borrow_mut();
+
+                let worker = match job_state.worker.take() {
+                    Some(worker) => worker,
+                    None => return,
+                };
+
+                job_state.pending = false;
+                drop(job_state);
+
+                // A failed worker isn't trusted with another job
+                worker.terminate();
+                on_settled(Err(error));
+            }) as Box<dyn FnMut(JsValue)>).into_js_value()
+        };
+
+        worker.set_onmessage(Some(on_message.unchecked_ref()));
+        worker.set_onerror(Some(on_error.unchecked_ref()));
+        worker.post_message(input)?;
+
+        self.state.borrow_mut().worker = Some(worker);
+
+        Ok(())
+    }
+
+    /// Stops a job that is still running. Its worker is terminated rather than
+    /// pooled, since there is no telling when it would be free again.
+    pub fn cancel(&self) {
+        if let Some(worker) = self.state.borrow_mut().worker.take() {
+            worker.terminate();
+        }
+    }
+}